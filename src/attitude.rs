@@ -0,0 +1,281 @@
+//! Attitude of spacecraft, the orientation of their body axes and its rate of change.
+//!
+//! Orientations are unit quaternions (w, x, y, z) rotating vectors from the body axes into the
+//! reference frame, v_ref = q v_body q*. Euler angles (alpha, beta, gamma) are the 3-2-1 sequence
+//! of rotations about the z axis, the new y axis and the new x axis turning the reference axes
+//! into the body axes. Without torques acting on the body it keeps rotating at its angular
+//! velocity, given in the body axes.
+
+use frames::{Frame, LocalFrame};
+use serde::{Deserialize, Serialize};
+use std::ops;
+use time;
+use types::{Array3d, Matrix3d};
+
+// Norm of a quaternion below which it is not a rotation
+const QUATERNION_TOLERANCE: f64 = 1e-12;
+
+/// Rotation as a quaternion with the scalar part first.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    fn norm(&self) -> f64 {
+        (self.w.powi(2) + self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
+    }
+
+    /// The unit quaternion of the same rotation, none if the quaternion is not a rotation.
+    pub fn normalized(self) -> Option<Self> {
+        let norm = self.norm();
+        if !norm.is_finite() || norm < QUATERNION_TOLERANCE {
+            return None;
+        }
+
+        Some(Quaternion {
+            w: self.w / norm,
+            x: self.x / norm,
+            y: self.y / norm,
+            z: self.z / norm,
+        })
+    }
+
+    /// Rotation from the body axes into the reference frame of Euler angles in radians.
+    pub fn from_euler(alpha: f64, beta: f64, gamma: f64) -> Self {
+        Quaternion::from_matrix(
+            &(Matrix3d::rot_x(gamma) * Matrix3d::rot_y(beta) * Matrix3d::rot_z(alpha)).transpose(),
+        )
+    }
+
+    /// Euler angles in radians of the rotation from the body axes into the reference frame,
+    /// gamma is zero at the singularity of beta at +-90 degrees.
+    pub fn to_euler(self) -> (f64, f64, f64) {
+        // Rows of the rotation from the reference frame into the body axes
+        let body_from_reference = self.to_matrix().transpose().m;
+        let beta = (-body_from_reference[0][2]).clamp(-1.0, 1.0).asin();
+        if body_from_reference[0][2].abs() > 1.0 - QUATERNION_TOLERANCE {
+            let alpha = (-body_from_reference[1][0]).atan2(body_from_reference[1][1]);
+            return (alpha, beta, 0.0);
+        }
+
+        (
+            body_from_reference[0][1].atan2(body_from_reference[0][0]),
+            beta,
+            body_from_reference[1][2].atan2(body_from_reference[2][2]),
+        )
+    }
+
+    /// Quaternion of a rotation matrix, by Shepperd's method.
+    pub fn from_matrix(matrix: &Matrix3d) -> Self {
+        let m = &matrix.m;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let quaternion = if trace > m[0][0].max(m[1][1]).max(m[2][2]) {
+            let s = 2.0 * (1.0 + trace).sqrt();
+            Quaternion {
+                w: s / 4.0,
+                x: (m[2][1] - m[1][2]) / s,
+                y: (m[0][2] - m[2][0]) / s,
+                z: (m[1][0] - m[0][1]) / s,
+            }
+        } else if m[0][0] >= m[1][1] && m[0][0] >= m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Quaternion {
+                w: (m[2][1] - m[1][2]) / s,
+                x: s / 4.0,
+                y: (m[0][1] + m[1][0]) / s,
+                z: (m[0][2] + m[2][0]) / s,
+            }
+        } else if m[1][1] >= m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Quaternion {
+                w: (m[0][2] - m[2][0]) / s,
+                x: (m[0][1] + m[1][0]) / s,
+                y: s / 4.0,
+                z: (m[1][2] + m[2][1]) / s,
+            }
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Quaternion {
+                w: (m[1][0] - m[0][1]) / s,
+                x: (m[0][2] + m[2][0]) / s,
+                y: (m[1][2] + m[2][1]) / s,
+                z: s / 4.0,
+            }
+        };
+
+        // The scalar part is kept positive so that equal rotations are written out alike
+        if quaternion.w < 0.0 {
+            quaternion * -1.0
+        } else {
+            quaternion
+        }
+    }
+
+    /// Rotation matrix of a unit quaternion.
+    pub fn to_matrix(self) -> Matrix3d {
+        let Quaternion { w, x, y, z } = self;
+        Matrix3d {
+            m: [
+                [
+                    1.0 - 2.0 * (y * y + z * z),
+                    2.0 * (x * y - w * z),
+                    2.0 * (x * z + w * y),
+                ],
+                [
+                    2.0 * (x * y + w * z),
+                    1.0 - 2.0 * (x * x + z * z),
+                    2.0 * (y * z - w * x),
+                ],
+                [
+                    2.0 * (x * z - w * y),
+                    2.0 * (y * z + w * x),
+                    1.0 - 2.0 * (x * x + y * y),
+                ],
+            ],
+        }
+    }
+}
+
+impl_op_ex!(*|a: &Quaternion, b: &Quaternion| -> Quaternion {
+    Quaternion {
+        w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+        y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+        z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+    }
+});
+
+impl_op_ex!(*|a: &Quaternion, b: &f64| -> Quaternion {
+    Quaternion {
+        w: a.w * b,
+        x: a.x * b,
+        y: a.y * b,
+        z: a.z * b,
+    }
+});
+
+/// Euler angles in degrees.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct EulerAngles {
+    pub alpha: f64, // Rotation about the z axis
+    pub beta: f64,  // Rotation about the new y axis
+    pub gamma: f64, // Rotation about the new x axis
+}
+
+/// Attitude of a spacecraft as provided by the input file, relative to the inertial frame of the
+/// input or to a local orbital frame of the spacecraft. Takes either a quaternion or Euler angles.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct AttitudeInput {
+    #[serde(default)]
+    pub quaternion: Option<Quaternion>,
+    #[serde(default)]
+    pub euler_deg: Option<EulerAngles>,
+    #[serde(default)]
+    pub reference: Option<LocalFrame>, // The inertial frame of the input if none
+    #[serde(default)]
+    pub angular_velocity_dps: Option<Array3d>, // In the body axes, at rest if none
+}
+
+impl AttitudeInput {
+    /// Checks that the attitude is given by exactly one valid orientation.
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.quaternion, self.euler_deg) {
+            (Some(quaternion), None) => quaternion
+                .normalized()
+                .map(|_| ())
+                .ok_or_else(|| "The attitude quaternion is not a rotation.".to_string()),
+            (None, Some(_)) => Ok(()),
+            _ => Err("The attitude takes either a 'quaternion' or 'euler_deg'.".to_string()),
+        }
+    }
+
+    /// Converts the attitude into the simulation frame.
+    ///
+    /// ### Arguments
+    /// * 'frame' - The frame of inertial states.
+    /// * 'epoch' - The start epoch.
+    /// * 'position' - Position of the spacecraft in the simulation frame.
+    /// * 'velocity' - Velocity of the spacecraft in the simulation frame.
+    ///
+    pub fn to_simulation_frame(
+        self,
+        frame: Frame,
+        epoch: &time::Epoch,
+        position: &Array3d,
+        velocity: &Array3d,
+    ) -> Result<Attitude, String> {
+        self.validate()?;
+        let reference_from_body = match (self.quaternion, self.euler_deg) {
+            (Some(quaternion), _) => quaternion.normalized().unwrap_or(quaternion),
+            (None, Some(euler)) => Quaternion::from_euler(
+                euler.alpha.to_radians(),
+                euler.beta.to_radians(),
+                euler.gamma.to_radians(),
+            ),
+            (None, None) => unreachable!("validated attitude"),
+        };
+        let simulation_from_reference = match self.reference {
+            Some(local_frame) => local_frame
+                .local_from_inertial(position, velocity)
+                .transpose(),
+            None => frame.simulation_from_frame(epoch),
+        };
+        let angular_velocity_dps = self.angular_velocity_dps.unwrap_or(Array3d {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        });
+
+        Ok(Attitude {
+            orientation: Quaternion::from_matrix(
+                &(simulation_from_reference * reference_from_body.to_matrix()),
+            ),
+            angular_velocity: angular_velocity_dps * 1f64.to_radians(),
+        })
+    }
+}
+
+/// Attitude of a spacecraft in the simulation frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Attitude {
+    pub orientation: Quaternion, // Rotation from the body axes into the simulation frame
+    pub angular_velocity: Array3d, // In the body axes in rad/s
+}
+
+impl Attitude {
+    /// Rotates the body at its angular velocity over a step.
+    ///
+    /// ### Argument
+    /// * 'step_time_s' - Step time of the simulation in seconds.
+    ///
+    pub fn propagate(&mut self, step_time_s: f64) {
+        let rate = self.angular_velocity.dot(&self.angular_velocity).sqrt();
+        if rate == 0.0 {
+            return;
+        }
+
+        let (sin_half, cos_half) = (rate * step_time_s / 2.0).sin_cos();
+        let axis = self.angular_velocity * (sin_half / rate);
+        let rotation = Quaternion {
+            w: cos_half,
+            x: axis.x,
+            y: axis.y,
+            z: axis.z,
+        };
+        let orientation = self.orientation * rotation;
+        self.orientation = orientation.normalized().unwrap_or(orientation);
+    }
+
+    /// Orientation of the body axes relative to another frame.
+    ///
+    /// ### Argument
+    /// * 'frame_from_simulation' - Rotation from the simulation frame into the frame.
+    ///
+    pub fn orientation_in(&self, frame_from_simulation: &Matrix3d) -> Quaternion {
+        Quaternion::from_matrix(&(frame_from_simulation * self.orientation.to_matrix()))
+    }
+}
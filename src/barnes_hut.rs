@@ -0,0 +1,204 @@
+//! Barnes-Hut octree used to approximate the mutual gravity between simulation objects.
+//!
+//! Distant groups of bodies are replaced by a point mass at their center of mass whenever the
+//! ratio of the node size to the distance is below the opening angle theta, giving O(n log n)
+//! force evaluation for the whole group.
+
+use types::Array3d;
+
+// Depth after which coincident bodies are kept together in a single leaf
+const MAX_DEPTH: usize = 64;
+
+/// A point mass inserted into the tree.
+pub struct TreeBody {
+    pub id: u32,
+    pub position: Array3d,
+    pub mass: f64,
+}
+
+struct Node {
+    center: Array3d,        // Geometric center of the cube covered by the node
+    half_size: f64,         // Half of the edge length of the cube
+    mass: f64,              // Total mass within the node
+    center_of_mass: Array3d,
+    children: Vec<usize>,   // Indices of the non empty child nodes
+    bodies: Vec<usize>,     // Indices of the bodies held by a leaf
+}
+
+pub struct Octree {
+    nodes: Vec<Node>,
+    bodies: Vec<TreeBody>,
+}
+
+impl Octree {
+    /// Builds the octree enclosing all of the provided bodies.
+    ///
+    /// ### Argument
+    /// * 'bodies' - The point masses to insert.
+    ///
+    /// ### Return
+    ///     The built tree, empty if no bodies were provided.
+    ///
+    pub fn new(bodies: Vec<TreeBody>) -> Self {
+        let mut tree = Octree {
+            nodes: Vec::new(),
+            bodies,
+        };
+
+        if tree.bodies.is_empty() {
+            return tree;
+        }
+
+        // Bounding cube of all bodies
+        let mut min = tree.bodies[0].position;
+        let mut max = tree.bodies[0].position;
+        for body in tree.bodies.iter() {
+            min.x = min.x.min(body.position.x);
+            min.y = min.y.min(body.position.y);
+            min.z = min.z.min(body.position.z);
+            max.x = max.x.max(body.position.x);
+            max.y = max.y.max(body.position.y);
+            max.z = max.z.max(body.position.z);
+        }
+        let center = (min + max) * 0.5;
+        let half_size = ((max.x - min.x).max(max.y - min.y).max(max.z - min.z) / 2f64).max(1f64);
+
+        let indices = (0..tree.bodies.len()).collect();
+        tree.build(indices, center, half_size, 0);
+
+        tree
+    }
+
+    /// Recursively creates the node covering the provided bodies.
+    ///
+    /// ### Return
+    ///     The index of the created node.
+    ///
+    fn build(&mut self, indices: Vec<usize>, center: Array3d, half_size: f64, depth: usize) -> usize {
+        let mass: f64 = indices.iter().map(|idx| self.bodies[*idx].mass).sum();
+        let weighted = indices.iter().fold(
+            Array3d {
+                x: 0f64,
+                y: 0f64,
+                z: 0f64,
+            },
+            |acc, idx| acc + self.bodies[*idx].position * self.bodies[*idx].mass,
+        );
+        let center_of_mass = if mass > 0f64 { weighted * (1f64 / mass) } else { center };
+
+        let node_idx = self.nodes.len();
+        self.nodes.push(Node {
+            center,
+            half_size,
+            mass,
+            center_of_mass,
+            children: Vec::new(),
+            bodies: Vec::new(),
+        });
+
+        if indices.len() <= 1 || depth >= MAX_DEPTH {
+            self.nodes[node_idx].bodies = indices;
+            return node_idx;
+        }
+
+        // Partition the bodies into the eight octants of the node
+        let mut octants: Vec<Vec<usize>> = vec![Vec::new(); 8];
+        for idx in indices {
+            let position = &self.bodies[idx].position;
+            let octant = (position.x >= center.x) as usize
+                | ((position.y >= center.y) as usize) << 1
+                | ((position.z >= center.z) as usize) << 2;
+            octants[octant].push(idx);
+        }
+
+        let child_half = half_size / 2f64;
+        for (octant, octant_indices) in octants.into_iter().enumerate() {
+            if octant_indices.is_empty() {
+                continue;
+            }
+            let offset = |bit: usize| if octant & bit != 0 { child_half } else { -child_half };
+            let child_center = Array3d {
+                x: center.x + offset(1),
+                y: center.y + offset(2),
+                z: center.z + offset(4),
+            };
+            let child_idx = self.build(octant_indices, child_center, child_half, depth + 1);
+            self.nodes[node_idx].children.push(child_idx);
+        }
+
+        node_idx
+    }
+
+    /// Calculates the gravitational acceleration at a position due to every body in the tree.
+    ///
+    /// ### Arguments
+    /// * 'id' - ID of the body the acceleration is evaluated for, it is excluded from the sum.
+    /// * 'position' - The position to evaluate the acceleration at.
+    /// * 'theta' - Opening angle, nodes with a size to distance ratio below it are approximated.
+    /// * 'softening' - Softening length in meters avoiding singular close encounters.
+    /// * 'gravitational_constant' - The gravitational constant.
+    ///
+    /// ### Return
+    ///     The acceleration in meters per second squared.
+    ///
+    pub fn acceleration(
+        &self,
+        id: u32,
+        position: &Array3d,
+        theta: f64,
+        softening: f64,
+        gravitational_constant: f64,
+    ) -> Array3d {
+        let mut acceleration = Array3d {
+            x: 0f64,
+            y: 0f64,
+            z: 0f64,
+        };
+        if self.nodes.is_empty() {
+            return acceleration;
+        }
+
+        let point_mass = |mass: f64, source: &Array3d| -> Array3d {
+            let distance_vector = source - position;
+            let distance_sq = distance_vector.dot(&distance_vector) + softening * softening;
+            if distance_sq == 0f64 {
+                return distance_vector;
+            }
+            distance_vector * (gravitational_constant * mass / (distance_sq * distance_sq.sqrt()))
+        };
+
+        let mut stack = vec![0usize];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+
+            if node.children.is_empty() {
+                for body in node.bodies.iter().map(|idx| &self.bodies[*idx]) {
+                    if body.id != id {
+                        acceleration = acceleration + point_mass(body.mass, &body.position);
+                    }
+                }
+                continue;
+            }
+
+            let distance_vector = node.center_of_mass - position;
+            let distance = distance_vector.dot(&distance_vector).sqrt();
+            if !node.contains(position) && 2f64 * node.half_size < theta * distance {
+                acceleration = acceleration + point_mass(node.mass, &node.center_of_mass);
+            } else {
+                stack.extend(node.children.iter());
+            }
+        }
+
+        acceleration
+    }
+}
+
+impl Node {
+    /// Checks if a position lies within the cube covered by the node.
+    fn contains(&self, position: &Array3d) -> bool {
+        (position.x - self.center.x).abs() <= self.half_size
+            && (position.y - self.center.y).abs() <= self.half_size
+            && (position.z - self.center.z).abs() <= self.half_size
+    }
+}
+
@@ -1,15 +1,38 @@
+use crate::attitude::{Attitude, AttitudeInput};
+use crate::barnes_hut;
+use crate::covariance::{Covariance, CovarianceInput};
+use crate::eop;
+use crate::ephemeris;
+use crate::frames;
+use crate::maneuver::Maneuver;
 use crate::output;
+use crate::state::InitialState;
+use crate::input;
+use crate::time;
 use crate::types;
-use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono::{DateTime, Utc};
+use ephemeris::{ChebyshevFit, EphemerisTarget, JplEphemeris};
+use force_models::{self, ForceModelConfig};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::f64::consts::PI;
+use std::rc::Rc;
 use strum_macros::Display;
 use types::Array3d;
 
-const METERS_PER_ASTRONOMICAL_UNIT: f64 = 1.4959787e+11;
+pub const METERS_PER_ASTRONOMICAL_UNIT: f64 = 1.4959787e+11;
 const METERS_PER_EARTH_EQUATORIAL_RADIUS: f64 = 6378140.0;
 const EARTH_RADII_PER_ASTRONOMICAL_UNIT: f64 =
     METERS_PER_ASTRONOMICAL_UNIT / METERS_PER_EARTH_EQUATORIAL_RADIUS; // 23454.78
 const AU_METER: f64 = 1.496e+11;
+const ZERO_VECTOR: Array3d = Array3d {
+    x: 0f64,
+    y: 0f64,
+    z: 0f64,
+};
+const SOLAR_FIT_DEGREE: usize = 16; // Degree of the Chebyshev fits of solar object positions
+const SOLAR_VELOCITY_STEP_S: f64 = 60.0; // Half span of the differences of solar object positions
 
 pub type SimobjT = Box<dyn Simobj>;
 pub type PlanetBody = Box<dyn KeplerModel>;
@@ -17,46 +40,223 @@ pub type PlanetBody = Box<dyn KeplerModel>;
 #[derive(Serialize, Deserialize)]
 pub struct InitData {
     pub date: String,                // Datetime in ISO 8601 format
+    #[serde(default)]
+    pub frame: frames::Frame,        // Frame of the inertial object states, EME2000 if none
+    #[serde(default)]
+    pub central_body: CentralBody,   // Body the object coordinates are relative to
+    #[serde(default)]
+    pub forces: Option<Vec<ForceModelConfig>>, // Enabled force models, the gravity forces if none
     pub debris: Vec<Debris>,         // Debris objects
     pub spacecraft: Vec<Spacecraft>, // Spacecraft objects
+    #[serde(default)]
+    pub catalogs: Vec<input::Catalog>, // Element set catalogs and orbit messages
+}
+
+/// Solar system object at the origin of the simulation, object coordinates are relative to it.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CentralBody {
+    #[default]
+    Earth,
+    Moon,
+    Mars,
+    Sun,
 }
 
 pub trait Simobj {
     fn type_of(&self) -> String;
     fn get_id(&self) -> u32;
-    fn id_mut(&mut self) -> &mut u32;
+    /// ID given by the input or assigned once all objects are read, none until then.
+    fn id_mut(&mut self) -> &mut Option<u32>;
+    fn get_name(&self) -> Option<&str>;
     fn get_ref_coords(&self) -> &types::Array3d;
     fn set_coords(&mut self, value: Array3d);
     fn get_ref_velocity(&self) -> &types::Array3d;
     fn set_velocity(&mut self, value: Array3d);
-    fn get_drag_area(&self) -> f64;
+    /// Takes the initial state from the input, left to be converted into the simulation frame.
+    fn take_initial_state(&mut self) -> Option<InitialState>;
+    /// Epoch the object is inserted into the simulation at, present from the start if none.
+    fn get_insert_epoch(&self) -> Option<&DateTime<Utc>>;
+    /// Takes the covariance from the input, left to be converted into the simulation frame.
+    fn take_initial_covariance(&mut self) -> Option<CovarianceInput>;
+    /// State covariance in the simulation frame, none if the input gives none.
+    fn covariance_mut(&mut self) -> &mut Option<Covariance>;
+    fn get_covariance(&self) -> Option<&Covariance>;
+    /// Takes the maneuvers from the input, left to be scheduled at the start of the simulation.
+    fn take_maneuvers(&mut self) -> Vec<Maneuver>;
+    /// Average cross-sectional area in m^2, used for drag and radiation pressure.
+    fn get_cross_section(&self) -> f64;
+    /// Surface properties overriding the defaults of the force models.
+    fn get_surface(&self) -> &SurfaceProperties;
     fn get_mass(&self) -> f64;
+    fn get_gravity_group(&self) -> Option<&str>;
+    /// Converts the attitude from the input into the simulation frame, once the state is.
+    fn resolve_attitude(&mut self, _frame: frames::Frame, _epoch: &time::Epoch) -> Result<(), String> {
+        Ok(())
+    }
+    fn get_attitude(&self) -> Option<&Attitude> {
+        None
+    }
+    fn attitude_mut(&mut self) -> Option<&mut Attitude> {
+        None
+    }
+    /// Model file of the spacecraft geometry as given by the input.
+    fn get_model_file(&self) -> Option<&str> {
+        None
+    }
 
-    fn to_output_form(&self, sim_time: f64) -> output::SimulationObjectParameters {
-        let coords = self.get_ref_coords();
-        let velocity = self.get_ref_velocity();
+    fn to_output_form(
+        &self,
+        sim_time: f64,
+        utc: &str,
+        frame: &output::OutputFrame,
+    ) -> output::SimulationObjectParameters {
+        let coords = frame.rotate(self.get_ref_coords());
+        let velocity = frame.rotate(self.get_ref_velocity());
 
         output::SimulationObjectParameters {
             id: self.get_id(),
+            name: self.get_name().map(String::from),
             sim_time,
+            utc: utc.to_string(),
+            frame: frame.frame.to_string(),
             x_coord: coords.x,
             y_coord: coords.y,
             z_coord: coords.z,
             x_velocity: velocity.x,
             y_velocity: velocity.y,
-            z_velocity: velocity.z
+            z_velocity: velocity.z,
+            latitude_deg: None,
+            longitude_deg: None,
+            altitude_m: None,
+            local_solar_time_h: None,
         }
     }
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(try_from = "SpacecraftInput")]
 pub struct Spacecraft {
-    #[serde(skip_deserializing)]
-    id: u32,
+    id: Option<u32>, // ID given by the input, such as a NORAD catalog number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
     coords: Array3d,
     velocity: Array3d,
+    #[serde(skip)]
+    initial_state: Option<InitialState>,
+    #[serde(skip)]
+    insert_epoch: Option<DateTime<Utc>>, // Launch or deployment during the run
+    #[serde(skip)]
+    initial_covariance: Option<CovarianceInput>,
+    #[serde(skip)]
+    covariance: Option<Covariance>,
+    #[serde(skip)]
+    maneuvers: Vec<Maneuver>, // Impulsive maneuvers left to be scheduled
+    drag_area: f64,
+    mass: f64,
+    #[serde(flatten)]
+    surface: SurfaceProperties,
+    gravity_group: Option<String>, // Objects sharing a group attract each other
+    #[serde(skip)]
+    initial_attitude: Option<AttitudeInput>,
+    #[serde(skip)]
+    attitude: Option<Attitude>, // Orientation of the body axes, not tracked if none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model_file: Option<String>, // Geometry of the spacecraft for attitude dependent analyses
+}
+
+/// Spacecraft as provided by the input file.
+#[derive(Deserialize)]
+struct SpacecraftInput {
+    #[serde(default)]
+    id: Option<u32>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    coords: Option<Array3d>,
+    #[serde(default)]
+    velocity: Option<Array3d>,
+    #[serde(default)]
+    state: Option<InitialState>,
+    #[serde(default)]
+    insert_epoch: Option<String>, // Datetime in ISO 8601 format
+    #[serde(default)]
+    covariance: Option<CovarianceInput>,
     drag_area: f64,
     mass: f64,
+    #[serde(flatten)]
+    surface: SurfaceProperties,
+    #[serde(default)]
+    gravity_group: Option<String>,
+    #[serde(default)]
+    maneuvers: Vec<Maneuver>,
+    #[serde(default)]
+    attitude: Option<AttitudeInput>,
+    #[serde(default)]
+    model_file: Option<String>,
+}
+
+impl TryFrom<SpacecraftInput> for Spacecraft {
+    type Error = String;
+
+    fn try_from(input: SpacecraftInput) -> Result<Self, Self::Error> {
+        input.surface.validate()?;
+        if let Some(covariance) = &input.covariance {
+            covariance.validate()?;
+        }
+        if let Some(attitude) = &input.attitude {
+            attitude.validate()?;
+        }
+
+        Ok(Spacecraft {
+            id: input.id,
+            name: input.name,
+            coords: ZERO_VECTOR,
+            velocity: ZERO_VECTOR,
+            initial_state: Some(InitialState::from_fields(input.coords, input.velocity, input.state)?),
+            insert_epoch: parse_insert_epoch(input.insert_epoch)?,
+            initial_covariance: input.covariance,
+            covariance: None,
+            maneuvers: input.maneuvers,
+            drag_area: input.drag_area,
+            mass: input.mass,
+            surface: input.surface,
+            gravity_group: input.gravity_group,
+            initial_attitude: input.attitude,
+            attitude: None,
+            model_file: input.model_file,
+        })
+    }
+}
+
+impl Spacecraft {
+    /// Spacecraft starting from a given state.
+    pub fn new(
+        name: Option<String>,
+        state: InitialState,
+        drag_area: f64,
+        mass: f64,
+        surface: SurfaceProperties,
+        gravity_group: Option<String>,
+        maneuvers: Vec<Maneuver>,
+    ) -> Result<Self, String> {
+        Spacecraft::try_from(SpacecraftInput {
+            id: None,
+            name,
+            coords: None,
+            velocity: None,
+            state: Some(state),
+            insert_epoch: None,
+            covariance: None,
+            drag_area,
+            mass,
+            surface,
+            gravity_group,
+            maneuvers,
+            attitude: None,
+            model_file: None,
+        })
+    }
 }
 
 impl Simobj for Spacecraft {
@@ -65,13 +265,17 @@ impl Simobj for Spacecraft {
     }
 
     fn get_id(&self) -> u32 {
-        self.id
+        self.id.unwrap_or_default()
     }
 
-    fn id_mut(&mut self) -> &mut u32 {
+    fn id_mut(&mut self) -> &mut Option<u32> {
         &mut self.id
     }
 
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
 
     fn get_ref_coords(&self) -> &Array3d {
         &self.coords
@@ -89,24 +293,328 @@ impl Simobj for Spacecraft {
         self.velocity = value;
     }
 
-    fn get_drag_area(&self) -> f64 {
+    fn take_initial_state(&mut self) -> Option<InitialState> {
+        self.initial_state.take()
+    }
+
+    fn get_insert_epoch(&self) -> Option<&DateTime<Utc>> {
+        self.insert_epoch.as_ref()
+    }
+
+    fn take_initial_covariance(&mut self) -> Option<CovarianceInput> {
+        self.initial_covariance.take()
+    }
+
+    fn covariance_mut(&mut self) -> &mut Option<Covariance> {
+        &mut self.covariance
+    }
+
+    fn get_covariance(&self) -> Option<&Covariance> {
+        self.covariance.as_ref()
+    }
+
+    fn take_maneuvers(&mut self) -> Vec<Maneuver> {
+        std::mem::take(&mut self.maneuvers)
+    }
+
+    fn get_cross_section(&self) -> f64 {
         self.drag_area
     }
 
+    fn get_surface(&self) -> &SurfaceProperties {
+        &self.surface
+    }
+
     fn get_mass(&self) -> f64 {
         self.mass
     }
+
+    fn get_gravity_group(&self) -> Option<&str> {
+        self.gravity_group.as_deref()
+    }
+
+    fn resolve_attitude(&mut self, frame: frames::Frame, epoch: &time::Epoch) -> Result<(), String> {
+        if let Some(attitude) = self.initial_attitude.take() {
+            self.attitude =
+                Some(attitude.to_simulation_frame(frame, epoch, &self.coords, &self.velocity)?);
+        }
+        Ok(())
+    }
+
+    fn get_attitude(&self) -> Option<&Attitude> {
+        self.attitude.as_ref()
+    }
+
+    fn attitude_mut(&mut self) -> Option<&mut Attitude> {
+        self.attitude.as_mut()
+    }
+
+    fn get_model_file(&self) -> Option<&str> {
+        self.model_file.as_deref()
+    }
+}
+
+/// Shape of a debris object.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Shape {
+    Cube,     // Rectangular cuboid with edges W, L and H
+    Spheroid, // Ellipsoid with diameters W, L and H
+}
+
+/// Outer dimensions of an object in meters.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Dimensions {
+    pub width: f64,
+    pub length: f64,
+    pub height: f64,
+}
+
+/// Material a debris object is made of.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Material {
+    #[serde(alias = "aluminium")]
+    Aluminum,
+    #[serde(alias = "aluminum_oxide")]
+    AluminiumOxide,
+    Titanium,
+    Steel,
+    Copper,
+    Cfrp, // Carbon fibre reinforced polymer
+}
+
+impl Material {
+    /// Density of the material in kg/m^3.
+    pub fn density(&self) -> f64 {
+        match self {
+            Material::Aluminum => 2700.0,
+            Material::AluminiumOxide => 3950.0,
+            Material::Titanium => 4506.0,
+            Material::Steel => 7850.0,
+            Material::Copper => 8960.0,
+            Material::Cfrp => 1600.0,
+        }
+    }
+}
+
+impl Shape {
+    /// Volume of the shape in m^3.
+    pub fn volume(&self, dims: &Dimensions) -> f64 {
+        match self {
+            Shape::Cube => dims.width * dims.length * dims.height,
+            Shape::Spheroid => PI / 6f64 * dims.width * dims.length * dims.height,
+        }
+    }
+
+    /// Cross-sectional area of the shape averaged over all orientations in m^2. For convex
+    /// bodies this is a quarter of the surface area (Cauchy's formula), the surface area of the
+    /// spheroid is approximated with Knud Thomsen's formula.
+    pub fn average_cross_section(&self, dims: &Dimensions) -> f64 {
+        match self {
+            Shape::Cube => {
+                (dims.width * dims.length + dims.width * dims.height + dims.length * dims.height)
+                    / 2f64
+            }
+            Shape::Spheroid => {
+                const P: f64 = 1.6075;
+                let (a, b, c) = (dims.width / 2f64, dims.length / 2f64, dims.height / 2f64);
+                PI * (((a * b).powf(P) + (a * c).powf(P) + (b * c).powf(P)) / 3f64).powf(1f64 / P)
+            }
+        }
+    }
+}
+
+/// Surface properties of an object for the drag and radiation pressure forces, the force models
+/// apply their own defaults to the properties left out.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct SurfaceProperties {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drag_coefficient: Option<f64>, // Drag coefficient, the 'cd' of the drag force if none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srp_area: Option<f64>, // Area facing the Sun in m^2, the drag area if none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reflectivity: Option<f64>, // Reflectivity coefficient, the 'cr' of the srp force if none
+}
+
+impl SurfaceProperties {
+    /// The properties, completed with the ones of another set where left out.
+    pub fn or(self, other: SurfaceProperties) -> Self {
+        SurfaceProperties {
+            drag_coefficient: self.drag_coefficient.or(other.drag_coefficient),
+            srp_area: self.srp_area.or(other.srp_area),
+            reflectivity: self.reflectivity.or(other.reflectivity),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let properties = [self.drag_coefficient, self.srp_area, self.reflectivity];
+        if properties.iter().flatten().any(|value| *value <= 0f64) {
+            return Err(
+                "Drag coefficients, radiation pressure areas and reflectivities must be positive."
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the insertion epoch of an object.
+fn parse_insert_epoch(insert_epoch: Option<String>) -> Result<Option<DateTime<Utc>>, String> {
+    insert_epoch
+        .map(|datetime| {
+            datetime.parse::<DateTime<Utc>>().map_err(|_| {
+                format!("Invalid insert epoch '{}', expected ISO 8601 format.", datetime)
+            })
+        })
+        .transpose()
 }
 
 /// Struct for holding attributes relating to debris
 #[derive(Serialize, Deserialize)]
+#[serde(try_from = "DebrisInput")]
 pub struct Debris {
-    #[serde(skip_deserializing)]
-    id: u32,
+    id: Option<u32>, // ID given by the input, such as a NORAD catalog number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
     coords: Array3d,
     velocity: Array3d,
-    drag_area: f64,
-    mass: f64,
+    #[serde(skip)]
+    initial_state: Option<InitialState>,
+    #[serde(skip)]
+    insert_epoch: Option<DateTime<Utc>>, // Launch or deployment during the run
+    #[serde(skip)]
+    initial_covariance: Option<CovarianceInput>,
+    #[serde(skip)]
+    covariance: Option<Covariance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shape: Option<Shape>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<Dimensions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    material: Option<Material>,
+    drag_area: f64, // Average cross-section in m^2
+    mass: f64,      // kg
+    #[serde(flatten)]
+    surface: SurfaceProperties,
+    #[serde(skip)]
+    gravity_group: Option<String>, // Objects sharing a group attract each other
+}
+
+/// Debris as provided by the input file, the mass and cross-section may be left out when they
+/// can be derived from the shape, dimensions and material.
+#[derive(Deserialize)]
+struct DebrisInput {
+    #[serde(default)]
+    id: Option<u32>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    coords: Option<Array3d>,
+    #[serde(default)]
+    velocity: Option<Array3d>,
+    #[serde(default)]
+    state: Option<InitialState>,
+    #[serde(default)]
+    insert_epoch: Option<String>, // Datetime in ISO 8601 format
+    #[serde(default)]
+    covariance: Option<CovarianceInput>,
+    #[serde(default)]
+    shape: Option<Shape>,
+    #[serde(default)]
+    dimensions: Option<Dimensions>,
+    #[serde(default)]
+    material: Option<Material>,
+    #[serde(default)]
+    drag_area: Option<f64>,
+    #[serde(default)]
+    mass: Option<f64>,
+    #[serde(flatten)]
+    surface: SurfaceProperties,
+    #[serde(default)]
+    gravity_group: Option<String>,
+}
+
+impl TryFrom<DebrisInput> for Debris {
+    type Error = String;
+
+    fn try_from(input: DebrisInput) -> Result<Self, Self::Error> {
+        if let Some(dims) = &input.dimensions {
+            if !(dims.width > 0f64 && dims.length > 0f64 && dims.height > 0f64) {
+                return Err("Debris dimensions must be positive.".to_string());
+            }
+        }
+        let shape_dims = input.shape.zip(input.dimensions);
+
+        let drag_area = match (input.drag_area, shape_dims) {
+            (Some(area), _) => area,
+            (None, Some((shape, dims))) => shape.average_cross_section(&dims),
+            (None, None) => {
+                return Err("Debris requires 'drag_area' or 'shape' and 'dimensions'.".to_string())
+            }
+        };
+        let mass = match (input.mass, shape_dims, input.material) {
+            (Some(mass), _, _) => mass,
+            (None, Some((shape, dims)), Some(material)) => material.density() * shape.volume(&dims),
+            _ => {
+                return Err(
+                    "Debris requires 'mass' or 'shape', 'dimensions' and 'material'.".to_string(),
+                )
+            }
+        };
+        input.surface.validate()?;
+        if let Some(covariance) = &input.covariance {
+            covariance.validate()?;
+        }
+
+        Ok(Debris {
+            id: input.id,
+            name: input.name,
+            coords: ZERO_VECTOR,
+            velocity: ZERO_VECTOR,
+            initial_state: Some(InitialState::from_fields(input.coords, input.velocity, input.state)?),
+            insert_epoch: parse_insert_epoch(input.insert_epoch)?,
+            initial_covariance: input.covariance,
+            covariance: None,
+            shape: input.shape,
+            dimensions: input.dimensions,
+            material: input.material,
+            drag_area,
+            mass,
+            surface: input.surface,
+            gravity_group: input.gravity_group,
+        })
+    }
+}
+
+impl Debris {
+    /// Debris of known cross-section and mass starting from a given state.
+    pub fn new(
+        name: Option<String>,
+        state: InitialState,
+        drag_area: f64,
+        mass: f64,
+        surface: SurfaceProperties,
+        gravity_group: Option<String>,
+    ) -> Result<Self, String> {
+        Debris::try_from(DebrisInput {
+            id: None,
+            name,
+            coords: None,
+            velocity: None,
+            state: Some(state),
+            insert_epoch: None,
+            covariance: None,
+            shape: None,
+            dimensions: None,
+            material: None,
+            drag_area: Some(drag_area),
+            mass: Some(mass),
+            surface,
+            gravity_group,
+        })
+    }
 }
 
 impl Simobj for Debris {
@@ -115,13 +623,17 @@ impl Simobj for Debris {
     }
 
     fn get_id(&self) -> u32 {
-        self.id
+        self.id.unwrap_or_default()
     }
 
-    fn id_mut(&mut self) -> &mut u32 {
+    fn id_mut(&mut self) -> &mut Option<u32> {
         &mut self.id
     }
 
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
 
     fn get_ref_coords(&self) -> &Array3d {
         &self.coords
@@ -139,145 +651,373 @@ impl Simobj for Debris {
         self.velocity = value;
     }
 
-    fn get_drag_area(&self) -> f64 {
+    fn take_initial_state(&mut self) -> Option<InitialState> {
+        self.initial_state.take()
+    }
+
+    fn get_insert_epoch(&self) -> Option<&DateTime<Utc>> {
+        self.insert_epoch.as_ref()
+    }
+
+    fn take_initial_covariance(&mut self) -> Option<CovarianceInput> {
+        self.initial_covariance.take()
+    }
+
+    fn covariance_mut(&mut self) -> &mut Option<Covariance> {
+        &mut self.covariance
+    }
+
+    fn get_covariance(&self) -> Option<&Covariance> {
+        self.covariance.as_ref()
+    }
+
+    fn take_maneuvers(&mut self) -> Vec<Maneuver> {
+        Vec::new()
+    }
+
+    fn get_cross_section(&self) -> f64 {
         self.drag_area
     }
 
+    fn get_surface(&self) -> &SurfaceProperties {
+        &self.surface
+    }
+
     fn get_mass(&self) -> f64 {
         self.mass
     }
+
+    fn get_gravity_group(&self) -> Option<&str> {
+        self.gravity_group.as_deref()
+    }
 }
 
 pub struct Environment {
     pub day: f64, // Current day of bodies
     pub last_day_update_s: f64,
     pub sim_time_s: f64, // Simulation time in seconds
-    pub start_time: chrono::DateTime<Utc>,
+    pub start_epoch: time::Epoch, // Epoch at the start of the simulation
+    solar_step_s: f64, // Span of the solar object fits built at each update in seconds
     bodies: Vec<PlanetBody>, // 0th index is always the centric
+    fits: Vec<ChebyshevFit>, // Position fits of the bodies, in the same order as bodies
+    gravity_groups: HashMap<String, barnes_hut::Octree>, // Trees of mutually attracting objects
+    earth_orientation: frames::EarthOrientation, // Orientation of the Earth at the last update
+    eop: Option<eop::EopTable>, // Earth orientation parameters, zero corrections if none
 }
 
 impl Environment {
-    /// Updates the solar system objects within the environment.
-    ///
-    /// ### Argument
-    /// * 'up_day' - New datetime of simulation.
-    ///
-    fn update_solar_objs(&mut self, up_day: &DateTime<chrono::Utc>) {
-        let new_day = Self::datetime_to_days(up_day);
+    /// Fits the positions of the solar system objects over the next solar step so that they can
+    /// be evaluated at any time before the following update.
+    fn fit_solar_objs(&mut self) -> Result<(), String> {
+        let start_day = self.sim_time_to_days(self.sim_time_s);
+        let end_day = self.sim_time_to_days(self.sim_time_s + self.solar_step_s);
+
+        self.fits = self
+            .bodies
+            .iter()
+            .map(|planet| {
+                ChebyshevFit::new(start_day, end_day, SOLAR_FIT_DEGREE, |day| -> Result<_, String> {
+                    let coords = planet.ecliptic_cartesian_coords(day)?;
+                    // TDB days are taken as TT, within 2 ms, for the rotation of frames of date
+                    let epoch = time::Epoch::from_jd_tt(ephemeris::JD_DAY_ORIGIN + day);
+                    let position = planet.frame().simulation_from_frame(&epoch)
+                        * Array3d {
+                            x: coords.xh,
+                            y: coords.yh,
+                            z: coords.zh,
+                        };
+                    Ok([position.x, position.y, position.z])
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(())
+    }
 
-        for planet in self.bodies.iter_mut() {
-            *planet.mut_coords() = planet.ecliptic_cartesian_coords(new_day);
+    /// Moves the solar system objects to their positions at the current simulation time using
+    /// the fits from the last update.
+    pub fn interpolate_solar_objs(&mut self) {
+        let day = self.sim_time_to_days(self.sim_time_s);
+
+        for (planet, fit) in self.bodies.iter_mut().zip(self.fits.iter()) {
+            let [xh, yh, zh] = fit.evaluate(day);
+            let coords = planet.mut_coords();
+            coords.xh = xh;
+            coords.yh = yh;
+            coords.zh = zh;
         }
 
-        self.day = new_day;
+        self.day = day;
     }
 
-    /// Calculates the distance in X, Y, Z form from a simulation object to the solar body
-    /// specified the the provided index. For reference, the solar body at index 0 is always the
-    /// centric object.
+    /// Calculates the position of a solar body relative to the center of the Sun.
     ///
-    /// ### Arguments:
-    /// * 'sim_obj' - The simulation object
+    /// ### Arguments
     /// * 'solar_obj_index' - The index of the solar body
+    /// * 'sim_time_s' - Simulation time within the span of the current fits in seconds
     ///
     /// ### Return
-    ///     A ndarray containing the distance between the simulation object and the solar body in
-    ///     Cartesian Distance: (X, Y, Z)
+    ///     The heliocentric position of the body or none if the index is out of range.
     ///
-    pub fn distance_to(
-        &self,
-        sim_obj: &dyn Simobj,
-        solar_obj_index: usize,
-    ) -> Option<Array3d> {
-        let current_solar_obj = match self.bodies.get(solar_obj_index) {
-            Some(obj) => obj,
-            None => return None,
-        };
+    fn heliocentric_coords(&self, solar_obj_index: usize, sim_time_s: f64) -> Option<Array3d> {
+        let [x, y, z] = self
+            .fits
+            .get(solar_obj_index)?
+            .evaluate(self.sim_time_to_days(sim_time_s));
+        let position = Array3d { x, y, z };
+
+        if self.bodies.get(solar_obj_index)?.get_coords().heliocentric {
+            return Some(position);
+        }
+
+        // Bodies that are not heliocentric are positioned relative to the Earth
+        let earth_idx = self
+            .bodies
+            .iter()
+            .position(|body| matches!(body.get_solar_object(), Solarobj::Earth { attr: _ }))?;
 
-        let solar_obj_coords = current_solar_obj.get_coords();
-        let sim_coords = sim_obj.get_ref_coords();
+        Some(position + self.heliocentric_coords(earth_idx, sim_time_s)?)
+    }
 
-        // If the object is the centric or not helio centric
+    /// Calculates the position of a solar body relative to the central body of the simulation,
+    /// the frame all simulation object coordinates are expressed in. For reference, the solar
+    /// body at index 0 is always the centric object.
+    ///
+    /// ### Arguments
+    /// * 'solar_obj_index' - The index of the solar body
+    /// * 'sim_time_s' - Simulation time within the span of the current fits in seconds
+    ///
+    /// ### Return
+    ///     The position of the solar body relative to the central body or none if the index is
+    ///     out of range.
+    ///
+    pub fn position_from_central(&self, solar_obj_index: usize, sim_time_s: f64) -> Option<Array3d> {
         if solar_obj_index == 0 {
-            return Some(sim_coords.clone());
+            return self.bodies.first().map(|_| Array3d {
+                x: 0f64,
+                y: 0f64,
+                z: 0f64,
+            });
         }
 
-        let dist_array = if !current_solar_obj.get_coords().heliocentric {
-            Array3d{
-                x: solar_obj_coords.xh - sim_coords.x,
-                y: solar_obj_coords.yh  - sim_coords.y,
-                z: solar_obj_coords.zh - sim_coords.z,
-            }
-        } else {
-            let centric_solar_obj = match self.bodies.get(0) {
-                Some(obj) => obj,
-                None => return None,
-            };
-
-            let centric_obj_coords = centric_solar_obj.get_coords();
-
-            Array3d{
-                x: solar_obj_coords.xh - (centric_obj_coords.xh + sim_coords.x),
-                y: solar_obj_coords.yh - (centric_obj_coords.yh + sim_coords.y),
-                z: solar_obj_coords.zh - (centric_obj_coords.zh + sim_coords.z)
+        Some(
+            self.heliocentric_coords(solar_obj_index, sim_time_s)?
+                - self.heliocentric_coords(0, sim_time_s)?,
+        )
+    }
+
+    /// Calculates the velocity of a solar body relative to the central body from the central
+    /// difference of its fitted positions.
+    ///
+    /// ### Arguments
+    /// * 'solar_obj_index' - The index of the solar body
+    /// * 'sim_time_s' - Simulation time within the span of the current fits in seconds
+    ///
+    /// ### Return
+    ///     The velocity of the solar body relative to the central body in m/s or none if the
+    ///     index is out of range.
+    ///
+    pub fn velocity_from_central(&self, solar_obj_index: usize, sim_time_s: f64) -> Option<Array3d> {
+        let step_s = SOLAR_VELOCITY_STEP_S;
+        let after = self.position_from_central(solar_obj_index, sim_time_s + step_s)?;
+        let before = self.position_from_central(solar_obj_index, sim_time_s - step_s)?;
+
+        Some((after - before) * (0.5 / step_s))
+    }
+
+    /// Finds the index of a body among the solar system objects, none if it is not simulated.
+    pub fn solar_object_index(&self, body: CentralBody) -> Option<usize> {
+        self.bodies.iter().position(|planet| {
+            matches!(
+                (planet.get_solar_object(), body),
+                (Solarobj::Earth { attr: _ }, CentralBody::Earth)
+                    | (Solarobj::Moon { attr: _ }, CentralBody::Moon)
+                    | (Solarobj::Mars { attr: _ }, CentralBody::Mars)
+                    | (Solarobj::Sun { attr: _ }, CentralBody::Sun)
+            )
+        })
+    }
+
+    /// Rebuilds the Barnes-Hut trees of every gravity group from the current object positions.
+    /// Objects without a gravity group are treated as test particles and left out.
+    ///
+    /// ### Argument
+    /// * 'sim_objects' - All simulation objects.
+    ///
+    pub fn update_mutual_gravity(&mut self, sim_objects: &[SimobjT]) {
+        let mut groups: HashMap<String, Vec<barnes_hut::TreeBody>> = HashMap::new();
+        for sim_obj in sim_objects {
+            if let Some(group) = sim_obj.get_gravity_group() {
+                groups
+                    .entry(group.to_string())
+                    .or_default()
+                    .push(barnes_hut::TreeBody {
+                        id: sim_obj.get_id(),
+                        position: *sim_obj.get_ref_coords(),
+                        mass: sim_obj.get_mass(),
+                    });
             }
-        };
+        }
+
+        self.gravity_groups = groups
+            .into_iter()
+            .map(|(group, tree_bodies)| (group, barnes_hut::Octree::new(tree_bodies)))
+            .collect();
+    }
+
+    /// Gets the Barnes-Hut tree of a gravity group.
+    pub fn get_gravity_group(&self, group: &str) -> Option<&barnes_hut::Octree> {
+        self.gravity_groups.get(group)
+    }
+
+    /// Earth orientation parameters at an epoch, zero when no parameters were loaded.
+    pub fn eop_corrections(&self, epoch: &time::Epoch) -> frames::EopCorrections {
+        match &self.eop {
+            Some(eop) => eop.corrections(epoch),
+            None => frames::EopCorrections::default(),
+        }
+    }
+
+    /// Gravitational parameter of the central body in m^3/s^2.
+    pub fn central_gravitational_parameter(&self) -> f64 {
+        self.bodies
+            .first()
+            .map_or(0f64, |body| force_models::G * body.get_solar_object().get_mass_kg())
+    }
 
-        Some(dist_array)
+    /// Checks if the Earth is the central body of the simulation.
+    pub fn central_is_earth(&self) -> bool {
+        matches!(
+            self.bodies.first().map(|body| body.get_solar_object()),
+            Some(Solarobj::Earth { attr: _ })
+        )
     }
 
-    pub fn update(&mut self) {
-        let new_time = self.start_time + Duration::seconds(self.sim_time_s as i64);
+    /// Calculates the orientation of the Earth at a simulation time.
+    pub fn earth_orientation_at(&self, sim_time_s: f64) -> frames::EarthOrientation {
+        let epoch = self.epoch(sim_time_s);
+        frames::EarthOrientation::new(&epoch, &self.eop_corrections(&epoch))
+    }
 
-        self.update_solar_objs(&new_time);
+    /// Gets the orientation of the Earth at the last update.
+    pub fn get_earth_orientation(&self) -> &frames::EarthOrientation {
+        &self.earth_orientation
+    }
+
+    /// Refits the solar system objects from their models at the current simulation time, failing
+    /// if a model does not cover the span of the fit.
+    pub fn update(&mut self) -> Result<(), String> {
+        self.fit_solar_objs()?;
+        self.interpolate_solar_objs();
+        self.earth_orientation = self.earth_orientation_at(self.sim_time_s);
 
         self.last_day_update_s = self.sim_time_s;
+
+        Ok(())
     }
 
-    /// Calculates a delta for provided datetime from 0/Jan/2000 00:00 UTC
+    /// The epoch of a simulation time.
     ///
     /// ### Argument
-    /// * 'datetime' - User provided datetime object.
+    /// * 'sim_time_s' - Simulation time in seconds, elapsed SI seconds since the start.
+    ///
+    pub fn epoch(&self, sim_time_s: f64) -> time::Epoch {
+        self.start_epoch.offset(sim_time_s)
+    }
+
+    /// Converts a simulation time into the day value used by the solar system object models.
+    fn sim_time_to_days(&self, sim_time_s: f64) -> f64 {
+        Self::epoch_to_days(&self.epoch(sim_time_s))
+    }
+
+    /// Calculates the day value of an epoch, the TDB days since 0/Jan/2000 00:00.
+    ///
+    /// ### Argument
+    /// * 'epoch' - The epoch to convert.
     ///
     /// ### Return
-    ///     The delta from 0/Jan/2000 00:00 UTC in days.
+    ///     The delta from 0/Jan/2000 00:00 TDB in days.
     ///
-    fn datetime_to_days(datetime_obj: &DateTime<chrono::Utc>) -> f64 {
-        let origin_dt = chrono::Utc.ymd(2000, 1, 1).and_hms(0, 0, 0);
-
-        (1.15741e-5f64 * (*datetime_obj - origin_dt).num_seconds() as f64) as f64
+    fn epoch_to_days(epoch: &time::Epoch) -> f64 {
+        epoch.jd_tdb() - ephemeris::JD_DAY_ORIGIN
     }
 
     pub fn get_solar_objects(&self) -> &Vec<PlanetBody> {
         self.bodies.as_ref()
     }
 
-    /// Creates the initial vector of solar system objects.
-    /// 0 - Sun, 1 - Earth, 2 - Moon
+    /// Creates the initial vector of solar system objects. The central body is always at index
+    /// 0, followed by the remaining bodies out of Earth, Sun and Moon.
     ///
-    /// ### Argument
-    /// * 'day' - The day value greater than zero. From 2000-01-01
+    /// ### Arguments
+    /// * 'start_time' - The datetime at the start of the simulation.
+    /// * 'central_body' - The body at the origin of the simulation.
+    /// * 'ephemeris' - JPL development ephemeris backing the solar system objects. When none is
+    ///   provided the low precision analytic models are used.
+    /// * 'eop' - Earth orientation parameters used for the orientation of the Earth.
+    /// * 'solar_step_s' - Interval between refits of the solar system objects in seconds.
     ///
     /// ### Return
     ///     new Environment loaded with all possible solar system objects tracked by the
-    ///     simulation.
+    ///     simulation or an error if the models do not cover the start of the simulation.
     ///
-    pub fn new(start_time: DateTime<Utc>) -> Environment {
-        let day = Environment::datetime_to_days(&start_time);
-
-        let mut solar_bodies: Vec<PlanetBody> = Vec::new();
+    pub fn new(
+        start_time: DateTime<Utc>,
+        central_body: CentralBody,
+        ephemeris: Option<Rc<JplEphemeris>>,
+        eop: Option<eop::EopTable>,
+        solar_step_s: f64,
+    ) -> Result<Environment, String> {
+        let start_epoch = time::Epoch::from_utc(&start_time);
+        let day = Environment::epoch_to_days(&start_epoch);
+
+        let make_body = |body: CentralBody| -> Result<PlanetBody, String> {
+            Ok(match (&ephemeris, body) {
+                (Some(ephemeris), CentralBody::Earth) => {
+                    Box::new(make_ephemeris_body(earth_solar_object(), ephemeris, day)?)
+                }
+                (Some(ephemeris), CentralBody::Moon) => {
+                    Box::new(make_ephemeris_body(moon_solar_object(), ephemeris, day)?)
+                }
+                (Some(ephemeris), CentralBody::Mars) => {
+                    Box::new(make_ephemeris_body(mars_solar_object(), ephemeris, day)?)
+                }
+                (Some(ephemeris), CentralBody::Sun) => {
+                    Box::new(make_ephemeris_body(sun_solar_object(), ephemeris, day)?)
+                }
+                (None, CentralBody::Earth) => Box::new(make_earth(day)?),
+                (None, CentralBody::Moon) => Box::new(make_moon(day)?),
+                (None, CentralBody::Mars) => Box::new(make_mars(day)?),
+                (None, CentralBody::Sun) => Box::new(make_sun()),
+            })
+        };
 
-        solar_bodies.push(Box::new(make_earth(day)));
-        solar_bodies.push(Box::new(make_sun()));
-        solar_bodies.push(Box::new(make_moon(day)));
+        // Mars is only tracked when it is the central body
+        let mut solar_bodies: Vec<PlanetBody> = vec![make_body(central_body)?];
+        for body in [CentralBody::Earth, CentralBody::Sun, CentralBody::Moon].iter() {
+            if *body != central_body {
+                solar_bodies.push(make_body(*body)?);
+            }
+        }
 
-        Environment {
+        let mut env = Environment {
             day,
             last_day_update_s: 0.0,
-            start_time,
+            start_epoch,
             sim_time_s: 0f64,
+            solar_step_s,
             bodies: solar_bodies,
-        }
+            fits: Vec::new(),
+            gravity_groups: HashMap::new(),
+            earth_orientation: frames::EarthOrientation::new(
+                &start_epoch,
+                &frames::EopCorrections::default(),
+            ),
+            eop,
+        };
+        env.update()?;
+
+        Ok(env)
     }
 }
 
@@ -289,12 +1029,15 @@ pub enum Solarobj {
     Earth { attr: SolarAttr },
     #[strum(serialize = "moon")]
     Moon { attr: SolarAttr },
+    #[strum(serialize = "mars")]
+    Mars { attr: SolarAttr },
 }
 
 #[derive(Clone)]
 pub struct SolarAttr {
     radius: f64, // meters
     mass: f64,   // kg
+    j2: f64,     // Second zonal harmonic of the gravity field, relative to the radius
 }
 
 impl Solarobj {
@@ -303,6 +1046,25 @@ impl Solarobj {
             Solarobj::Sun { attr } => attr.mass,
             Solarobj::Earth { attr } => attr.mass,
             Solarobj::Moon { attr } => attr.mass,
+            Solarobj::Mars { attr } => attr.mass,
+        }
+    }
+
+    pub fn get_j2(&self) -> f64 {
+        match self {
+            Solarobj::Sun { attr } => attr.j2,
+            Solarobj::Earth { attr } => attr.j2,
+            Solarobj::Moon { attr } => attr.j2,
+            Solarobj::Mars { attr } => attr.j2,
+        }
+    }
+
+    pub fn get_radius_m(&self) -> f64 {
+        match self {
+            Solarobj::Sun { attr } => attr.radius,
+            Solarobj::Earth { attr } => attr.radius,
+            Solarobj::Moon { attr } => attr.radius,
+            Solarobj::Mars { attr } => attr.radius,
         }
     }
 }
@@ -323,9 +1085,13 @@ pub struct PlanetPS {
     ec: f64, // eccentricity (0=circle, 0..1=ellipse, 1=parabola)
     m0: f64,
     mc: f64, // M0 = mean anomaly  (deg) (0 at perihelion; increases uniformly with time).  Mc ("mean motion") = rate of change
+    #[allow(dead_code)]
     mag_base: f64,
+    #[allow(dead_code)]
     mag_phase_factor: f64,
+    #[allow(dead_code)]
     mag_nonlinear_factor: f64,
+    #[allow(dead_code)]
     mag_nonlinear_exponent: f64,
 }
 
@@ -339,6 +1105,13 @@ pub struct Sun {
     coords: CartesianCoords,
 }
 
+/// Solar system object with positions evaluated from a JPL development ephemeris.
+pub struct EphemerisBody {
+    solartype: Solarobj,
+    coords: CartesianCoords,
+    ephemeris: Rc<JplEphemeris>,
+}
+
 pub struct CartesianCoords {
     heliocentric: bool, // False if geocentric
     pub xh: f64,        // X location in meters
@@ -381,12 +1154,12 @@ mod kepler_utilities {
 
     /// Calculates the mean anomaly for the Sun.
     fn mean_anomaly_of_sun(day: f64) -> f64 {
-        (356.0470 + (0.9856002585 * day)) as f64
+        356.0470 + (0.9856002585 * day)
     }
 
     /// Calculates the argument of perihelion for the Sun.
     fn sun_argument_of_perihelion(day: f64) -> f64 {
-        (282.9404 + (4.70935e-5 * day)) as f64
+        282.9404 + (4.70935e-5 * day)
     }
 
     /// Calculates the ecliptic latitude and longitude for the given inputs.
@@ -457,16 +1230,24 @@ mod kepler_utilities {
         let zp = r * sinlat;
 
         CartesianCoords {
-            xh: xp as f64,
-            yh: yp as f64,
-            zh: zp as f64,
+            xh: xp,
+            yh: yp,
+            zh: zp,
             heliocentric: false,
         }
     }
 }
 
 pub trait KeplerModel {
-    fn ecliptic_cartesian_coords(&self, day: f64) -> CartesianCoords;
+    /// Position of the body at a day, expressed in the frame of the model, or an error if the
+    /// model does not cover the day.
+    fn ecliptic_cartesian_coords(&self, day: f64) -> Result<CartesianCoords, String>;
+
+    /// Frame of the positions of the model, the analytic models are referred to the ecliptic and
+    /// equinox of date.
+    fn frame(&self) -> frames::Frame {
+        frames::Frame::EclipticOfDate
+    }
 
     fn perturb(&self, xh: f64, yh: f64, zh: f64, _day: f64) -> CartesianCoords {
         CartesianCoords {
@@ -483,19 +1264,33 @@ pub trait KeplerModel {
 
     fn get_solar_object(&self) -> &Solarobj;
 
-    fn to_output_form(&self, sim_time_s: f64) -> output::SolarObjectOut {
+    fn to_output_form(
+        &self,
+        sim_time_s: f64,
+        utc: &str,
+        frame: &output::OutputFrame,
+    ) -> output::SolarObjectOut {
+        let coords = self.get_coords();
+        let position = frame.rotate(&Array3d {
+            x: coords.xh,
+            y: coords.yh,
+            z: coords.zh,
+        });
+
         output::SolarObjectOut {
             name: self.get_solar_object().to_string(),
             sim_time: sim_time_s,
-            x_coord: self.get_coords().xh as f32,
-            y_coord: self.get_coords().yh as f32,
-            z_coord: self.get_coords().zh as f32,
+            utc: utc.to_string(),
+            frame: frame.frame.to_string(),
+            x_coord: position.x as f32,
+            y_coord: position.y as f32,
+            z_coord: position.z as f32,
         }
     }
 }
 
 impl KeplerModel for PlanetPS {
-    fn ecliptic_cartesian_coords(&self, day: f64) -> CartesianCoords {
+    fn ecliptic_cartesian_coords(&self, day: f64) -> Result<CartesianCoords, String> {
         // Default impl
         let a = self.a0 + (day * self.ac);
         let e = self.e0 + (day * self.ec);
@@ -528,7 +1323,7 @@ impl KeplerModel for PlanetPS {
         yh *= AU_METER;
         zh *= AU_METER;
 
-        self.perturb(xh as f64, yh as f64, zh as f64, day)
+        Ok(self.perturb(xh, yh, zh, day))
     }
 
     /// Calculates additional perturbations on top of main heliocentric position calculation.
@@ -538,7 +1333,7 @@ impl KeplerModel for PlanetPS {
     ///  * 'xh' - X coord
     ///  * 'yh' - Y coord
     ///  * 'zh' - Z coord
-    ///  8 'day' - Day value
+    ///  * 'day' - Day value
     ///
     /// ### Returns
     ///      Cartesian coords with the added perturbations.
@@ -591,7 +1386,7 @@ impl KeplerModel for Earth {
     ///
     /// ### Return
     ///     The coordinates of Earth at the provided time.
-    fn ecliptic_cartesian_coords(&self, day: f64) -> CartesianCoords {
+    fn ecliptic_cartesian_coords(&self, day: f64) -> Result<CartesianCoords, String> {
         let d = day - 1.5;
         // Julian centuries since J2000.0
         let t = d / 36525.0;
@@ -619,12 +1414,12 @@ impl KeplerModel for Earth {
         y *= AU_METER;
 
         // the Earth's center is always on the plane of the ecliptic (z=0), by definition!
-        CartesianCoords {
+        Ok(CartesianCoords {
             xh: x,
             yh: y,
             zh: 0f64,
             heliocentric: true,
-        }
+        })
     }
 
     fn get_coords(&self) -> &CartesianCoords {
@@ -641,13 +1436,64 @@ impl KeplerModel for Earth {
 }
 
 impl KeplerModel for Sun {
-    fn ecliptic_cartesian_coords(&self, _day: f64) -> CartesianCoords {
-        CartesianCoords {
+    fn ecliptic_cartesian_coords(&self, _day: f64) -> Result<CartesianCoords, String> {
+        Ok(CartesianCoords {
             xh: 0f64,
             yh: 0f64,
             zh: 0f64,
             heliocentric: true,
-        }
+        })
+    }
+
+    fn get_coords(&self) -> &CartesianCoords {
+        &self.coords
+    }
+
+    fn mut_coords(&mut self) -> &mut CartesianCoords {
+        &mut self.coords
+    }
+
+    fn get_solar_object(&self) -> &Solarobj {
+        &self.solartype
+    }
+}
+
+impl KeplerModel for EphemerisBody {
+    /// Calculate the position of the body from the ephemeris, in the ICRF axes of the
+    /// ephemeris.
+    ///
+    /// ### Argument
+    /// * 'day' - Day as an f64
+    ///
+    /// ### Return
+    ///     The coordinates of the body at the provided time or an error if the time is outside
+    ///     of the span of the ephemeris.
+    fn ecliptic_cartesian_coords(&self, day: f64) -> Result<CartesianCoords, String> {
+        // The day count is TDB based, the time argument of the ephemeris
+        let jd = ephemeris::JD_DAY_ORIGIN + day;
+        let position = match self.solartype {
+            Solarobj::Earth { attr: _ } => self.ephemeris.earth_heliocentric_position_m(jd),
+            Solarobj::Sun { attr: _ } => self.ephemeris.heliocentric_position_m(EphemerisTarget::Sun, jd),
+            Solarobj::Moon { attr: _ } => self.ephemeris.heliocentric_position_m(EphemerisTarget::Moon, jd),
+            Solarobj::Mars { attr: _ } => self.ephemeris.heliocentric_position_m(EphemerisTarget::Mars, jd),
+        };
+        let [x, y, z] = position.ok_or_else(|| {
+            format!(
+                "Simulation time (JD {}) is outside of the DE{} ephemeris span (JD {} to {}).",
+                jd, self.ephemeris.de_number, self.ephemeris.start_jd, self.ephemeris.end_jd
+            )
+        })?;
+
+        Ok(CartesianCoords {
+            xh: x,
+            yh: y,
+            zh: z,
+            heliocentric: !matches!(self.solartype, Solarobj::Moon { attr: _ }),
+        })
+    }
+
+    fn frame(&self) -> frames::Frame {
+        frames::Frame::Gcrf
     }
 
     fn get_coords(&self) -> &CartesianCoords {
@@ -668,12 +1514,7 @@ impl KeplerModel for Sun {
 ///  ### Return
 ///       A newly crafted sun object.
 fn make_sun() -> Sun {
-    let solar_trait = Solarobj::Sun {
-        attr: SolarAttr {
-            radius: 6.95700e8,
-            mass: 1.9891e30,
-        },
-    };
+    let solar_trait = sun_solar_object();
 
     Sun {
         solartype: solar_trait,
@@ -694,13 +1535,8 @@ fn make_sun() -> Sun {
 /// ### Return
 ///      A newly created earth object.
 ///
-fn make_earth(day: f64) -> Earth {
-    let solar_trait = Solarobj::Earth {
-        attr: SolarAttr {
-            radius: 6.3781e6,
-            mass: 5.9722e24,
-        },
-    };
+fn make_earth(day: f64) -> Result<Earth, String> {
+    let solar_trait = earth_solar_object();
 
     let mut earth_body = Earth {
         solartype: solar_trait,
@@ -712,9 +1548,9 @@ fn make_earth(day: f64) -> Earth {
         },
     };
 
-    earth_body.coords = earth_body.ecliptic_cartesian_coords(day);
+    earth_body.coords = earth_body.ecliptic_cartesian_coords(day)?;
 
-    earth_body
+    Ok(earth_body)
 }
 
 /// Create the moon, geocentric.
@@ -725,13 +1561,8 @@ fn make_earth(day: f64) -> Earth {
 /// ### Return
 ///     A newly created moon PlanetPS object.
 ///
-fn make_moon(day: f64) -> PlanetPS {
-    let solar_trait = Solarobj::Moon {
-        attr: SolarAttr {
-            radius: 1738.1,
-            mass: 0.07346e24,
-        },
-    };
+fn make_moon(day: f64) -> Result<PlanetPS, String> {
+    let solar_trait = moon_solar_object();
 
     let mut moon_body = PlanetPS {
         solartype: solar_trait,
@@ -759,7 +1590,130 @@ fn make_moon(day: f64) -> PlanetPS {
         mag_nonlinear_exponent: 4f64,
     };
 
-    moon_body.coords = moon_body.ecliptic_cartesian_coords(day);
+    moon_body.coords = moon_body.ecliptic_cartesian_coords(day)?;
+
+    Ok(moon_body)
+}
 
-    moon_body
+/// Create Mars, heliocentric.
+///
+/// ### Argument
+/// * 'day' - Day value greater than zero.
+///
+/// ### Return
+///     A newly created mars PlanetPS object.
+///
+fn make_mars(day: f64) -> Result<PlanetPS, String> {
+    let solar_trait = mars_solar_object();
+
+    let mut mars_body = PlanetPS {
+        solartype: solar_trait,
+        coords: CartesianCoords {
+            xh: 0f64,
+            yh: 0f64,
+            zh: 0f64,
+            heliocentric: true,
+        },
+        n0: 49.5574,
+        nc: 2.11081e-5,
+        i0: 1.8497,
+        ic: -1.78e-8,
+        w0: 286.5016,
+        wc: 2.92961e-5,
+        a0: 1.523688,
+        ac: 0.0,
+        e0: 0.093405,
+        ec: 2.516e-9,
+        m0: 18.6021,
+        mc: 0.5240207766,
+        mag_base: -1.51,
+        mag_phase_factor: 0.016,
+        mag_nonlinear_factor: 0.0,
+        mag_nonlinear_exponent: 0.0,
+    };
+
+    mars_body.coords = mars_body.ecliptic_cartesian_coords(day)?;
+
+    Ok(mars_body)
+}
+
+/// Create a solar system object backed by a JPL development ephemeris.
+///
+/// ### Arguments
+/// * 'solartype' - The solar object to evaluate from the ephemeris.
+/// * 'ephemeris' - The loaded ephemeris.
+/// * 'day' - Day value greater than zero.
+///
+/// ### Return
+///     A newly created ephemeris backed object or an error if the day is outside of the
+///     ephemeris.
+///
+fn make_ephemeris_body(
+    solartype: Solarobj,
+    ephemeris: &Rc<JplEphemeris>,
+    day: f64,
+) -> Result<EphemerisBody, String> {
+    let mut body = EphemerisBody {
+        solartype,
+        coords: CartesianCoords {
+            xh: 0f64,
+            yh: 0f64,
+            zh: 0f64,
+            heliocentric: true,
+        },
+        ephemeris: Rc::clone(ephemeris),
+    };
+
+    body.coords = body.ecliptic_cartesian_coords(day)?;
+
+    Ok(body)
+}
+
+/// Physical attributes of the Sun.
+fn sun_solar_object() -> Solarobj {
+    Solarobj::Sun {
+        attr: SolarAttr {
+            radius: 6.95700e8,
+            mass: 1.9891e30,
+            j2: 2.2e-7,
+        },
+    }
+}
+
+/// Gravitational parameter of the Earth in m^3/s^2, as the simulation models it.
+pub fn earth_gravitational_parameter() -> f64 {
+    force_models::G * earth_solar_object().get_mass_kg()
+}
+
+/// Physical attributes of the Earth.
+fn earth_solar_object() -> Solarobj {
+    Solarobj::Earth {
+        attr: SolarAttr {
+            radius: 6.3781e6,
+            mass: 5.9722e24,
+            j2: 1.08262668e-3,
+        },
+    }
+}
+
+/// Physical attributes of the Moon.
+fn moon_solar_object() -> Solarobj {
+    Solarobj::Moon {
+        attr: SolarAttr {
+            radius: 1.7381e6,
+            mass: 0.07346e24,
+            j2: 2.0330e-4,
+        },
+    }
+}
+
+/// Physical attributes of Mars.
+fn mars_solar_object() -> Solarobj {
+    Solarobj::Mars {
+        attr: SolarAttr {
+            radius: 3.3895e6,
+            mass: 6.4171e23,
+            j2: 1.96045e-3,
+        },
+    }
 }
@@ -0,0 +1,735 @@
+//! Reader of CCSDS orbit data messages (CCSDS 502.0-B-2) in KVN and XML encoding.
+//!
+//! Two message types are supported:
+//! * OPM - Orbit parameter message, a cartesian state in an inertial frame with the spacecraft
+//!   parameters and maneuvers of the object. The state is moved from the epoch of the message to
+//!   the start epoch on its two-body orbit.
+//! * OMM - Orbit mean-element message with SGP4 mean elements in the TEME frame, treated like a
+//!   two-line element set.
+//!
+//! Both encodings are reduced to the keywords of the KVN format and their values, the XML
+//! elements carry the same names. Zero spacecraft parameters are taken as not given. Finite
+//! maneuvers are applied as impulses at the middle of the burn, their mass change is ignored.
+
+use crate::bodies;
+use crate::maneuver::Maneuver;
+use crate::state::InitialState;
+use crate::time;
+use crate::tle::TwoLineElements;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use frames::{Frame, LocalFrame};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use types::Array3d;
+
+const METERS_PER_KILOMETER: f64 = 1000.0;
+const SECONDS_PER_DAY: f64 = 86400.0;
+const TT_MINUS_TAI_S: f64 = 32.184;
+const TAI_MINUS_GPS_S: f64 = 19.0;
+const MJD_UNIX_EPOCH: i64 = 40587;
+
+/// Keyword of a message with its value and line in the file.
+struct Keyword {
+    name: String,
+    value: String,
+    line: usize,
+}
+
+/// Object described by an orbit parameter or mean-element message.
+pub struct OrbitMessage {
+    pub object_name: String,
+    pub center: bodies::CentralBody, // Body the state is relative to
+    pub frame: Frame,                // Reference frame of the state
+    pub epoch: time::Epoch,          // Epoch of the state
+    pub state: InitialState,
+    pub mass: Option<f64>,      // kg
+    pub drag_area: Option<f64>, // Cross-section for drag in m^2
+    pub surface: bodies::SurfaceProperties,
+    pub maneuvers: Vec<Maneuver>,
+}
+
+/// Checks whether a file holds an orbit parameter or mean-element message rather than a
+/// scenario or element sets.
+///
+/// ### Argument
+/// * 'text' - Contents of the file.
+///
+pub fn is_orbit_message(text: &str) -> bool {
+    let text = text.trim_start();
+    text.starts_with("CCSDS_OPM_VERS")
+        || text.starts_with("CCSDS_OMM_VERS")
+        || (text.starts_with('<') && (text.contains("<opm") || text.contains("<omm")))
+}
+
+impl OrbitMessage {
+    /// Parses a message in KVN or XML encoding.
+    ///
+    /// ### Argument
+    /// * 'text' - Contents of the message file.
+    ///
+    /// ### Return
+    ///     The described object, or an error naming the offending line or keyword.
+    ///
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let keywords = if text.trim_start().starts_with('<') {
+            xml_keywords(text)?
+        } else {
+            kvn_keywords(text)?
+        };
+        let message = Message::new(keywords)?;
+
+        let object_name = message.required("OBJECT_NAME")?.to_string();
+        let center = match message.required("CENTER_NAME")?.to_uppercase().as_str() {
+            "EARTH" => bodies::CentralBody::Earth,
+            "MOON" => bodies::CentralBody::Moon,
+            "MARS" => bodies::CentralBody::Mars,
+            "SUN" => bodies::CentralBody::Sun,
+            _ => return Err(message.error("CENTER_NAME", "is not a supported center.")),
+        };
+        let frame = match message.required("REF_FRAME")? {
+            "EME2000" => Frame::Eme2000,
+            "GCRF" | "ICRF" => Frame::Gcrf,
+            "TOD" => Frame::Tod,
+            "TEME" => Frame::Teme,
+            _ => return Err(message.error("REF_FRAME", "is not a supported reference frame.")),
+        };
+        let time_system = message.required("TIME_SYSTEM")?;
+        let epoch = message.epoch("EPOCH", time_system)?;
+
+        let state = if message.get("CCSDS_OPM_VERS").is_some() {
+            InitialState::Dated {
+                epoch,
+                frame,
+                coords: message.vector(["X", "Y", "Z"])?,
+                velocity: message.vector(["X_DOT", "Y_DOT", "Z_DOT"])?,
+            }
+        } else {
+            message.mean_elements(&object_name, center, frame, epoch)?
+        };
+
+        let maneuvers = message
+            .maneuvers
+            .iter()
+            .map(|maneuver| maneuver.to_maneuver(time_system))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(OrbitMessage {
+            object_name,
+            center,
+            frame,
+            epoch,
+            state,
+            mass: message.parameter("MASS")?,
+            drag_area: message.parameter("DRAG_AREA")?,
+            surface: bodies::SurfaceProperties {
+                drag_coefficient: message.parameter("DRAG_COEFF")?,
+                srp_area: message.parameter("SOLAR_RAD_AREA")?,
+                reflectivity: message.parameter("SOLAR_RAD_COEFF")?,
+            },
+            maneuvers,
+        })
+    }
+}
+
+/// Keywords of a message, the maneuvers grouped by their ignition epoch.
+struct Message {
+    keywords: HashMap<String, Keyword>,
+    maneuvers: Vec<Message>,
+}
+
+impl Message {
+    fn new(keywords: Vec<Keyword>) -> Result<Self, String> {
+        let mut message = Message {
+            keywords: HashMap::new(),
+            maneuvers: Vec::new(),
+        };
+
+        for keyword in keywords {
+            if keyword.name == "COMMENT" || keyword.name.starts_with("USER_DEFINED") {
+                continue;
+            }
+
+            let target = if keyword.name == "MAN_EPOCH_IGNITION" {
+                message.maneuvers.push(Message {
+                    keywords: HashMap::new(),
+                    maneuvers: Vec::new(),
+                });
+                message.maneuvers.last_mut()
+            } else if keyword.name.starts_with("MAN_") {
+                message.maneuvers.last_mut()
+            } else {
+                Some(&mut message)
+            };
+
+            match target {
+                Some(target) if !target.keywords.contains_key(&keyword.name) => {
+                    target.keywords.insert(keyword.name.clone(), keyword);
+                }
+                Some(_) => {
+                    return Err(format!(
+                        "Line {}: Duplicate keyword {}.",
+                        keyword.line, keyword.name
+                    ))
+                }
+                None => {
+                    return Err(format!(
+                        "Line {}: Maneuver keyword {} before MAN_EPOCH_IGNITION.",
+                        keyword.line, keyword.name
+                    ))
+                }
+            }
+        }
+
+        Ok(message)
+    }
+
+    fn get(&self, name: &str) -> Option<&Keyword> {
+        self.keywords.get(name)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.get(name)
+            .map(|keyword| keyword.value.as_str())
+            .ok_or_else(|| format!("Missing keyword {}.", name))
+    }
+
+    fn error(&self, name: &str, message: &str) -> String {
+        match self.get(name) {
+            Some(keyword) => format!("Line {}: {} {}", keyword.line, name, message),
+            None => format!("{} {}", name, message),
+        }
+    }
+
+    fn float(&self, name: &str) -> Result<f64, String> {
+        self.required(name)?
+            .parse::<f64>()
+            .map_err(|_| self.error(name, "is not a number."))
+    }
+
+    /// Optional spacecraft parameter, none if left out or zero.
+    fn parameter(&self, name: &str) -> Result<Option<f64>, String> {
+        if self.get(name).is_none() {
+            return Ok(None);
+        }
+
+        let value = self.float(name)?;
+        if value < 0.0 {
+            return Err(self.error(name, "must not be negative."));
+        }
+
+        Ok(Some(value).filter(|value| *value > 0.0))
+    }
+
+    /// Vector of keywords in kilometers, converted to meters.
+    fn vector(&self, names: [&str; 3]) -> Result<Array3d, String> {
+        Ok(Array3d {
+            x: self.float(names[0])? * METERS_PER_KILOMETER,
+            y: self.float(names[1])? * METERS_PER_KILOMETER,
+            z: self.float(names[2])? * METERS_PER_KILOMETER,
+        })
+    }
+
+    /// Epoch of a keyword in calendar or day of year format, in the time system of the message.
+    fn epoch(&self, name: &str, time_system: &str) -> Result<time::Epoch, String> {
+        let value = self.required(name)?.trim_end_matches('Z');
+        let datetime = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%jT%H:%M:%S%.f"))
+            .map_err(|_| self.error(name, "is not a valid epoch."))?;
+
+        // Read as UTC and shifted by the offset of the time system to UTC at that time
+        let epoch = time::Epoch::from_utc(&DateTime::<Utc>::from_utc(datetime, Utc));
+        let tai_minus_utc =
+            time::tai_minus_utc(datetime.timestamp().div_euclid(86400) + MJD_UNIX_EPOCH);
+        match time_system {
+            "UTC" => Ok(epoch),
+            "TAI" => Ok(epoch.offset(-tai_minus_utc)),
+            "GPS" => Ok(epoch.offset(TAI_MINUS_GPS_S - tai_minus_utc)),
+            // TDB differs from TT by less than two milliseconds
+            "TT" | "TDB" => Ok(epoch.offset(-TT_MINUS_TAI_S - tai_minus_utc)),
+            _ => Err(self.error("TIME_SYSTEM", "is not a supported time system.")),
+        }
+    }
+
+    /// SGP4 mean elements of an orbit mean-element message.
+    fn mean_elements(
+        &self,
+        object_name: &str,
+        center: bodies::CentralBody,
+        frame: Frame,
+        epoch: time::Epoch,
+    ) -> Result<InitialState, String> {
+        if self.required("MEAN_ELEMENT_THEORY")? != "SGP4" {
+            return Err(self.error("MEAN_ELEMENT_THEORY", "has to be SGP4."));
+        }
+        if center != bodies::CentralBody::Earth || frame != Frame::Teme {
+            return Err("SGP4 mean elements have to be relative to the Earth in TEME.".to_string());
+        }
+
+        let mean_motion = self.float("MEAN_MOTION")? * 2.0 * PI / SECONDS_PER_DAY;
+        if mean_motion <= 0.0 {
+            return Err(self.error("MEAN_MOTION", "must be positive."));
+        }
+        let eccentricity = self.float("ECCENTRICITY")?;
+        if !(0.0..1.0).contains(&eccentricity) {
+            return Err(self.error("ECCENTRICITY", "must be within [0, 1)."));
+        }
+        let catalog_number = match self.get("NORAD_CAT_ID") {
+            Some(keyword) => keyword
+                .value
+                .parse::<u32>()
+                .map_err(|_| self.error("NORAD_CAT_ID", "is not a catalog number."))?,
+            None => 0,
+        };
+        // The derivatives of the mean motion are not used by SGP4
+        let bstar = match self.get("BSTAR") {
+            Some(_) => self.float("BSTAR")?,
+            None => 0.0,
+        };
+
+        Ok(InitialState::Tle(TwoLineElements {
+            name: Some(object_name.to_string()),
+            catalog_number,
+            epoch,
+            bstar,
+            inclination_rad: self.float("INCLINATION")?.to_radians(),
+            raan_rad: self.float("RA_OF_ASC_NODE")?.to_radians(),
+            eccentricity,
+            arg_perigee_rad: self.float("ARG_OF_PERICENTER")?.to_radians(),
+            mean_anomaly_rad: self.float("MEAN_ANOMALY")?.to_radians(),
+            mean_motion,
+        }))
+    }
+
+    /// Impulsive maneuver of the keywords of a maneuver, at the middle of a finite burn.
+    fn to_maneuver(&self, time_system: &str) -> Result<Maneuver, String> {
+        let ignition = self.epoch("MAN_EPOCH_IGNITION", time_system)?;
+        let duration_s = match self.get("MAN_DURATION") {
+            Some(_) => self.float("MAN_DURATION")?,
+            None => 0.0,
+        };
+        let delta_v = self.vector(["MAN_DV_1", "MAN_DV_2", "MAN_DV_3"])?;
+
+        let (frame, delta_v) = match self.required("MAN_REF_FRAME")? {
+            "RTN" | "RSW" => (LocalFrame::Rtn, delta_v),
+            // Tangential along the velocity, normal within the orbit plane and W along the
+            // angular momentum, the binormal of VNB points against the normal
+            "TNW" => (
+                LocalFrame::Vnb,
+                Array3d {
+                    x: delta_v.x,
+                    y: delta_v.z,
+                    z: -delta_v.y,
+                },
+            ),
+            _ => return Err(self.error("MAN_REF_FRAME", "has to be RTN, RSW or TNW.")),
+        };
+
+        Ok(Maneuver {
+            epoch: ignition.offset(duration_s / 2.0).to_utc_string(),
+            delta_v,
+            frame,
+        })
+    }
+}
+
+/// Keywords of a KVN message, 'KEYWORD = value [units]' on each line.
+fn kvn_keywords(text: &str) -> Result<Vec<Keyword>, String> {
+    let mut keywords = Vec::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        // Comments and the start and stop markers of blocks carry no values
+        if line.is_empty()
+            || line.starts_with("COMMENT")
+            || line.ends_with("_START")
+            || line.ends_with("_STOP")
+        {
+            continue;
+        }
+
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Line {}: Expected 'KEYWORD = value'.", idx + 1))?;
+        let value = value.split('[').next().unwrap_or("");
+        keywords.push(Keyword {
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+            line: idx + 1,
+        });
+    }
+
+    Ok(keywords)
+}
+
+/// Keywords of an XML message, the elements holding text only. The version attribute of the
+/// root element becomes the version keyword of the KVN format.
+fn xml_keywords(text: &str) -> Result<Vec<Keyword>, String> {
+    let line_at = |idx: usize| text[..idx].matches('\n').count() + 1;
+    let mut keywords = Vec::new();
+    let mut pos = 0;
+
+    while let Some(start) = text[pos..].find('<').map(|idx| pos + idx) {
+        let markup_end = |end: &str| {
+            text[start..]
+                .find(end)
+                .map(|idx| start + idx + end.len())
+                .ok_or_else(|| format!("Line {}: Unterminated markup.", line_at(start)))
+        };
+
+        let rest = &text[start..];
+        if rest.starts_with("<!--") {
+            pos = markup_end("-->")?;
+            continue;
+        }
+        pos = markup_end(">")?;
+        if rest.starts_with("<?") || rest.starts_with("<!") || rest.starts_with("</") {
+            continue;
+        }
+
+        let tag = &text[start + 1..pos - 1];
+        let name = tag.split_whitespace().next().unwrap_or("");
+        if tag.ends_with('/') {
+            continue;
+        }
+        if name == "opm" || name == "omm" {
+            keywords.push(Keyword {
+                name: format!("CCSDS_{}_VERS", name.to_uppercase()),
+                value: xml_attribute(tag, "version").unwrap_or_default(),
+                line: line_at(start),
+            });
+            continue;
+        }
+
+        let content_end = text[pos..].find('<').map_or(text.len(), |idx| pos + idx);
+        if text[content_end..].starts_with(&format!("</{}>", name)) {
+            keywords.push(Keyword {
+                name: name.to_string(),
+                value: xml_unescape(text[pos..content_end].trim()),
+                line: line_at(start),
+            });
+        }
+    }
+
+    Ok(keywords)
+}
+
+/// Value of an attribute of a start tag.
+fn xml_attribute(tag: &str, name: &str) -> Option<String> {
+    let value_start = tag.find(&format!("{}=", name))? + name.len() + 1;
+    let quote = tag[value_start..].chars().next()?;
+    let value = &tag[value_start + 1..];
+
+    value.find(quote).map(|end| xml_unescape(&value[..end]))
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tle;
+
+    // The state of data/iss.opm in GPS time with a finite burn in TNW and an impulse in RTN
+    const OPM_KVN: &str = "CCSDS_OPM_VERS = 2.0
+CREATION_DATE = 2019-04-24T10:00:00
+ORIGINATOR = TEST
+
+META_START
+OBJECT_NAME = ISS
+OBJECT_ID = 1998-067A
+CENTER_NAME = EARTH
+REF_FRAME = EME2000
+TIME_SYSTEM = GPS
+META_STOP
+
+COMMENT State vector
+EPOCH = 2019-04-24T12:00:18.000
+X = -194.68815 [km]
+Y = 6753.21668 [km]
+Z = -4.800139863 [km]
+X_DOT = -4.800139863 [km/s]
+Y_DOT = -0.711367936 [km/s]
+Z_DOT = -5.934797548 [km/s]
+
+MASS = 420000.0 [kg]
+DRAG_AREA = 0.0 [m**2]
+DRAG_COEFF = 2.3
+USER_DEFINED_OPERATOR = PARTNER
+
+MAN_EPOCH_IGNITION = 2019-04-24T12:00:48.000
+MAN_DURATION = 10.0 [s]
+MAN_DELTA_MASS = -1.0 [kg]
+MAN_REF_FRAME = TNW
+MAN_DV_1 = 0.001 [km/s]
+MAN_DV_2 = 0.002 [km/s]
+MAN_DV_3 = 0.003 [km/s]
+
+COMMENT Second maneuver
+MAN_EPOCH_IGNITION = 2019-115T13:00:18.000
+MAN_DELTA_MASS = -1.0 [kg]
+MAN_REF_FRAME = RTN
+MAN_DV_1 = 0.0 [km/s]
+MAN_DV_2 = -0.001 [km/s]
+MAN_DV_3 = 0.0 [km/s]
+";
+
+    const OPM_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opm id="CCSDS_OPM_VERS" version="2.0">
+  <header>
+    <CREATION_DATE>2019-04-24T10:00:00</CREATION_DATE>
+    <ORIGINATOR>TEST</ORIGINATOR>
+  </header>
+  <body>
+    <segment>
+      <metadata>
+        <OBJECT_NAME>ISS &amp; PMA</OBJECT_NAME>
+        <CENTER_NAME>EARTH</CENTER_NAME>
+        <REF_FRAME>EME2000</REF_FRAME>
+        <TIME_SYSTEM>TAI</TIME_SYSTEM>
+      </metadata>
+      <data>
+        <!-- <EPOCH>2000-01-01T00:00:00</EPOCH> -->
+        <stateVector>
+          <EPOCH>2019-04-24T12:00:37.000</EPOCH>
+          <X units="km">-194.68815</X>
+          <Y units="km">6753.21668</Y>
+          <Z units="km">-4.800139863</Z>
+          <X_DOT units="km/s">-4.800139863</X_DOT>
+          <Y_DOT units="km/s">-0.711367936</Y_DOT>
+          <Z_DOT units="km/s">-5.934797548</Z_DOT>
+        </stateVector>
+        <spacecraftParameters>
+          <MASS units="kg">420000.0</MASS>
+          <SOLAR_RAD_AREA units="m**2">2500.0</SOLAR_RAD_AREA>
+        </spacecraftParameters>
+        <userDefinedParameters/>
+      </data>
+    </segment>
+  </body>
+</opm>
+"#;
+
+    // The element set of the ISS in data/catalog.tle
+    const OMM_KVN: &str = "CCSDS_OMM_VERS = 2.0
+CREATION_DATE = 2008-09-21T00:00:00
+ORIGINATOR = TEST
+OBJECT_NAME = ISS (ZARYA)
+OBJECT_ID = 1998-067A
+CENTER_NAME = EARTH
+REF_FRAME = TEME
+TIME_SYSTEM = UTC
+MEAN_ELEMENT_THEORY = SGP4
+EPOCH = 2008-09-20T12:25:40.104192
+MEAN_MOTION = 15.72125391 [rev/day]
+ECCENTRICITY = 0.0006703
+INCLINATION = 51.6416 [deg]
+RA_OF_ASC_NODE = 247.4627 [deg]
+ARG_OF_PERICENTER = 130.5360 [deg]
+MEAN_ANOMALY = 325.0288 [deg]
+NORAD_CAT_ID = 25544
+BSTAR = -0.11606E-4 [1/ER]
+MEAN_MOTION_DOT = -0.00002182 [rev/day**2]
+";
+
+    const OMM_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<omm id="CCSDS_OMM_VERS" version="2.0">
+  <header><CREATION_DATE>2008-09-21T00:00:00</CREATION_DATE><ORIGINATOR>TEST</ORIGINATOR></header>
+  <body><segment>
+    <metadata>
+      <OBJECT_NAME>ISS (ZARYA)</OBJECT_NAME>
+      <CENTER_NAME>EARTH</CENTER_NAME>
+      <REF_FRAME>TEME</REF_FRAME>
+      <TIME_SYSTEM>UTC</TIME_SYSTEM>
+      <MEAN_ELEMENT_THEORY>SGP4</MEAN_ELEMENT_THEORY>
+    </metadata>
+    <data>
+      <meanElements>
+        <EPOCH>2008-09-20T12:25:40.104192</EPOCH>
+        <MEAN_MOTION>15.72125391</MEAN_MOTION>
+        <ECCENTRICITY>0.0006703</ECCENTRICITY>
+        <INCLINATION>51.6416</INCLINATION>
+        <RA_OF_ASC_NODE>247.4627</RA_OF_ASC_NODE>
+        <ARG_OF_PERICENTER>130.5360</ARG_OF_PERICENTER>
+        <MEAN_ANOMALY>325.0288</MEAN_ANOMALY>
+      </meanElements>
+      <tleParameters>
+        <NORAD_CAT_ID>25544</NORAD_CAT_ID>
+        <BSTAR>-0.11606E-4</BSTAR>
+      </tleParameters>
+    </data>
+  </segment></body>
+</omm>
+"#;
+
+    const ISS_TLE: &str = "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537";
+
+    fn utc(datetime: &str) -> time::Epoch {
+        time::Epoch::from_utc(&DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc))
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() <= tolerance, "{} != {}", actual, expected);
+    }
+
+    fn find<'a>(keywords: &'a [Keyword], name: &str) -> &'a Keyword {
+        keywords.iter().find(|keyword| keyword.name == name).unwrap()
+    }
+
+    #[test]
+    fn kvn_keywords_of_opm() {
+        let keywords = kvn_keywords(OPM_KVN).unwrap();
+
+        assert_eq!(keywords[0].name, "CCSDS_OPM_VERS");
+        assert_eq!(keywords[0].value, "2.0");
+        assert_eq!(keywords[0].line, 1);
+        // Block markers and comments carry no values, units are removed
+        assert!(keywords
+            .iter()
+            .all(|keyword| keyword.name != "COMMENT" && !keyword.name.ends_with("_START")));
+        let x = find(&keywords, "X");
+        assert_eq!((x.value.as_str(), x.line), ("-194.68815", 15));
+        assert_eq!(keywords.iter().filter(|keyword| keyword.name == "MAN_REF_FRAME").count(), 2);
+
+        let err = kvn_keywords("CCSDS_OPM_VERS = 2.0\n\nOBJECT_NAME ISS\n").err().unwrap();
+        assert_eq!(err, "Line 3: Expected 'KEYWORD = value'.");
+    }
+
+    #[test]
+    fn xml_keywords_of_opm() {
+        let keywords = xml_keywords(OPM_XML).unwrap();
+
+        // The root element gives the version, the commented out epoch is skipped
+        assert_eq!(keywords[0].name, "CCSDS_OPM_VERS");
+        assert_eq!(keywords[0].value, "2.0");
+        assert_eq!(keywords.iter().filter(|keyword| keyword.name == "EPOCH").count(), 1);
+        // Elements holding other elements are not keywords
+        assert!(keywords.iter().all(|keyword| keyword.name != "stateVector"
+            && keyword.name != "header"
+            && keyword.name != "userDefinedParameters"));
+        assert_eq!(find(&keywords, "OBJECT_NAME").value, "ISS & PMA");
+        let y = find(&keywords, "Y");
+        assert_eq!((y.value.as_str(), y.line), ("6753.21668", 20));
+
+        assert_eq!(
+            xml_keywords("<opm version=\"2.0\">\n<X>1.0</X\n").err().unwrap(),
+            "Line 2: Unterminated markup."
+        );
+    }
+
+    #[test]
+    fn opm_with_maneuvers_in_gps_time() {
+        let message = OrbitMessage::parse(OPM_KVN).unwrap();
+
+        assert_eq!(message.object_name, "ISS");
+        assert!(message.center == bodies::CentralBody::Earth);
+        assert!(message.frame == Frame::Eme2000);
+        // GPS is 18 seconds ahead of UTC in 2019
+        assert_close(message.epoch.seconds_since(&utc("2019-04-24T12:00:00Z")), 0.0, 1e-6);
+        match message.state {
+            InitialState::Dated { coords, velocity, .. } => {
+                assert_close(coords.y, 6753216.68, 1e-6);
+                assert_close(velocity.z, -5934.797548, 1e-9);
+            }
+            _ => panic!("Expected a dated state."),
+        }
+        assert_eq!(message.mass, Some(420000.0));
+        // Zero parameters are not given
+        assert_eq!(message.drag_area, None);
+        assert_eq!(message.surface.drag_coefficient, Some(2.3));
+        assert_eq!(message.surface.reflectivity, None);
+
+        assert_eq!(message.maneuvers.len(), 2);
+        let burn = &message.maneuvers[0];
+        // Impulse at the middle of the burn
+        assert_eq!(burn.epoch, "2019-04-24T12:00:35.000Z");
+        assert_eq!(burn.frame, LocalFrame::Vnb);
+        // T, N and W of 1, 2 and 3 m/s along the velocity, binormal and against the normal
+        assert_close(burn.delta_v.x, 1.0, 1e-12);
+        assert_close(burn.delta_v.y, 3.0, 1e-12);
+        assert_close(burn.delta_v.z, -2.0, 1e-12);
+
+        let impulse = &message.maneuvers[1];
+        assert_eq!(impulse.epoch, "2019-04-25T13:00:00.000Z");
+        assert_eq!(impulse.frame, LocalFrame::Rtn);
+        assert_close(impulse.delta_v.y, -1.0, 1e-12);
+    }
+
+    #[test]
+    fn opm_in_xml_and_tai() {
+        let message = OrbitMessage::parse(OPM_XML).unwrap();
+
+        assert_eq!(message.object_name, "ISS & PMA");
+        // TAI is 37 seconds ahead of UTC in 2019
+        assert_close(message.epoch.seconds_since(&utc("2019-04-24T12:00:00Z")), 0.0, 1e-6);
+        match message.state {
+            InitialState::Dated { coords, .. } => assert_close(coords.x, -194688.15, 1e-6),
+            _ => panic!("Expected a dated state."),
+        }
+        assert_eq!(message.surface.srp_area, Some(2500.0));
+        assert!(message.maneuvers.is_empty());
+    }
+
+    #[test]
+    fn omm_as_element_set() {
+        let expected = tle::parse_element_sets(ISS_TLE).unwrap().pop().unwrap();
+        let expected_state = expected.state_at(&expected.epoch.offset(3600.0)).unwrap();
+
+        for text in [OMM_KVN, OMM_XML].iter() {
+            let message = OrbitMessage::parse(text).unwrap();
+            assert!(message.frame == Frame::Teme);
+            let element_set = match message.state {
+                InitialState::Tle(element_set) => element_set,
+                _ => panic!("Expected mean elements."),
+            };
+
+            assert_eq!(element_set.name.as_deref(), Some("ISS (ZARYA)"));
+            assert_eq!(element_set.catalog_number, 25544);
+            assert_close(element_set.bstar, -0.11606e-4, 1e-15);
+            assert_close(element_set.epoch.seconds_since(&expected.epoch), 0.0, 1e-6);
+            let (position, velocity) =
+                element_set.state_at(&expected.epoch.offset(3600.0)).unwrap();
+            assert_close(position.x, expected_state.0.x, 1e-3);
+            assert_close(position.z, expected_state.0.z, 1e-3);
+            assert_close(velocity.y, expected_state.1.y, 1e-6);
+        }
+    }
+
+    #[test]
+    fn invalid_messages() {
+        let replace = |from: &str, to: &str| {
+            OrbitMessage::parse(&OPM_KVN.replace(from, to))
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(
+            replace("TIME_SYSTEM = GPS", "TIME_SYSTEM = TCB"),
+            "Line 10: TIME_SYSTEM is not a supported time system."
+        );
+        assert_eq!(
+            replace("MAN_REF_FRAME = TNW", "MAN_REF_FRAME = EME2000"),
+            "Line 30: MAN_REF_FRAME has to be RTN, RSW or TNW."
+        );
+        assert_eq!(
+            replace("MAN_EPOCH_IGNITION = 2019-04-24T12:00:48.000", "COMMENT First maneuver"),
+            "Line 28: Maneuver keyword MAN_DURATION before MAN_EPOCH_IGNITION."
+        );
+        assert_eq!(
+            replace("DRAG_COEFF = 2.3", "MASS = 1.0"),
+            "Line 24: Duplicate keyword MASS."
+        );
+        assert_eq!(
+            OrbitMessage::parse(&OMM_KVN.replace("REF_FRAME = TEME", "REF_FRAME = EME2000"))
+                .err()
+                .unwrap(),
+            "SGP4 mean elements have to be relative to the Earth in TEME."
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! State covariances of objects, 6x6 matrices of the position in meters and the velocity in
+//! meters per second.
+//!
+//! Covariances are given as the rows of their lower triangle, in the order of the CCSDS orbit
+//! parameter message: x, y, z, x_dot, y_dot, z_dot. A covariance in a local frame rotates the
+//! position and velocity by the same axes, without accounting for the rotation of the frame.
+//! They are propagated alongside the state by the linearized step of the integrator under the
+//! gravity of the central body, the other forces do not enter the state transition.
+
+use frames::{Frame, LocalFrame};
+use serde::{Deserialize, Serialize};
+use time;
+use types::{Array3d, Matrix3d};
+
+pub const LOWER_TRIANGLE_LEN: usize = 21;
+
+// Tolerance of correlation coefficients exceeding one by rounding
+const CORRELATION_TOLERANCE: f64 = 1e-9;
+
+/// Covariance of a position and velocity.
+#[derive(Clone, Copy, Debug)]
+pub struct Covariance {
+    pub m: [[f64; 6]; 6],
+}
+
+impl Covariance {
+    /// Covariance of the rows of its lower triangle.
+    ///
+    /// ### Argument
+    /// * 'values' - The 21 elements of the lower triangle.
+    ///
+    /// ### Return
+    ///     The covariance, or an error if it is not one.
+    ///
+    pub fn from_lower_triangle(values: &[f64]) -> Result<Self, String> {
+        if values.len() != LOWER_TRIANGLE_LEN {
+            return Err(format!(
+                "A covariance takes the {} elements of its lower triangle, not {}.",
+                LOWER_TRIANGLE_LEN,
+                values.len()
+            ));
+        }
+        if values.iter().any(|value| !value.is_finite()) {
+            return Err("The covariance has elements which are not finite.".to_string());
+        }
+
+        let mut m = [[0.0; 6]; 6];
+        for (value, (row, column)) in values.iter().zip(lower_triangle_elements()) {
+            m[row][column] = *value;
+            m[column][row] = *value;
+        }
+
+        for (row, diagonal) in (0..6).map(|index| (index, m[index][index])) {
+            if diagonal < 0.0 {
+                return Err("The covariance has negative variances.".to_string());
+            }
+            if (0..row).any(|column| {
+                m[row][column].powi(2)
+                    > diagonal * m[column][column] * (1.0 + CORRELATION_TOLERANCE)
+            }) {
+                return Err("The covariance has correlations beyond one.".to_string());
+            }
+        }
+
+        Ok(Covariance { m })
+    }
+
+    /// Rows of the lower triangle of the covariance.
+    pub fn lower_triangle(&self) -> [f64; LOWER_TRIANGLE_LEN] {
+        let mut values = [0.0; LOWER_TRIANGLE_LEN];
+        for (value, (row, column)) in values.iter_mut().zip(lower_triangle_elements()) {
+            *value = self.m[row][column];
+        }
+        values
+    }
+
+    /// The covariance with the position and velocity rotated into another frame.
+    ///
+    /// ### Argument
+    /// * 'rotation' - Rotation into the other frame.
+    ///
+    pub fn rotated(&self, rotation: &Matrix3d) -> Self {
+        let mut transform = [[0.0; 6]; 6];
+        for row in 0..3 {
+            for column in 0..3 {
+                transform[row][column] = rotation.m[row][column];
+                transform[row + 3][column + 3] = rotation.m[row][column];
+            }
+        }
+
+        self.transformed(&transform)
+    }
+
+    /// Propagates the covariance over a step of the semi-implicit Euler integrator.
+    ///
+    /// ### Arguments
+    /// * 'position' - Position of the object relative to the central body at the start of the step.
+    /// * 'mu' - Gravitational parameter of the central body in m^3/s^2.
+    /// * 'step_time_s' - Step time of the simulation in seconds.
+    ///
+    pub fn propagate(&mut self, position: &Array3d, mu: f64, step_time_s: f64) {
+        let radius = position.dot(position).sqrt();
+        if radius == 0.0 {
+            return;
+        }
+
+        // Gravity gradient of the central body, the change of acceleration with position
+        let direction = [
+            position.x / radius,
+            position.y / radius,
+            position.z / radius,
+        ];
+        let scale = mu / radius.powi(3);
+        let mut gradient = [[0.0; 3]; 3];
+        for (row, gradient_row) in gradient.iter_mut().enumerate() {
+            for (column, element) in gradient_row.iter_mut().enumerate() {
+                let identity = if row == column { 1.0 } else { 0.0 };
+                *element = scale * (3.0 * direction[row] * direction[column] - identity);
+            }
+        }
+
+        // The step updates the velocity by the acceleration first, then the position by the new
+        // velocity: v' = v + a(r) dt, r' = r + v' dt
+        let mut transition = [[0.0; 6]; 6];
+        for row in 0..3 {
+            for column in 0..3 {
+                let identity = if row == column { 1.0 } else { 0.0 };
+                let gradient_step = gradient[row][column] * step_time_s;
+                transition[row][column] = identity + gradient_step * step_time_s;
+                transition[row][column + 3] = identity * step_time_s;
+                transition[row + 3][column] = gradient_step;
+                transition[row + 3][column + 3] = identity;
+            }
+        }
+
+        *self = self.transformed(&transition);
+    }
+
+    /// The covariance of the state transformed by a matrix, T P T^T.
+    fn transformed(&self, transform: &[[f64; 6]; 6]) -> Self {
+        let mut product = [[0.0; 6]; 6];
+        for (row, product_row) in product.iter_mut().enumerate() {
+            for (column, element) in product_row.iter_mut().enumerate() {
+                *element = (0..6)
+                    .map(|index| transform[row][index] * self.m[index][column])
+                    .sum();
+            }
+        }
+
+        let mut m = [[0.0; 6]; 6];
+        for row in 0..6 {
+            for column in 0..=row {
+                let value: f64 = (0..6)
+                    .map(|index| product[row][index] * transform[column][index])
+                    .sum();
+                m[row][column] = value;
+                m[column][row] = value;
+            }
+        }
+
+        Covariance { m }
+    }
+}
+
+/// Row and column of the elements of the lower triangle in their order.
+fn lower_triangle_elements() -> impl Iterator<Item = (usize, usize)> {
+    (0..6).flat_map(|row| (0..=row).map(move |column| (row, column)))
+}
+
+/// Covariance of an object as provided by the input file, in the inertial frame of the input or
+/// in a local orbital frame of the object.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CovarianceInput {
+    #[serde(default)]
+    pub frame: Option<LocalFrame>, // The inertial frame of the input if none
+    pub lower_triangle: Vec<f64>, // Rows of the lower triangle in m^2, m^2/s and m^2/s^2
+}
+
+impl CovarianceInput {
+    /// Checks that the elements make up a covariance.
+    pub fn validate(&self) -> Result<(), String> {
+        Covariance::from_lower_triangle(&self.lower_triangle).map(|_| ())
+    }
+
+    /// Converts the covariance into the simulation frame.
+    ///
+    /// ### Arguments
+    /// * 'frame' - The frame of inertial states.
+    /// * 'epoch' - The start epoch.
+    /// * 'position' - Position of the object in the simulation frame.
+    /// * 'velocity' - Velocity of the object in the simulation frame.
+    ///
+    pub fn to_simulation_frame(
+        &self,
+        frame: Frame,
+        epoch: &time::Epoch,
+        position: &Array3d,
+        velocity: &Array3d,
+    ) -> Result<Covariance, String> {
+        let covariance = Covariance::from_lower_triangle(&self.lower_triangle)?;
+        let simulation_from_input = match self.frame {
+            Some(local_frame) => local_frame
+                .local_from_inertial(position, velocity)
+                .transpose(),
+            None => frame.simulation_from_frame(epoch),
+        };
+
+        Ok(covariance.rotated(&simulation_from_input))
+    }
+}
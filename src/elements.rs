@@ -0,0 +1,899 @@
+//! Orbital element sets and their conversions to and from cartesian states.
+//!
+//! Elements are osculating, relative to the central body with gravitational parameter `mu`, and
+//! referred to the axes of the frame the state is given in. The supported sets are
+//! * Keplerian - a, e, i, RAAN, argument of periapsis and true anomaly. The angles that are
+//!   undefined for circular or equatorial orbits are set to zero and the remaining angles measured
+//!   from the x axis: the true anomaly becomes the argument of latitude for circular orbits, the
+//!   argument of periapsis the longitude of periapsis for equatorial orbits and the true anomaly the
+//!   true longitude for circular equatorial orbits.
+//! * Equinoctial - a, h, k, p, q and the mean longitude, nonsingular for elliptic orbits that
+//!   are not retrograde equatorial.
+//! * Modified equinoctial - p, f, g, h, k and the true longitude, nonsingular for all
+//!   eccentricities of orbits that are not retrograde equatorial.
+
+use std::f64::consts::PI;
+use types::{Array3d, Matrix3d};
+
+// Eccentricity and inclination below which orbits are treated as circular and equatorial
+const SINGULARITY_TOLERANCE: f64 = 1e-11;
+// Convergence tolerance of Kepler's equation in radians
+const KEPLER_TOLERANCE: f64 = 1e-13;
+const KEPLER_MAX_ITERATIONS: usize = 50;
+// Refinements of the first order osculating to mean element transformation
+const MEAN_ELEMENTS_ITERATIONS: usize = 5;
+// Indices of the angles of the nonsingular elements used for the refinements
+const NONSINGULAR_ANGLES: [usize; 2] = [4, 5];
+
+/// Classical Keplerian elements.
+#[derive(Clone, Copy, Debug)]
+pub struct KeplerianElements {
+    pub semi_major_axis_m: f64, // Negative for hyperbolic and infinite for parabolic orbits
+    pub eccentricity: f64,
+    pub inclination_rad: f64,
+    pub raan_rad: f64, // Right ascension of the ascending node
+    pub arg_periapsis_rad: f64,
+    pub true_anomaly_rad: f64,
+}
+
+/// Equinoctial elements of Broucke and Cefola.
+#[derive(Clone, Copy, Debug)]
+pub struct EquinoctialElements {
+    pub semi_major_axis_m: f64,
+    pub h: f64,                  // e sin(arg_periapsis + raan)
+    pub k: f64,                  // e cos(arg_periapsis + raan)
+    pub p: f64,                  // tan(i / 2) sin(raan)
+    pub q: f64,                  // tan(i / 2) cos(raan)
+    pub mean_longitude_rad: f64, // mean_anomaly + arg_periapsis + raan
+}
+
+/// Modified equinoctial elements of Walker, Ireland and Owens.
+#[derive(Clone, Copy, Debug)]
+pub struct ModifiedEquinoctialElements {
+    pub semi_latus_rectum_m: f64,
+    pub f: f64,                  // e cos(arg_periapsis + raan)
+    pub g: f64,                  // e sin(arg_periapsis + raan)
+    pub h: f64,                  // tan(i / 2) cos(raan)
+    pub k: f64,                  // tan(i / 2) sin(raan)
+    pub true_longitude_rad: f64, // true_anomaly + arg_periapsis + raan
+}
+
+/// Wraps an angle into [0, 2 pi).
+fn wrap_angle(angle: f64) -> f64 {
+    angle.rem_euclid(2.0 * PI)
+}
+
+/// Angle between two vectors in [0, pi].
+fn angle_between(a: &Array3d, b: &Array3d) -> f64 {
+    a.cross(b).dot(&a.cross(b)).sqrt().atan2(a.dot(b))
+}
+
+/// Unit vectors f and g of the equinoctial frame spanning the orbit plane, f is the ascending
+/// node rotated back by the RAAN within the plane.
+///
+/// ### Arguments
+/// * 'tan_sin' - tan(i / 2) sin(raan)
+/// * 'tan_cos' - tan(i / 2) cos(raan)
+///
+fn equinoctial_axes(tan_sin: f64, tan_cos: f64) -> (Array3d, Array3d) {
+    let scale = 1.0 / (1.0 + tan_sin.powi(2) + tan_cos.powi(2));
+    let f_axis = Array3d {
+        x: 1.0 - tan_sin.powi(2) + tan_cos.powi(2),
+        y: 2.0 * tan_sin * tan_cos,
+        z: -2.0 * tan_sin,
+    } * scale;
+    let g_axis = Array3d {
+        x: 2.0 * tan_sin * tan_cos,
+        y: 1.0 + tan_sin.powi(2) - tan_cos.powi(2),
+        z: 2.0 * tan_cos,
+    } * scale;
+
+    (f_axis, g_axis)
+}
+
+impl KeplerianElements {
+    /// Calculates the elements of a state.
+    ///
+    /// ### Arguments
+    /// * 'position' - Position relative to the central body in meters.
+    /// * 'velocity' - Velocity relative to the central body in m/s.
+    /// * 'mu' - Gravitational parameter of the central body in m^3/s^2.
+    ///
+    pub fn from_state(position: &Array3d, velocity: &Array3d, mu: f64) -> Self {
+        let r = position.dot(position).sqrt();
+        let momentum = position.cross(velocity);
+        let node = Array3d {
+            x: -momentum.y,
+            y: momentum.x,
+            z: 0.0,
+        };
+        let eccentricity_vector =
+            (velocity.cross(&momentum) * (1.0 / mu)) - (*position * (1.0 / r));
+
+        let eccentricity = eccentricity_vector.dot(&eccentricity_vector).sqrt();
+        let energy = velocity.dot(velocity) / 2.0 - mu / r;
+        let semi_major_axis_m = -mu / (2.0 * energy);
+        let inclination_rad = momentum.x.hypot(momentum.y).atan2(momentum.z);
+
+        let circular = eccentricity < SINGULARITY_TOLERANCE;
+        let equatorial =
+            inclination_rad < SINGULARITY_TOLERANCE || PI - inclination_rad < SINGULARITY_TOLERANCE;
+        let x_axis = Array3d {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        // Angles of the plane measured positive in the direction of motion
+        let in_direction_of_motion = |angle: f64, along: f64| {
+            if along < 0.0 {
+                2.0 * PI - angle
+            } else {
+                angle
+            }
+        };
+        let retrograde_sign = momentum.z.signum();
+
+        let (raan_rad, arg_periapsis_rad, true_anomaly_rad) = match (circular, equatorial) {
+            (false, false) => (
+                in_direction_of_motion(angle_between(&x_axis, &node), node.y),
+                in_direction_of_motion(
+                    angle_between(&node, &eccentricity_vector),
+                    eccentricity_vector.z,
+                ),
+                in_direction_of_motion(
+                    angle_between(&eccentricity_vector, position),
+                    position.dot(velocity),
+                ),
+            ),
+            (true, false) => (
+                in_direction_of_motion(angle_between(&x_axis, &node), node.y),
+                0.0,
+                // Argument of latitude
+                in_direction_of_motion(angle_between(&node, position), position.z),
+            ),
+            (false, true) => (
+                0.0,
+                // Longitude of periapsis
+                in_direction_of_motion(
+                    angle_between(&x_axis, &eccentricity_vector),
+                    eccentricity_vector.y * retrograde_sign,
+                ),
+                in_direction_of_motion(
+                    angle_between(&eccentricity_vector, position),
+                    position.dot(velocity),
+                ),
+            ),
+            (true, true) => (
+                0.0,
+                0.0,
+                // True longitude
+                in_direction_of_motion(
+                    angle_between(&x_axis, position),
+                    position.y * retrograde_sign,
+                ),
+            ),
+        };
+
+        KeplerianElements {
+            semi_major_axis_m,
+            eccentricity,
+            inclination_rad,
+            raan_rad: wrap_angle(raan_rad),
+            arg_periapsis_rad: wrap_angle(arg_periapsis_rad),
+            true_anomaly_rad: wrap_angle(true_anomaly_rad),
+        }
+    }
+
+    /// Calculates the state of the elements.
+    ///
+    /// ### Argument
+    /// * 'mu' - Gravitational parameter of the central body in m^3/s^2.
+    ///
+    /// ### Return
+    ///     The position and velocity relative to the central body.
+    ///
+    pub fn to_state(self, mu: f64) -> (Array3d, Array3d) {
+        let semi_latus_rectum = if self.semi_major_axis_m.is_infinite() {
+            // Parabolic orbits are only defined by their periapsis, not representable here
+            f64::NAN
+        } else {
+            self.semi_major_axis_m * (1.0 - self.eccentricity.powi(2))
+        };
+        let (sin_nu, cos_nu) = self.true_anomaly_rad.sin_cos();
+        let r = semi_latus_rectum / (1.0 + self.eccentricity * cos_nu);
+        let speed_scale = (mu / semi_latus_rectum).sqrt();
+
+        // Perifocal position and velocity
+        let position = Array3d {
+            x: r * cos_nu,
+            y: r * sin_nu,
+            z: 0.0,
+        };
+        let velocity = Array3d {
+            x: -speed_scale * sin_nu,
+            y: speed_scale * (self.eccentricity + cos_nu),
+            z: 0.0,
+        };
+
+        let inertial_from_perifocal = (Matrix3d::rot_z(self.arg_periapsis_rad)
+            * Matrix3d::rot_x(self.inclination_rad)
+            * Matrix3d::rot_z(self.raan_rad))
+        .transpose();
+
+        (
+            inertial_from_perifocal * position,
+            inertial_from_perifocal * velocity,
+        )
+    }
+
+    /// Mean anomaly of elliptic and hyperbolic orbits in radians.
+    pub fn mean_anomaly(&self) -> f64 {
+        let e = self.eccentricity;
+        let (sin_nu, cos_nu) = self.true_anomaly_rad.sin_cos();
+        if e < 1.0 {
+            let eccentric_anomaly = ((1.0 - e.powi(2)).sqrt() * sin_nu).atan2(e + cos_nu);
+            wrap_angle(eccentric_anomaly - e * eccentric_anomaly.sin())
+        } else {
+            let hyperbolic_anomaly =
+                ((e.powi(2) - 1.0).sqrt() * sin_nu / (1.0 + e * cos_nu)).asinh();
+            e * hyperbolic_anomaly.sinh() - hyperbolic_anomaly
+        }
+    }
+
+    /// Elements of an elliptic or hyperbolic orbit after a time of flight on the unperturbed
+    /// two-body orbit.
+    ///
+    /// ### Arguments
+    /// * 'mu' - Gravitational parameter of the central body.
+    /// * 'time_of_flight_s' - Time of flight, negative to propagate backwards.
+    ///
+    pub fn propagate(self, mu: f64, time_of_flight_s: f64) -> Self {
+        let mean_motion = (mu / self.semi_major_axis_m.abs().powi(3)).sqrt();
+        let mean_anomaly = self.mean_anomaly() + mean_motion * time_of_flight_s;
+
+        KeplerianElements {
+            true_anomaly_rad: Self::true_anomaly_from_mean(self.eccentricity, mean_anomaly),
+            ..self
+        }
+    }
+
+    /// True anomaly of an elliptic or hyperbolic orbit from its mean anomaly.
+    ///
+    /// ### Arguments
+    /// * 'eccentricity' - Eccentricity of the orbit.
+    /// * 'mean_anomaly' - Mean anomaly in radians.
+    ///
+    pub fn true_anomaly_from_mean(eccentricity: f64, mean_anomaly: f64) -> f64 {
+        let e = eccentricity;
+        if e < 1.0 {
+            let mean_anomaly = wrap_angle(mean_anomaly);
+            let mut eccentric_anomaly = if e < 0.8 { mean_anomaly } else { PI };
+            for _ in 0..KEPLER_MAX_ITERATIONS {
+                let delta = (eccentric_anomaly - e * eccentric_anomaly.sin() - mean_anomaly)
+                    / (1.0 - e * eccentric_anomaly.cos());
+                eccentric_anomaly -= delta;
+                if delta.abs() < KEPLER_TOLERANCE {
+                    break;
+                }
+            }
+            let (sin_ea, cos_ea) = eccentric_anomaly.sin_cos();
+            wrap_angle(((1.0 - e.powi(2)).sqrt() * sin_ea).atan2(cos_ea - e))
+        } else {
+            let mut hyperbolic_anomaly = (mean_anomaly / e).asinh();
+            for _ in 0..KEPLER_MAX_ITERATIONS {
+                let delta = (e * hyperbolic_anomaly.sinh() - hyperbolic_anomaly - mean_anomaly)
+                    / (e * hyperbolic_anomaly.cosh() - 1.0);
+                hyperbolic_anomaly -= delta;
+                if delta.abs() < KEPLER_TOLERANCE {
+                    break;
+                }
+            }
+            let (sinh_ha, cosh_ha) = (hyperbolic_anomaly.sinh(), hyperbolic_anomaly.cosh());
+            wrap_angle(((e.powi(2) - 1.0).sqrt() * sinh_ha).atan2(e - cosh_ha))
+        }
+    }
+}
+
+impl EquinoctialElements {
+    /// Calculates the elements of an elliptic state.
+    ///
+    /// ### Arguments
+    /// * 'position' - Position relative to the central body in meters.
+    /// * 'velocity' - Velocity relative to the central body in m/s.
+    /// * 'mu' - Gravitational parameter of the central body in m^3/s^2.
+    ///
+    pub fn from_state(position: &Array3d, velocity: &Array3d, mu: f64) -> Self {
+        let modified = ModifiedEquinoctialElements::from_state(position, velocity, mu);
+        let (h, k) = (modified.g, modified.f);
+        let semi_major_axis_m = modified.semi_latus_rectum_m / (1.0 - h.powi(2) - k.powi(2));
+
+        // Eccentric longitude from the position within the orbit plane
+        let (f_axis, g_axis) = equinoctial_axes(modified.k, modified.h);
+        let beta = 1.0 / (1.0 + (1.0 - h.powi(2) - k.powi(2)).sqrt());
+        let x = position.dot(&f_axis) / semi_major_axis_m + k;
+        let y = position.dot(&g_axis) / semi_major_axis_m + h;
+        // Solves x = (1 - h^2 beta) cos F + h k beta sin F and y = h k beta cos F + (1 - k^2 beta) sin F
+        let (a11, a12) = (1.0 - h.powi(2) * beta, h * k * beta);
+        let (a21, a22) = (h * k * beta, 1.0 - k.powi(2) * beta);
+        let cos_f = a22 * x - a12 * y;
+        let sin_f = a11 * y - a21 * x;
+        let eccentric_longitude = sin_f.atan2(cos_f);
+
+        EquinoctialElements {
+            semi_major_axis_m,
+            h,
+            k,
+            p: modified.k,
+            q: modified.h,
+            mean_longitude_rad: wrap_angle(
+                eccentric_longitude + h * eccentric_longitude.cos() - k * eccentric_longitude.sin(),
+            ),
+        }
+    }
+
+    /// Calculates the state of the elements.
+    ///
+    /// ### Argument
+    /// * 'mu' - Gravitational parameter of the central body in m^3/s^2.
+    ///
+    /// ### Return
+    ///     The position and velocity relative to the central body.
+    ///
+    pub fn to_state(self, mu: f64) -> (Array3d, Array3d) {
+        let (a, h, k) = (self.semi_major_axis_m, self.h, self.k);
+
+        // Kepler's equation in equinoctial form, mean_longitude = F + h cos F - k sin F
+        let mut eccentric_longitude = self.mean_longitude_rad;
+        for _ in 0..KEPLER_MAX_ITERATIONS {
+            let (sin_f, cos_f) = eccentric_longitude.sin_cos();
+            let delta = (eccentric_longitude + h * cos_f - k * sin_f - self.mean_longitude_rad)
+                / (1.0 - h * sin_f - k * cos_f);
+            eccentric_longitude -= delta;
+            if delta.abs() < KEPLER_TOLERANCE {
+                break;
+            }
+        }
+
+        let (sin_f, cos_f) = eccentric_longitude.sin_cos();
+        let beta = 1.0 / (1.0 + (1.0 - h.powi(2) - k.powi(2)).sqrt());
+        let mean_motion = (mu / a.powi(3)).sqrt();
+        let r = a * (1.0 - k * cos_f - h * sin_f);
+
+        let x = a * ((1.0 - h.powi(2) * beta) * cos_f + h * k * beta * sin_f - k);
+        let y = a * ((1.0 - k.powi(2) * beta) * sin_f + h * k * beta * cos_f - h);
+        let x_dot =
+            a.powi(2) * mean_motion / r * (h * k * beta * cos_f - (1.0 - h.powi(2) * beta) * sin_f);
+        let y_dot =
+            a.powi(2) * mean_motion / r * ((1.0 - k.powi(2) * beta) * cos_f - h * k * beta * sin_f);
+
+        let (f_axis, g_axis) = equinoctial_axes(self.p, self.q);
+        (f_axis * x + g_axis * y, f_axis * x_dot + g_axis * y_dot)
+    }
+}
+
+impl ModifiedEquinoctialElements {
+    /// Calculates the elements of a state.
+    ///
+    /// ### Arguments
+    /// * 'position' - Position relative to the central body in meters.
+    /// * 'velocity' - Velocity relative to the central body in m/s.
+    /// * 'mu' - Gravitational parameter of the central body in m^3/s^2.
+    ///
+    pub fn from_state(position: &Array3d, velocity: &Array3d, mu: f64) -> Self {
+        let r = position.dot(position).sqrt();
+        let momentum = position.cross(velocity);
+        let momentum_norm = momentum.dot(&momentum).sqrt();
+        let normal = momentum * (1.0 / momentum_norm);
+
+        let h = -normal.y / (1.0 + normal.z);
+        let k = normal.x / (1.0 + normal.z);
+        let eccentricity_vector =
+            (velocity.cross(&momentum) * (1.0 / mu)) - (*position * (1.0 / r));
+
+        let (f_axis, g_axis) = equinoctial_axes(k, h);
+
+        ModifiedEquinoctialElements {
+            semi_latus_rectum_m: momentum_norm.powi(2) / mu,
+            f: eccentricity_vector.dot(&f_axis),
+            g: eccentricity_vector.dot(&g_axis),
+            h,
+            k,
+            true_longitude_rad: wrap_angle(position.dot(&g_axis).atan2(position.dot(&f_axis))),
+        }
+    }
+
+    /// Calculates the state of the elements.
+    ///
+    /// ### Argument
+    /// * 'mu' - Gravitational parameter of the central body in m^3/s^2.
+    ///
+    /// ### Return
+    ///     The position and velocity relative to the central body.
+    ///
+    #[allow(dead_code)]
+    pub fn to_state(self, mu: f64) -> (Array3d, Array3d) {
+        let (sin_l, cos_l) = self.true_longitude_rad.sin_cos();
+        let r = self.semi_latus_rectum_m / (1.0 + self.f * cos_l + self.g * sin_l);
+        let speed_scale = (mu / self.semi_latus_rectum_m).sqrt();
+
+        let (f_axis, g_axis) = equinoctial_axes(self.k, self.h);
+        (
+            (f_axis * cos_l + g_axis * sin_l) * r,
+            (f_axis * -(self.g + sin_l) + g_axis * (self.f + cos_l)) * speed_scale,
+        )
+    }
+}
+
+impl KeplerianElements {
+    /// Brouwer-Lyddane mean elements of osculating elliptic elements in the J2 field of the
+    /// central body, with the short and long period terms removed. The elements have to be
+    /// referred to the equator of the central body. The theory is singular for equatorial orbits
+    /// and at the critical inclinations of 63.4 and 116.6 degrees.
+    ///
+    /// ### Arguments
+    /// * 'j2' - Second zonal harmonic of the central body.
+    /// * 'body_radius_m' - Equatorial radius the harmonic is referred to.
+    ///
+    /// ### Return
+    ///     The mean elements, the true anomaly is the one of the mean anomaly. None for unbound
+    ///     orbits and when the iteration does not converge to a bound orbit above the surface,
+    ///     as happens close to the singularities.
+    ///
+    pub fn brouwer_lyddane_mean(self, j2: f64, body_radius_m: f64) -> Option<Self> {
+        if self.eccentricity >= 1.0 {
+            return None;
+        }
+
+        let gamma2 =
+            |elements: &Self| j2 / 2.0 * (body_radius_m / elements.semi_major_axis_m).powi(2);
+        let target = self.nonsingular();
+
+        // The first order inverse of the mean to osculating transformation, refined by correcting
+        // the mean elements with the difference of their osculating elements to the target
+        let mut mean = brouwer_lyddane_transform(&self, -gamma2(&self));
+        for _ in 0..MEAN_ELEMENTS_ITERATIONS {
+            let residual = brouwer_lyddane_transform(&mean, gamma2(&mean)).nonsingular();
+            let mut corrected = mean.nonsingular();
+            for (idx, value) in corrected.iter_mut().enumerate() {
+                let difference = target[idx] - residual[idx];
+                *value += if NONSINGULAR_ANGLES.contains(&idx) {
+                    (difference + PI).rem_euclid(2.0 * PI) - PI
+                } else {
+                    difference
+                };
+            }
+            mean = Self::from_nonsingular(&corrected);
+        }
+
+        Some(mean).filter(|mean| {
+            mean.semi_major_axis_m.is_finite()
+                && mean.raan_rad.is_finite()
+                && (0.0..=PI).contains(&mean.inclination_rad)
+                && (0.0..1.0).contains(&mean.eccentricity)
+                && mean.semi_major_axis_m * (1.0 - mean.eccentricity) > body_radius_m
+        })
+    }
+
+    /// Elements nonsingular for circular orbits: a, e cos(w), e sin(w), i, raan and M + w.
+    fn nonsingular(&self) -> [f64; 6] {
+        let (sin_w, cos_w) = self.arg_periapsis_rad.sin_cos();
+        [
+            self.semi_major_axis_m,
+            self.eccentricity * cos_w,
+            self.eccentricity * sin_w,
+            self.inclination_rad,
+            self.raan_rad,
+            self.mean_anomaly() + self.arg_periapsis_rad,
+        ]
+    }
+
+    fn from_nonsingular(elements: &[f64; 6]) -> Self {
+        let eccentricity = elements[1].hypot(elements[2]);
+        let arg_periapsis_rad = wrap_angle(elements[2].atan2(elements[1]));
+
+        KeplerianElements {
+            semi_major_axis_m: elements[0],
+            eccentricity,
+            inclination_rad: elements[3],
+            raan_rad: wrap_angle(elements[4]),
+            arg_periapsis_rad,
+            true_anomaly_rad: Self::true_anomaly_from_mean(
+                eccentricity,
+                elements[5] - arg_periapsis_rad,
+            ),
+        }
+    }
+}
+
+/// Brouwer-Lyddane transformation from mean to osculating elements to first order in J2 after
+/// Schaub and Junkins (Analytical Mechanics of Space Systems, appendix F). With the sign of
+/// gamma2 reversed it maps osculating to mean elements to first order.
+///
+/// ### Arguments
+/// * 'elements' - The elements to transform.
+/// * 'gamma2' - J2 / 2 (R / a)^2 of the elements, negative for the inverse transformation.
+///
+fn brouwer_lyddane_transform(elements: &KeplerianElements, gamma2: f64) -> KeplerianElements {
+    let (a, e, i) = (
+        elements.semi_major_axis_m,
+        elements.eccentricity,
+        elements.inclination_rad,
+    );
+    let (w, raan, f) = (
+        elements.arg_periapsis_rad,
+        elements.raan_rad,
+        elements.true_anomaly_rad,
+    );
+    let m = elements.mean_anomaly();
+
+    let eta = (1.0 - e.powi(2)).sqrt();
+    let gamma2_prime = gamma2 / eta.powi(4);
+    let a_r = (1.0 + e * f.cos()) / eta.powi(2);
+    let cos_i = i.cos();
+    let cos2_i = cos_i.powi(2);
+    let critical = 1.0 - 5.0 * cos2_i;
+    // Difference of true and mean anomaly, the equation of the center
+    let center = (f - m + PI).rem_euclid(2.0 * PI) - PI + e * f.sin();
+    let periodic = 3.0 * (2.0 * w + 2.0 * f).cos()
+        + 3.0 * e * (2.0 * w + f).cos()
+        + e * (2.0 * w + 3.0 * f).cos();
+    let periodic_sin = 3.0 * (2.0 * w + 2.0 * f).sin()
+        + 3.0 * e * (2.0 * w + f).sin()
+        + e * (2.0 * w + 3.0 * f).sin();
+
+    let a_osc = a + a
+        * gamma2
+        * ((3.0 * cos2_i - 1.0) * (a_r.powi(3) - 1.0 / eta.powi(3))
+            + 3.0 * (1.0 - cos2_i) * a_r.powi(3) * (2.0 * w + 2.0 * f).cos());
+
+    let de1 = gamma2_prime / 8.0
+        * e
+        * eta.powi(2)
+        * (1.0 - 11.0 * cos2_i - 40.0 * cos2_i.powi(2) / critical)
+        * (2.0 * w).cos();
+    let radial_terms = e + 3.0 * f.cos() + 3.0 * e * f.cos().powi(2) + e.powi(2) * f.cos().powi(3);
+    let de = de1
+        + eta.powi(2) / 2.0
+            * (gamma2
+                * ((3.0 * cos2_i - 1.0) / eta.powi(6)
+                    * (e * eta
+                        + e / (1.0 + eta)
+                        + 3.0 * f.cos()
+                        + 3.0 * e * f.cos().powi(2)
+                        + e.powi(2) * f.cos().powi(3))
+                    + 3.0 * (1.0 - cos2_i) / eta.powi(6)
+                        * radial_terms
+                        * (2.0 * w + 2.0 * f).cos())
+                - gamma2_prime
+                    * (1.0 - cos2_i)
+                    * (3.0 * (2.0 * w + f).cos() + (2.0 * w + 3.0 * f).cos()));
+
+    let di = -e * de1 / (eta.powi(2) * i.tan())
+        + gamma2_prime / 2.0 * cos_i * (1.0 - cos2_i).sqrt() * periodic;
+
+    let long_period_node = gamma2_prime / 8.0
+        * e.powi(2)
+        * cos_i
+        * (11.0 + 80.0 * cos2_i / critical + 200.0 * cos2_i.powi(2) / critical.powi(2))
+        * (2.0 * w).sin();
+    let short_period_node = gamma2_prime / 2.0 * cos_i * (6.0 * center - periodic_sin);
+
+    let sum = m
+        + w
+        + raan
+        + gamma2_prime / 8.0
+            * eta.powi(3)
+            * (1.0 - 11.0 * cos2_i - 40.0 * cos2_i.powi(2) / critical)
+            * (2.0 * w).sin()
+        - gamma2_prime / 16.0
+            * (2.0 + e.powi(2)
+                - 11.0 * (2.0 + 3.0 * e.powi(2)) * cos2_i
+                - 40.0 * (2.0 + 5.0 * e.powi(2)) * cos2_i.powi(2) / critical
+                - 400.0 * e.powi(2) * cos2_i.powi(3) / critical.powi(2))
+            * (2.0 * w).sin()
+        + gamma2_prime / 4.0 * (-6.0 * critical * center + (3.0 - 5.0 * cos2_i) * periodic_sin)
+        - long_period_node
+        - short_period_node;
+
+    let e_dm = gamma2_prime / 8.0
+        * e
+        * eta.powi(3)
+        * (1.0 - 11.0 * cos2_i - 40.0 * cos2_i.powi(2) / critical)
+        * (2.0 * w).sin()
+        - gamma2_prime / 4.0
+            * eta.powi(3)
+            * (2.0 * (3.0 * cos2_i - 1.0) * (a_r.powi(2) * eta.powi(2) + a_r + 1.0) * f.sin()
+                + 3.0
+                    * (1.0 - cos2_i)
+                    * ((-a_r.powi(2) * eta.powi(2) - a_r + 1.0) * (2.0 * w + f).sin()
+                        + (a_r.powi(2) * eta.powi(2) + a_r + 1.0 / 3.0)
+                            * (2.0 * w + 3.0 * f).sin()));
+    let d_raan = -long_period_node - short_period_node;
+
+    let d1 = (e + de) * m.sin() + e_dm * m.cos();
+    let d2 = (e + de) * m.cos() - e_dm * m.sin();
+    let m_osc = d1.atan2(d2);
+    let e_osc = d1.hypot(d2);
+
+    let (sin_half_i, cos_half_i) = (i / 2.0).sin_cos();
+    let d3 = (sin_half_i + cos_half_i * di / 2.0) * raan.sin() + sin_half_i * d_raan * raan.cos();
+    let d4 = (sin_half_i + cos_half_i * di / 2.0) * raan.cos() - sin_half_i * d_raan * raan.sin();
+    let raan_osc = d3.atan2(d4);
+    let i_osc = 2.0 * d3.hypot(d4).min(1.0).asin();
+
+    let w_osc = sum - m_osc - raan_osc;
+
+    KeplerianElements {
+        semi_major_axis_m: a_osc,
+        eccentricity: e_osc,
+        inclination_rad: i_osc,
+        raan_rad: wrap_angle(raan_osc),
+        arg_periapsis_rad: wrap_angle(w_osc),
+        true_anomaly_rad: KeplerianElements::true_anomaly_from_mean(e_osc, m_osc),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MU: f64 = 3.986004418e14;
+    const TOLERANCE: f64 = 1e-9;
+
+    fn elements(
+        a: f64,
+        e: f64,
+        i_deg: f64,
+        raan_deg: f64,
+        w_deg: f64,
+        nu_deg: f64,
+    ) -> KeplerianElements {
+        KeplerianElements {
+            semi_major_axis_m: a,
+            eccentricity: e,
+            inclination_rad: i_deg.to_radians(),
+            raan_rad: raan_deg.to_radians(),
+            arg_periapsis_rad: w_deg.to_radians(),
+            true_anomaly_rad: nu_deg.to_radians(),
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64, scale: f64, what: &str) {
+        assert!(
+            (actual - expected).abs() <= TOLERANCE * scale,
+            "{}: {} != {}",
+            what,
+            actual,
+            expected
+        );
+    }
+
+    fn assert_angle_close(actual: f64, expected: f64, what: &str) {
+        let difference = (actual - expected + PI).rem_euclid(2.0 * PI) - PI;
+        assert_close(difference, 0.0, 1.0, what);
+    }
+
+    fn assert_state_close(actual: (Array3d, Array3d), expected: (Array3d, Array3d)) {
+        let position_scale = expected.0.dot(&expected.0).sqrt();
+        let velocity_scale = expected.1.dot(&expected.1).sqrt();
+        let pairs = [
+            (actual.0.x, expected.0.x, position_scale, "x"),
+            (actual.0.y, expected.0.y, position_scale, "y"),
+            (actual.0.z, expected.0.z, position_scale, "z"),
+            (actual.1.x, expected.1.x, velocity_scale, "vx"),
+            (actual.1.y, expected.1.y, velocity_scale, "vy"),
+            (actual.1.z, expected.1.z, velocity_scale, "vz"),
+        ];
+        for &(actual, expected, scale, what) in pairs.iter() {
+            assert_close(actual, expected, scale, what);
+        }
+    }
+
+    /// Converts the elements to a state and back, and the state to all element sets and back.
+    fn assert_round_trip(expected: KeplerianElements, elliptic: bool) {
+        let state = expected.to_state(MU);
+        let actual = KeplerianElements::from_state(&state.0, &state.1, MU);
+
+        assert_close(
+            actual.semi_major_axis_m,
+            expected.semi_major_axis_m,
+            expected.semi_major_axis_m.abs(),
+            "semi-major axis",
+        );
+        assert_close(
+            actual.eccentricity,
+            expected.eccentricity,
+            1.0,
+            "eccentricity",
+        );
+        assert_close(
+            actual.inclination_rad,
+            expected.inclination_rad,
+            1.0,
+            "inclination",
+        );
+        assert_angle_close(actual.raan_rad, expected.raan_rad, "raan");
+        assert_angle_close(
+            actual.arg_periapsis_rad,
+            expected.arg_periapsis_rad,
+            "periapsis",
+        );
+        assert_angle_close(
+            actual.true_anomaly_rad,
+            expected.true_anomaly_rad,
+            "true anomaly",
+        );
+        assert_state_close(actual.to_state(MU), state);
+
+        let modified = ModifiedEquinoctialElements::from_state(&state.0, &state.1, MU);
+        assert_state_close(modified.to_state(MU), state);
+
+        if elliptic {
+            let equinoctial = EquinoctialElements::from_state(&state.0, &state.1, MU);
+            assert_state_close(equinoctial.to_state(MU), state);
+        }
+    }
+
+    #[test]
+    fn round_trip_inclined_elliptic() {
+        assert_round_trip(elements(7.0e6, 0.1, 51.6, 120.0, 45.0, 200.0), true);
+    }
+
+    #[test]
+    fn round_trip_circular() {
+        // The true anomaly is the argument of latitude
+        assert_round_trip(elements(7.0e6, 0.0, 51.6, 120.0, 0.0, 75.0), true);
+    }
+
+    #[test]
+    fn round_trip_equatorial() {
+        // The argument of periapsis is the longitude of periapsis
+        assert_round_trip(elements(4.2e7, 0.3, 0.0, 0.0, 80.0, 300.0), true);
+    }
+
+    #[test]
+    fn round_trip_circular_equatorial() {
+        // The true anomaly is the true longitude
+        assert_round_trip(elements(4.2164e7, 0.0, 0.0, 0.0, 0.0, 135.0), true);
+    }
+
+    #[test]
+    fn round_trip_retrograde() {
+        assert_round_trip(elements(7.0e6, 0.05, 150.0, 300.0, 10.0, 20.0), true);
+    }
+
+    #[test]
+    fn round_trip_retrograde_equatorial() {
+        // Singular for the equinoctial sets
+        let expected = elements(7.0e6, 0.2, 180.0, 0.0, 80.0, 40.0);
+        let state = expected.to_state(MU);
+        let actual = KeplerianElements::from_state(&state.0, &state.1, MU);
+
+        assert_close(actual.inclination_rad, PI, 1.0, "inclination");
+        assert_angle_close(actual.raan_rad, 0.0, "raan");
+        assert_angle_close(
+            actual.arg_periapsis_rad,
+            expected.arg_periapsis_rad,
+            "periapsis",
+        );
+        assert_angle_close(
+            actual.true_anomaly_rad,
+            expected.true_anomaly_rad,
+            "true anomaly",
+        );
+        assert_state_close(actual.to_state(MU), state);
+    }
+
+    #[test]
+    fn round_trip_retrograde_circular_equatorial() {
+        let expected = elements(7.0e6, 0.0, 180.0, 0.0, 0.0, 250.0);
+        let state = expected.to_state(MU);
+        let actual = KeplerianElements::from_state(&state.0, &state.1, MU);
+
+        assert_angle_close(
+            actual.true_anomaly_rad,
+            expected.true_anomaly_rad,
+            "true longitude",
+        );
+        assert_state_close(actual.to_state(MU), state);
+    }
+
+    #[test]
+    fn round_trip_hyperbolic() {
+        assert_round_trip(elements(-2.0e7, 1.5, 30.0, 60.0, 90.0, 40.0), false);
+    }
+
+    #[test]
+    fn mean_anomaly_round_trip() {
+        for &e in [0.0, 0.3, 0.95, 1.5].iter() {
+            let orbit = elements(7.0e6, e, 10.0, 0.0, 0.0, 70.0);
+            assert_angle_close(
+                KeplerianElements::true_anomaly_from_mean(e, orbit.mean_anomaly()),
+                orbit.true_anomaly_rad,
+                "true anomaly",
+            );
+        }
+    }
+
+    const J2: f64 = 1.08262668e-3;
+    const EARTH_RADIUS_M: f64 = 6378137.0;
+
+    /// Acceleration of the central body with its J2 term, the pole along the z axis.
+    fn j2_acceleration(position: &Array3d) -> Array3d {
+        let r2 = position.dot(position);
+        let r = r2.sqrt();
+        let z2_ratio = position.z.powi(2) / r2;
+        let j2_scale = 1.5 * J2 * (EARTH_RADIUS_M.powi(2) / r2);
+        let scale = -MU / (r2 * r);
+        Array3d {
+            x: scale * position.x * (1.0 + j2_scale * (1.0 - 5.0 * z2_ratio)),
+            y: scale * position.y * (1.0 + j2_scale * (1.0 - 5.0 * z2_ratio)),
+            z: scale * position.z * (1.0 + j2_scale * (3.0 - 5.0 * z2_ratio)),
+        }
+    }
+
+    /// Propagates a state in the J2 field with a fourth order Runge-Kutta step.
+    fn j2_step(state: (Array3d, Array3d), step_s: f64) -> (Array3d, Array3d) {
+        let (r0, v0) = state;
+        let k1 = (v0, j2_acceleration(&r0));
+        let r1 = r0 + k1.0 * (step_s / 2.0);
+        let k2 = (v0 + k1.1 * (step_s / 2.0), j2_acceleration(&r1));
+        let r2 = r0 + k2.0 * (step_s / 2.0);
+        let k3 = (v0 + k2.1 * (step_s / 2.0), j2_acceleration(&r2));
+        let r3 = r0 + k3.0 * step_s;
+        let k4 = (v0 + k3.1 * step_s, j2_acceleration(&r3));
+        (
+            r0 + (k1.0 + k2.0 * 2.0 + k3.0 * 2.0 + k4.0) * (step_s / 6.0),
+            v0 + (k1.1 + k2.1 * 2.0 + k3.1 * 2.0 + k4.1) * (step_s / 6.0),
+        )
+    }
+
+    #[test]
+    fn brouwer_lyddane_mean_constant_in_j2_field() {
+        // Over one orbit the osculating semi-major axis and inclination vary by kilometers and
+        // hundredths of a degree, the mean ones only by the second order terms
+        let orbit = elements(7.0e6, 0.01, 51.6, 30.0, 40.0, 50.0);
+        let period_s = 2.0 * PI * (orbit.semi_major_axis_m.powi(3) / MU).sqrt();
+        let steps = 600;
+        let mut state = orbit.to_state(MU);
+        let mut osculating = Vec::new();
+        let mut mean = Vec::new();
+        for _ in 0..=steps {
+            let elements = KeplerianElements::from_state(&state.0, &state.1, MU);
+            osculating.push(elements);
+            mean.push(elements.brouwer_lyddane_mean(J2, EARTH_RADIUS_M).unwrap());
+            state = j2_step(state, period_s / steps as f64);
+        }
+
+        let spread = |elements: &[KeplerianElements], value: fn(&KeplerianElements) -> f64| {
+            let values = elements.iter().map(value);
+            values.clone().fold(f64::MIN, f64::max) - values.fold(f64::MAX, f64::min)
+        };
+        assert!(spread(&osculating, |elements| elements.semi_major_axis_m) > 1.0e3);
+        assert!(spread(&osculating, |elements| elements.inclination_rad) > 1.0e-4);
+        assert!(spread(&mean, |elements| elements.semi_major_axis_m) < 20.0);
+        assert!(spread(&mean, |elements| elements.inclination_rad) < 2.0e-6);
+    }
+
+    #[test]
+    fn brouwer_lyddane_mean_circular() {
+        // The nonsingular iteration holds for circular orbits, their mean eccentricity is small
+        let mean = elements(7.0e6, 0.0, 51.6, 30.0, 0.0, 50.0)
+            .brouwer_lyddane_mean(J2, EARTH_RADIUS_M)
+            .unwrap();
+        assert!((mean.semi_major_axis_m - 7.0e6).abs() < 1.0e4);
+        assert!(mean.eccentricity < 1.0e-3);
+        assert!((mean.inclination_rad - 51.6f64.to_radians()).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn brouwer_lyddane_mean_rejected() {
+        let mean = |orbit: KeplerianElements| orbit.brouwer_lyddane_mean(J2, EARTH_RADIUS_M);
+        // Singular for equatorial orbits, circular or not
+        assert!(mean(elements(7.0e6, 0.01, 0.0, 0.0, 40.0, 50.0)).is_none());
+        assert!(mean(elements(7.0e6, 0.0, 0.0, 0.0, 0.0, 50.0)).is_none());
+        // Unbound or below the surface
+        assert!(mean(elements(-2.0e7, 1.5, 30.0, 60.0, 90.0, 40.0)).is_none());
+        assert!(mean(elements(7.0e6, 0.2, 51.6, 30.0, 40.0, 50.0)).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! Reader for the IERS Earth orientation parameters.
+//!
+//! Reads the daily values of the `finals` files (e.g. `finals.all` or `finals2000A.data`, see
+//! https://datacenter.iers.org/versionMetadata.php?filename=latestVersionMeta/9_FINALS.ALL_IAU2000_V2013_019.txt
+//! for the fixed column layout). Bulletin A values are used, including the predictions at the
+//! end of the file, and interpolated linearly between the daily entries.
+//!
+//! Both series share the layout but differ in their nutation columns. The IAU-1980 files
+//! (`finals.*`) hold the corrections dpsi and deps to the IAU-1980 nutation used by POSE. The
+//! `finals2000A.*` files hold the celestial pole offsets dX and dY relative to the IAU-2000A
+//! model, which do not apply to the IAU-1980 nutation, so only their polar motion and UT1-UTC
+//! are used and the nutation corrections are zero.
+
+use frames::{EopCorrections, ARCSEC_TO_RAD};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use time;
+
+// Byte ranges of the Bulletin A columns of a finals line
+const COLUMN_MJD: (usize, usize) = (7, 15);
+const COLUMN_X_POLE: (usize, usize) = (18, 27);
+const COLUMN_Y_POLE: (usize, usize) = (37, 46);
+const COLUMN_UT1_UTC: (usize, usize) = (58, 68);
+const COLUMN_DPSI: (usize, usize) = (97, 106); // dX in the finals2000A files
+const COLUMN_DEPS: (usize, usize) = (116, 125); // dY in the finals2000A files
+const MAS_TO_RAD: f64 = ARCSEC_TO_RAD / 1000.0;
+
+/// Earth orientation parameters of a single day.
+struct EopRecord {
+    x_pole_rad: f64,
+    y_pole_rad: f64,
+    ut1_minus_tai_s: f64, // Continuous across leap seconds, unlike UT1-UTC
+    dpsi_rad: f64,
+    deps_rad: f64,
+}
+
+/// Daily Earth orientation parameters loaded from an IERS file.
+pub struct EopTable {
+    pub start_mjd: f64, // Modified Julian date (UTC) of the first entry
+    records: Vec<EopRecord>,
+}
+
+/// Reads a fixed width column of a line, none if the line is too short or the column blank.
+fn column(line: &str, range: (usize, usize)) -> Option<&str> {
+    let value = line.get(range.0..range.1.min(line.len()))?.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+impl EopTable {
+    /// Loads a finals file, IAU-1980 unless the file name marks it as finals2000A. Entries after
+    /// the last one with polar motion and UT1-UTC are ignored, missing nutation corrections are
+    /// taken as zero.
+    ///
+    /// ### Argument
+    /// * 'path' - Path to the file.
+    ///
+    /// ### Return
+    ///     The table or an error if the file cannot be read or holds no consecutive days.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let iau_1980 = !path
+            .as_ref()
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().contains("2000A"));
+        let contents = fs::read_to_string(path)?;
+
+        let mut start_mjd = None;
+        let mut records = Vec::new();
+        for (line_idx, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parse = |range: (usize, usize)| -> Result<Option<f64>, Box<dyn Error>> {
+                match column(line, range) {
+                    Some(value) => Ok(Some(value.parse::<f64>().map_err(|_| {
+                        format!(
+                            "Invalid value '{}' on line {} of the EOP file.",
+                            value,
+                            line_idx + 1
+                        )
+                    })?)),
+                    None => Ok(None),
+                }
+            };
+
+            let nutation = |range: (usize, usize)| -> Result<f64, Box<dyn Error>> {
+                if iau_1980 {
+                    Ok(parse(range)?.unwrap_or(0.0) * MAS_TO_RAD)
+                } else {
+                    Ok(0.0)
+                }
+            };
+
+            let mjd = parse(COLUMN_MJD)?.ok_or(format!(
+                "Missing MJD on line {} of the EOP file.",
+                line_idx + 1
+            ))?;
+            let (x_pole, y_pole, ut1_utc) = match (
+                parse(COLUMN_X_POLE)?,
+                parse(COLUMN_Y_POLE)?,
+                parse(COLUMN_UT1_UTC)?,
+            ) {
+                (Some(x_pole), Some(y_pole), Some(ut1_utc)) => (x_pole, y_pole, ut1_utc),
+                _ => break,
+            };
+
+            let expected_mjd = start_mjd.unwrap_or(mjd) + records.len() as f64;
+            if mjd != expected_mjd {
+                return Err(
+                    format!("EOP file skips from MJD {} to {}.", expected_mjd - 1.0, mjd).into(),
+                );
+            }
+            start_mjd.get_or_insert(mjd);
+
+            records.push(EopRecord {
+                x_pole_rad: x_pole * ARCSEC_TO_RAD,
+                y_pole_rad: y_pole * ARCSEC_TO_RAD,
+                ut1_minus_tai_s: ut1_utc - time::tai_minus_utc(mjd as i64),
+                dpsi_rad: nutation(COLUMN_DPSI)?,
+                deps_rad: nutation(COLUMN_DEPS)?,
+            });
+        }
+
+        match start_mjd {
+            Some(start_mjd) if records.len() >= 2 => Ok(EopTable { start_mjd, records }),
+            _ => Err("EOP file holds less than two days of polar motion and UT1-UTC.".into()),
+        }
+    }
+
+    /// Modified Julian date (UTC) of the last entry.
+    pub fn end_mjd(&self) -> f64 {
+        self.start_mjd + (self.records.len() - 1) as f64
+    }
+
+    /// Checks that the table covers a span of dates.
+    ///
+    /// ### Arguments
+    /// * 'first_mjd' - First modified Julian date (UTC) evaluated.
+    /// * 'last_mjd' - Last modified Julian date (UTC) evaluated.
+    ///
+    /// ### Return
+    ///     An error stating the span of the table if the dates are not covered.
+    ///
+    pub fn check_span(&self, first_mjd: f64, last_mjd: f64) -> Result<(), String> {
+        if first_mjd < self.start_mjd || last_mjd > self.end_mjd() {
+            return Err(format!(
+                "The simulation from MJD {:.3} to {:.3} is outside of the EOP table span (MJD {:.0} to {:.0}), the values of its first and last days are held outside of it.",
+                first_mjd,
+                last_mjd,
+                self.start_mjd,
+                self.end_mjd()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Interpolates the corrections at an epoch.
+    ///
+    /// ### Argument
+    /// * 'epoch' - The epoch of the corrections.
+    ///
+    /// ### Return
+    ///     The corrections, those of the first or last day of the table outside of the days it
+    ///     covers.
+    ///
+    pub fn corrections(&self, epoch: &time::Epoch) -> EopCorrections {
+        let mjd = epoch.mjd_utc();
+        let offset = (mjd - self.start_mjd).clamp(0.0, (self.records.len() - 1) as f64);
+
+        let idx = (offset.floor() as usize).min(self.records.len() - 2);
+        let fraction = offset - idx as f64;
+        let (before, after) = (&self.records[idx], &self.records[idx + 1]);
+        let lerp = |value: fn(&EopRecord) -> f64| {
+            value(before) + (value(after) - value(before)) * fraction
+        };
+
+        EopCorrections {
+            ut1_minus_utc_s: lerp(|record| record.ut1_minus_tai_s)
+                + time::tai_minus_utc(mjd.floor() as i64),
+            x_pole_rad: lerp(|record| record.x_pole_rad),
+            y_pole_rad: lerp(|record| record.y_pole_rad),
+            dpsi_rad: lerp(|record| record.dpsi_rad),
+            deps_rad: lerp(|record| record.deps_rad),
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! Reader for the JPL Development Ephemeris (DE4xx) files.
+//!
+//! Supports both the binary files distributed by JPL (e.g. `linux_p1550p2650.440`) and the ASCII
+//! distribution (`header.440` plus one or more `ascpXXXXX.440` data files). Positions are
+//! evaluated from the Chebyshev coefficients stored in each record for any epoch covered by the
+//! loaded records. See https://ssd.jpl.nasa.gov/planets/eph_export.html for the file layout.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// Byte offsets of the fields in the first record of a binary ephemeris file
+const TITLE_BYTES: usize = 3 * 84;
+const HEADER_CONSTANT_NAMES: usize = 400;
+const CONSTANT_NAME_BYTES: usize = 6;
+const OFFSET_SPAN: usize = TITLE_BYTES + HEADER_CONSTANT_NAMES * CONSTANT_NAME_BYTES;
+const OFFSET_EMRAT: usize = OFFSET_SPAN + 3 * 8 + 4 + 8; // Skips NCON and AU
+const OFFSET_IPT: usize = OFFSET_EMRAT + 8;
+const OFFSET_NUMDE: usize = OFFSET_IPT + 12 * 3 * 4;
+const OFFSET_LPT: usize = OFFSET_NUMDE + 4;
+
+// Number of coefficient blocks in the pointer table (11 bodies, nutations and librations)
+const POINTER_COUNT: usize = 13;
+const NUTATION_INDEX: usize = 11;
+
+/// Julian date of 0/Jan/2000 00:00 (1999-12-31 00:00), the origin of the simulation day count
+/// expected by the analytic solar object models.
+pub const JD_DAY_ORIGIN: f64 = 2451543.5;
+
+/// Bodies of a JPL development ephemeris evaluated by the simulation. The discriminant matches
+/// the index of the body within the coefficient pointer table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EphemerisTarget {
+    EarthMoonBarycenter = 2,
+    Mars = 3,
+    Moon = 9, // Relative to the Earth
+    Sun = 10,
+}
+
+/// A JPL development ephemeris loaded into memory. Positions are ICRF equatorial kilometers
+/// relative to the solar system barycenter, except for the Moon which is geocentric.
+pub struct JplEphemeris {
+    pub de_number: u32,
+    pub start_jd: f64,      // First Julian date (TDB) covered by the loaded records
+    pub end_jd: f64,        // Last Julian date (TDB) covered by the loaded records
+    pub interval_days: f64, // Span of a single record in days
+    pub emrat: f64,         // Earth-Moon mass ratio
+    pointers: [[usize; 3]; POINTER_COUNT], // (offset, coefficients, sub-intervals) per block
+    ncoeff: usize,          // Number of doubles in a record
+    records: Vec<f64>,      // All records back to back
+}
+
+/// Evaluates a Chebyshev series using Clenshaw's recurrence.
+///
+/// ### Arguments
+/// * 'coeffs' - The coefficients of the series, lowest order first.
+/// * 'tc' - The normalized time within the interval of the series, [-1, 1].
+///
+/// ### Return
+///     The value of the series at 'tc'.
+///
+pub fn chebyshev(coeffs: &[f64], tc: f64) -> f64 {
+    let mut b_1 = 0f64;
+    let mut b_2 = 0f64;
+
+    for coeff in coeffs.iter().skip(1).rev() {
+        let b_0 = 2f64 * tc * b_1 - b_2 + coeff;
+        b_2 = b_1;
+        b_1 = b_0;
+    }
+
+    match coeffs.first() {
+        Some(c_0) => tc * b_1 - b_2 + c_0,
+        None => 0f64,
+    }
+}
+
+/// Chebyshev approximation of a three dimensional function over a fixed interval.
+pub struct ChebyshevFit {
+    start: f64,
+    end: f64,
+    coeffs: [Vec<f64>; 3],
+}
+
+impl ChebyshevFit {
+    /// Fits the provided function by sampling it at the Chebyshev nodes of the interval.
+    ///
+    /// ### Arguments
+    /// * 'start' - Start of the interval.
+    /// * 'end' - End of the interval.
+    /// * 'degree' - Degree of the fitted series.
+    /// * 'func' - The function to approximate, failing if it cannot be evaluated at a node.
+    ///
+    /// ### Return
+    ///     The fitted series for each of the three components or the first failure of 'func'.
+    ///
+    pub fn new<E, F: Fn(f64) -> Result<[f64; 3], E>>(
+        start: f64,
+        end: f64,
+        degree: usize,
+        func: F,
+    ) -> Result<Self, E> {
+        let node_count = degree + 1;
+        let half_span = (end - start) / 2f64;
+        let mid = (end + start) / 2f64;
+
+        let angles: Vec<f64> = (0..node_count)
+            .map(|k| std::f64::consts::PI * (k as f64 + 0.5) / node_count as f64)
+            .collect();
+        let samples: Vec<[f64; 3]> = angles
+            .iter()
+            .map(|angle| func(mid + half_span * angle.cos()))
+            .collect::<Result<_, _>>()?;
+
+        let mut coeffs = [vec![0f64; node_count], vec![0f64; node_count], vec![0f64; node_count]];
+        for (component, component_coeffs) in coeffs.iter_mut().enumerate() {
+            for (order, coeff) in component_coeffs.iter_mut().enumerate() {
+                let sum: f64 = angles
+                    .iter()
+                    .zip(samples.iter())
+                    .map(|(angle, sample)| sample[component] * (order as f64 * angle).cos())
+                    .sum();
+                *coeff = 2f64 * sum / node_count as f64;
+            }
+            component_coeffs[0] /= 2f64;
+        }
+
+        Ok(ChebyshevFit { start, end, coeffs })
+    }
+
+    /// Evaluates the fit, values outside of the fitted interval are extrapolated.
+    pub fn evaluate(&self, value: f64) -> [f64; 3] {
+        let tc = (2f64 * value - self.start - self.end) / (self.end - self.start);
+
+        [
+            chebyshev(&self.coeffs[0], tc),
+            chebyshev(&self.coeffs[1], tc),
+            chebyshev(&self.coeffs[2], tc),
+        ]
+    }
+}
+
+impl JplEphemeris {
+    /// Loads an ephemeris from the provided files. The first file is read as a JPL binary
+    /// ephemeris unless it is an ASCII header (starts with 'KSIZE='), in which case the remaining
+    /// files are read as its ASCII data files.
+    ///
+    /// ### Argument
+    /// * 'paths' - The binary ephemeris, or the ASCII header followed by its data files.
+    ///
+    /// ### Return
+    ///      The loaded ephemeris or an error describing why the files could not be read.
+    ///
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Self, Box<dyn Error>> {
+        let first = match paths.first() {
+            Some(path) => path,
+            None => return Err("No ephemeris file provided.".into()),
+        };
+
+        let bytes = fs::read(first)?;
+        if bytes.trim_ascii_start().starts_with(b"KSIZE=") {
+            Self::from_ascii(first, &paths[1..])
+        } else {
+            Self::from_binary_bytes(&bytes)
+        }
+    }
+
+    fn from_binary_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if bytes.len() < OFFSET_LPT + 12 {
+            return Err("Ephemeris file is too short to contain a header record.".into());
+        }
+
+        // DE numbers are small positive values, use this to detect the byte order of the file
+        let big_endian = !(1..10000).contains(&read_i32(bytes, OFFSET_NUMDE, false));
+        let read_int = |offset: usize| read_i32(bytes, offset, big_endian);
+        let read_double = |offset: usize| read_f64(bytes, offset, big_endian);
+
+        let de_number = read_int(OFFSET_NUMDE);
+        if !(1..10000).contains(&de_number) {
+            return Err("File is not a JPL binary ephemeris.".into());
+        }
+
+        let first_jd = read_double(OFFSET_SPAN);
+        let interval_days = read_double(OFFSET_SPAN + 16);
+
+        let mut pointers = [[0usize; 3]; POINTER_COUNT];
+        for (idx, pointer) in pointers.iter_mut().enumerate() {
+            let offset = if idx < 12 {
+                OFFSET_IPT + idx * 12
+            } else {
+                OFFSET_LPT
+            };
+            for (field, value) in pointer.iter_mut().enumerate() {
+                *value = read_int(offset + field * 4).max(0) as usize;
+            }
+        }
+
+        // Later ephemerides append blocks (e.g. TT-TDB) that are not described by the pointer
+        // table above, find the record size by checking the spans of the first data records.
+        let min_ncoeff = record_size(&pointers);
+        let ncoeff = (min_ncoeff..=2 * min_ncoeff)
+            .find(|ncoeff| {
+                let first = 2 * ncoeff * 8;
+                let second = 3 * ncoeff * 8;
+                bytes.len() >= second + 8
+                    && read_double(first) == first_jd
+                    && read_double(first + 8) == first_jd + interval_days
+                    && read_double(second) == first_jd + interval_days
+            })
+            .ok_or("Could not determine the record size of the ephemeris file.")?;
+
+        let record_count = bytes.len() / (ncoeff * 8) - 2;
+        let records = (0..record_count * ncoeff)
+            .map(|idx| read_double((2 * ncoeff + idx) * 8))
+            .collect();
+
+        Self::new(
+            de_number as u32,
+            interval_days,
+            read_double(OFFSET_EMRAT),
+            pointers,
+            ncoeff,
+            records,
+        )
+    }
+
+    /// Reads the ASCII distribution of a JPL ephemeris.
+    ///
+    /// ### Arguments
+    /// * 'header_path' - Path to the header file (e.g. 'header.440').
+    /// * 'data_paths' - Paths to the data files (e.g. 'ascp01950.440'), in any order.
+    ///
+    pub fn from_ascii<P: AsRef<Path>, Q: AsRef<Path>>(
+        header_path: P,
+        data_paths: &[Q],
+    ) -> Result<Self, Box<dyn Error>> {
+        let header = fs::read_to_string(header_path)?;
+        let tokens: Vec<&str> = header.split_whitespace().collect();
+
+        let ncoeff: usize = match tokens.iter().position(|token| *token == "NCOEFF=") {
+            Some(idx) => tokens.get(idx + 1).ok_or("Missing NCOEFF value.")?.parse()?,
+            None => return Err("Ephemeris header is missing NCOEFF.".into()),
+        };
+
+        let group = |id: &str| -> Result<&[&str], Box<dyn Error>> {
+            let start = tokens
+                .windows(2)
+                .position(|pair| pair[0] == "GROUP" && pair[1] == id)
+                .ok_or(format!("Ephemeris header is missing GROUP {}.", id))?
+                + 2;
+            let end = tokens[start..]
+                .iter()
+                .position(|token| *token == "GROUP")
+                .map_or(tokens.len(), |idx| start + idx);
+            Ok(&tokens[start..end])
+        };
+
+        let span = group("1030")?;
+        let interval_days = parse_fortran_f64(span.get(2).ok_or("Missing record interval.")?)?;
+
+        let names = group("1040")?;
+        let values = group("1041")?;
+        let constant = |name: &str| -> Result<f64, Box<dyn Error>> {
+            let idx = names
+                .iter()
+                .skip(1)
+                .position(|constant_name| *constant_name == name)
+                .ok_or(format!("Ephemeris header is missing constant {}.", name))?;
+            parse_fortran_f64(values.get(idx + 1).ok_or("Missing constant value.")?)
+        };
+
+        // Three rows (offset, coefficients, sub-intervals) with one column per block
+        let pointer_table = group("1050")?;
+        let columns = pointer_table.len() / 3;
+        if columns < POINTER_COUNT {
+            return Err("Ephemeris header pointer table is incomplete.".into());
+        }
+        let mut pointers = [[0usize; 3]; POINTER_COUNT];
+        for (idx, pointer) in pointers.iter_mut().enumerate() {
+            for (field, value) in pointer.iter_mut().enumerate() {
+                *value = pointer_table[field * columns + idx].parse()?;
+            }
+        }
+
+        let mut records: Vec<Vec<f64>> = Vec::new();
+        for data_path in data_paths {
+            let data = fs::read_to_string(data_path)
+                .map_err(|err| format!("{}: {}", data_path.as_ref().display(), err))?;
+            let mut data_tokens = data.split_whitespace();
+            // Each record is preceded by its record number and its coefficient count
+            while let (Some(_), Some(count)) = (data_tokens.next(), data_tokens.next()) {
+                let count: usize = count.parse()?;
+                let record = data_tokens
+                    .by_ref()
+                    .take(count)
+                    .map(parse_fortran_f64)
+                    .collect::<Result<Vec<f64>, _>>()?;
+                if record.len() != count || count < ncoeff {
+                    return Err("Ephemeris data file contains an incomplete record.".into());
+                }
+                records.push(record[..ncoeff].to_vec());
+            }
+        }
+
+        // Consecutive data files repeat their boundary records
+        records.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        records.dedup_by(|a, b| a[0] == b[0]);
+
+        Self::new(
+            constant("DENUM")? as u32,
+            interval_days,
+            constant("EMRAT")?,
+            pointers,
+            ncoeff,
+            records.concat(),
+        )
+    }
+
+    fn new(
+        de_number: u32,
+        interval_days: f64,
+        emrat: f64,
+        pointers: [[usize; 3]; POINTER_COUNT],
+        ncoeff: usize,
+        records: Vec<f64>,
+    ) -> Result<Self, Box<dyn Error>> {
+        // Pointers are one based, a block that has coefficients cannot start at zero
+        if pointers.iter().any(|[offset, coeff_count, _]| *offset == 0 && *coeff_count != 0) {
+            return Err("Ephemeris pointer table contains a zero coefficient offset.".into());
+        }
+        if records.len() < ncoeff || ncoeff < record_size(&pointers) || interval_days <= 0f64 {
+            return Err("Ephemeris does not contain any usable records.".into());
+        }
+
+        let record_count = records.len() / ncoeff;
+        let start_jd = records[0];
+        let end_jd = records[(record_count - 1) * ncoeff + 1];
+
+        // Records must be contiguous so that they can be indexed directly from a date
+        for idx in 0..record_count {
+            let expected_start = start_jd + idx as f64 * interval_days;
+            if (records[idx * ncoeff] - expected_start).abs() > 1e-6 {
+                return Err("Ephemeris records are not contiguous in time.".into());
+            }
+        }
+
+        Ok(JplEphemeris {
+            de_number,
+            start_jd,
+            end_jd,
+            interval_days,
+            emrat,
+            pointers,
+            ncoeff,
+            records,
+        })
+    }
+
+    /// Checks that the loaded records cover a span of dates.
+    ///
+    /// ### Arguments
+    /// * 'first_jd' - First Julian date (TDB) evaluated.
+    /// * 'last_jd' - Last Julian date (TDB) evaluated.
+    ///
+    /// ### Return
+    ///     An error stating the span of the ephemeris if the dates are not covered.
+    ///
+    pub fn check_span(&self, first_jd: f64, last_jd: f64) -> Result<(), String> {
+        if first_jd < self.start_jd || last_jd > self.end_jd {
+            return Err(format!(
+                "The simulation from JD {:.3} to {:.3} is outside of the DE{} ephemeris span (JD {:.3} to {:.3}).",
+                first_jd, last_jd, self.de_number, self.start_jd, self.end_jd
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates the raw position of a body as stored in the ephemeris.
+    ///
+    /// ### Arguments
+    /// * 'target' - The body to evaluate.
+    /// * 'jd_tdb' - Julian date in barycentric dynamical time.
+    ///
+    /// ### Return
+    ///     The ICRF equatorial position in kilometers relative to the solar system barycenter
+    ///     (geocentric for the Moon), or none if the date is outside of the loaded records.
+    ///
+    pub fn position_km(&self, target: EphemerisTarget, jd_tdb: f64) -> Option<[f64; 3]> {
+        self.interpolate(target as usize, 3, jd_tdb).map(|values| {
+            [values[0], values[1], values[2]]
+        })
+    }
+
+    /// Evaluates the position of a body relative to the center of the Sun.
+    ///
+    /// ### Arguments
+    /// * 'target' - The body to evaluate. The Moon is returned relative to the Earth.
+    /// * 'jd_tdb' - Julian date in barycentric dynamical time.
+    ///
+    /// ### Return
+    ///     The ICRF equatorial position in meters or none if the date is not covered.
+    ///
+    pub fn heliocentric_position_m(&self, target: EphemerisTarget, jd_tdb: f64) -> Option<[f64; 3]> {
+        let position = match target {
+            EphemerisTarget::Sun => return Some([0f64; 3]),
+            EphemerisTarget::Moon => return self.position_km(target, jd_tdb).map(km_to_m),
+            _ => self.position_km(target, jd_tdb)?,
+        };
+        let sun = self.position_km(EphemerisTarget::Sun, jd_tdb)?;
+
+        Some(km_to_m([
+            position[0] - sun[0],
+            position[1] - sun[1],
+            position[2] - sun[2],
+        ]))
+    }
+
+    /// Evaluates the position of the Earth relative to the center of the Sun.
+    ///
+    /// ### Argument
+    /// * 'jd_tdb' - Julian date in barycentric dynamical time.
+    ///
+    /// ### Return
+    ///     The ICRF equatorial position in meters or none if the date is not covered.
+    ///
+    pub fn earth_heliocentric_position_m(&self, jd_tdb: f64) -> Option<[f64; 3]> {
+        let barycenter = self.heliocentric_position_m(EphemerisTarget::EarthMoonBarycenter, jd_tdb)?;
+        let moon = self.heliocentric_position_m(EphemerisTarget::Moon, jd_tdb)?;
+        let moon_fraction = 1f64 / (1f64 + self.emrat);
+
+        Some([
+            barycenter[0] - moon[0] * moon_fraction,
+            barycenter[1] - moon[1] * moon_fraction,
+            barycenter[2] - moon[2] * moon_fraction,
+        ])
+    }
+
+    /// Evaluates the Chebyshev coefficients of a block for all of its components.
+    fn interpolate(&self, block: usize, components: usize, jd_tdb: f64) -> Option<Vec<f64>> {
+        let [offset, coeff_count, sub_intervals] = self.pointers[block];
+        if coeff_count == 0 || sub_intervals == 0 || jd_tdb < self.start_jd || jd_tdb > self.end_jd {
+            return None;
+        }
+
+        let record_count = self.records.len() / self.ncoeff;
+        let record_idx = (((jd_tdb - self.start_jd) / self.interval_days) as usize).min(record_count - 1);
+        let record = &self.records[record_idx * self.ncoeff..(record_idx + 1) * self.ncoeff];
+
+        // Locate the sub-interval and normalize the time within it
+        let sub_length = self.interval_days / sub_intervals as f64;
+        let elapsed = jd_tdb - record[0];
+        let sub_idx = ((elapsed / sub_length) as usize).min(sub_intervals - 1);
+        let tc = 2f64 * (elapsed - sub_idx as f64 * sub_length) / sub_length - 1f64;
+
+        // Pointers are one based and count from the start of the record
+        let start = offset - 1 + sub_idx * coeff_count * components;
+        Some(
+            (0..components)
+                .map(|component| {
+                    let coeff_start = start + component * coeff_count;
+                    chebyshev(&record[coeff_start..coeff_start + coeff_count], tc)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Calculates the minimum record size required by the pointer table.
+fn record_size(pointers: &[[usize; 3]; POINTER_COUNT]) -> usize {
+    pointers
+        .iter()
+        .enumerate()
+        .map(|(idx, [offset, coeff_count, sub_intervals])| {
+            let components = if idx == NUTATION_INDEX { 2 } else { 3 };
+            if *coeff_count == 0 {
+                0
+            } else {
+                offset - 1 + coeff_count * sub_intervals * components
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn km_to_m(position: [f64; 3]) -> [f64; 3] {
+    [position[0] * 1e3, position[1] * 1e3, position[2] * 1e3]
+}
+
+/// Parses a floating point value written with a Fortran 'D' exponent.
+fn parse_fortran_f64(token: &str) -> Result<f64, Box<dyn Error>> {
+    Ok(token.replace('D', "E").replace('d', "e").parse::<f64>()?)
+}
+
+fn read_i32(bytes: &[u8], offset: usize, big_endian: bool) -> i32 {
+    let mut raw = [0u8; 4];
+    raw.copy_from_slice(&bytes[offset..offset + 4]);
+    if big_endian {
+        i32::from_be_bytes(raw)
+    } else {
+        i32::from_le_bytes(raw)
+    }
+}
+
+fn read_f64(bytes: &[u8], offset: usize, big_endian: bool) -> f64 {
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[offset..offset + 8]);
+    if big_endian {
+        f64::from_be_bytes(raw)
+    } else {
+        f64::from_le_bytes(raw)
+    }
+}
+
@@ -0,0 +1,524 @@
+//! Force models contributing accelerations to the simulation objects.
+//!
+//! Every force implements the `ForceModel` trait and is created by name through the
+//! `ForceRegistry` from the force configuration of the input. New forces are added by
+//! registering a factory under a new name, without touching the integration loop.
+
+use crate::bodies;
+use crate::frames;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use types::Array3d;
+
+// Gravitational constant 6.674×10−11
+pub const G: f64 = 6.674e-11;
+// Softening length of the mutual gravity between simulation objects in meters
+const MUTUAL_GRAVITY_SOFTENING_M: f64 = 1.0;
+// Default opening angle of the Barnes-Hut approximation
+const DEFAULT_BARNES_HUT_THETA: f64 = 0.5;
+// Default drag coefficient of the simulation objects
+const DEFAULT_DRAG_COEFFICIENT: f64 = 2.2;
+// Default reflectivity coefficient of the simulation objects
+const DEFAULT_REFLECTIVITY_COEFFICIENT: f64 = 1.3;
+// Solar radiation pressure at one astronomical unit in N/m^2
+const SOLAR_PRESSURE_1AU: f64 = 4.56e-6;
+
+// Exponential atmosphere model (Vallado, Fundamentals of Astrodynamics, table 8-4):
+// base altitude in km, density at the base altitude in kg/m^3 and scale height in km
+const EXPONENTIAL_ATMOSPHERE: [(f64, f64, f64); 28] = [
+    (0.0, 1.225, 7.249),
+    (25.0, 3.899e-2, 6.349),
+    (30.0, 1.774e-2, 6.682),
+    (40.0, 3.972e-3, 7.554),
+    (50.0, 1.057e-3, 8.382),
+    (60.0, 3.206e-4, 7.714),
+    (70.0, 8.770e-5, 6.549),
+    (80.0, 1.905e-5, 5.799),
+    (90.0, 3.396e-6, 5.382),
+    (100.0, 5.297e-7, 5.877),
+    (110.0, 9.661e-8, 7.263),
+    (120.0, 2.438e-8, 9.473),
+    (130.0, 8.484e-9, 12.636),
+    (140.0, 3.845e-9, 16.149),
+    (150.0, 2.070e-9, 22.523),
+    (180.0, 5.464e-10, 29.740),
+    (200.0, 2.789e-10, 37.105),
+    (250.0, 7.248e-11, 45.546),
+    (300.0, 2.418e-11, 53.628),
+    (350.0, 9.518e-12, 53.298),
+    (400.0, 3.725e-12, 58.515),
+    (450.0, 1.585e-12, 60.828),
+    (500.0, 6.967e-13, 63.822),
+    (600.0, 1.454e-13, 71.835),
+    (700.0, 3.614e-14, 88.667),
+    (800.0, 1.170e-14, 124.64),
+    (900.0, 5.245e-15, 181.05),
+    (1000.0, 3.019e-15, 268.00),
+];
+
+/// Configuration of a single force model, as provided by the input file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForceModelConfig {
+    pub name: String, // Name the force model is registered under
+    #[serde(flatten)]
+    pub params: HashMap<String, f64>, // Model specific parameters
+}
+
+/// A force acting on the simulation objects.
+pub trait ForceModel {
+    /// Name of the force, used as the perturbation type in the output.
+    fn name(&self) -> String;
+
+    /// Calculates the acceleration placed on an object by the force.
+    ///
+    /// ### Arguments
+    /// * 'sim_obj' - The object the force acts on, provides its physical properties.
+    /// * 'position' - Position of the object relative to the central body in meters.
+    /// * 'velocity' - Velocity of the object relative to the central body in m/s.
+    /// * 'sim_time_s' - Simulation time the acceleration is evaluated at in seconds.
+    /// * 'env' - The Simulation environment.
+    ///
+    /// ### Return
+    ///     The acceleration in m/s^2, or none if the force does not act on the object.
+    ///
+    fn acceleration(
+        &self,
+        sim_obj: &dyn bodies::Simobj,
+        position: &Array3d,
+        velocity: &Array3d,
+        sim_time_s: f64,
+        env: &bodies::Environment,
+    ) -> Option<Array3d>;
+}
+
+/// Creates a force model from its configuration.
+pub type ForceModelFactory =
+    fn(&ForceModelConfig, &bodies::Environment) -> Result<Box<dyn ForceModel>, String>;
+
+/// Registry of the force models which can be enabled by name.
+pub struct ForceRegistry {
+    factories: HashMap<String, ForceModelFactory>,
+}
+
+impl ForceRegistry {
+    /// Creates a registry holding the force models built into POSE:
+    /// * 'solar_obj_<body>' - Gravity of each solar system object in the environment
+    /// * 'mutual_gravity' - Gravity between objects sharing a gravity group (param 'theta',
+    ///   overridden by the --theta flag)
+    /// * 'drag' - Atmospheric drag of the Earth (param 'cd' for objects without a drag coefficient)
+    /// * 'srp' - Solar radiation pressure (param 'cr' for objects without a reflectivity)
+    /// * 'j2' - Oblateness of the Earth as central body
+    ///
+    /// ### Argument
+    /// * 'env' - The Simulation environment, determines the available solar objects.
+    ///
+    pub fn new(env: &bodies::Environment) -> Self {
+        let mut registry = ForceRegistry {
+            factories: HashMap::new(),
+        };
+
+        for solar_obj in env.get_solar_objects() {
+            registry.register(
+                &SolarObjectGravity::model_name(solar_obj.get_solar_object()),
+                SolarObjectGravity::from_config,
+            );
+        }
+        registry.register("mutual_gravity", MutualGravity::from_config);
+        registry.register("drag", AtmosphericDrag::from_config);
+        registry.register("srp", SolarRadiationPressure::from_config);
+        registry.register("j2", ZonalHarmonicJ2::from_config);
+
+        registry
+    }
+
+    /// Registers a force model, replacing any model previously registered under the name.
+    ///
+    /// ### Arguments
+    /// * 'name' - Name used to enable the force model in the configuration.
+    /// * 'factory' - Function creating the force model from its configuration.
+    ///
+    pub fn register(&mut self, name: &str, factory: ForceModelFactory) {
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    /// Configuration enabling the gravity of every solar object and mutual gravity, used when the
+    /// input does not configure the force models. Drag, solar radiation pressure and J2 are only
+    /// applied when configured.
+    ///
+    /// ### Argument
+    /// * 'env' - The Simulation environment.
+    ///
+    pub fn default_configs(env: &bodies::Environment) -> Vec<ForceModelConfig> {
+        let mut names: Vec<String> = env
+            .get_solar_objects()
+            .iter()
+            .map(|solar_obj| SolarObjectGravity::model_name(solar_obj.get_solar_object()))
+            .collect();
+        names.push("mutual_gravity".to_string());
+
+        names
+            .into_iter()
+            .map(|name| ForceModelConfig {
+                name,
+                params: HashMap::new(),
+            })
+            .collect()
+    }
+
+    /// Creates the force models in the order they are configured.
+    ///
+    /// ### Arguments
+    /// * 'configs' - Configuration of the enabled force models.
+    /// * 'env' - The Simulation environment.
+    ///
+    /// ### Return
+    ///     The force models or an error naming the model that could not be created.
+    ///
+    pub fn build(
+        &self,
+        configs: &[ForceModelConfig],
+        env: &bodies::Environment,
+    ) -> Result<Vec<Box<dyn ForceModel>>, String> {
+        configs
+            .iter()
+            .map(|config| match self.factories.get(&config.name) {
+                Some(factory) => factory(config, env),
+                None => Err(format!("Unknown force model '{}'.", config.name)),
+            })
+            .collect()
+    }
+}
+
+pub fn l2_norm(x: &Array3d) -> f64 {
+    x.dot(x).sqrt()
+}
+
+pub fn normalize(x: &Array3d, l2_norm_precalc: Option<f64>) -> Array3d {
+    let norm = match l2_norm_precalc {
+        Some(val) => val,
+        None => l2_norm(x),
+    };
+
+    Array3d {
+        x: x.x / norm,
+        y: x.y / norm,
+        z: x.z / norm,
+    }
+}
+
+/// Calculates the gravity field of a point mass using Newton's law of universal gravitation.
+///
+/// ### Arguments
+/// * 'distance_vector' - Position relative to the point mass.
+/// * 'mass_kg' - Mass of the point mass.
+///
+/// ### Return
+///     The acceleration at the position in m/s^2.
+///
+fn newton_gravitational_field(distance_vector: &Array3d, mass_kg: f64) -> Array3d {
+    let l2_dist = l2_norm(distance_vector);
+    // Calculate unit vector for perturbation
+    let unit_vector = normalize(distance_vector, Some(l2_dist));
+
+    unit_vector * (-G * (mass_kg / l2_dist.powi(2)))
+}
+
+/// Reads a positive coefficient from the parameters of a force model.
+fn positive_param(config: &ForceModelConfig, param: &str, default: f64) -> Result<f64, String> {
+    let value = *config.params.get(param).unwrap_or(&default);
+    if value.is_nan() || value <= 0f64 {
+        return Err(format!("Parameter '{}' of force model '{}' must be positive.", param, config.name));
+    }
+
+    Ok(value)
+}
+
+/// Gravity of a solar system object. For every body other than the centric the acceleration of
+/// the centric is subtracted, as simulation objects have positions relative to centric.
+pub struct SolarObjectGravity {
+    solar_obj_index: usize,
+    name: String,
+    mass_kg: f64,
+}
+
+impl SolarObjectGravity {
+    fn model_name(solar_obj: &bodies::Solarobj) -> String {
+        format!("solar_obj_{}", solar_obj)
+    }
+
+    fn from_config(
+        config: &ForceModelConfig,
+        env: &bodies::Environment,
+    ) -> Result<Box<dyn ForceModel>, String> {
+        let solar_obj_index = env
+            .get_solar_objects()
+            .iter()
+            .position(|solar_obj| Self::model_name(solar_obj.get_solar_object()) == config.name)
+            .ok_or(format!("Solar object of force model '{}' is not simulated.", config.name))?;
+
+        Ok(Box::new(SolarObjectGravity {
+            solar_obj_index,
+            name: config.name.clone(),
+            mass_kg: env.get_solar_objects()[solar_obj_index]
+                .get_solar_object()
+                .get_mass_kg(),
+        }))
+    }
+}
+
+impl ForceModel for SolarObjectGravity {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn acceleration(
+        &self,
+        _sim_obj: &dyn bodies::Simobj,
+        position: &Array3d,
+        _velocity: &Array3d,
+        sim_time_s: f64,
+        env: &bodies::Environment,
+    ) -> Option<Array3d> {
+        let solar_obj_position = env.position_from_central(self.solar_obj_index, sim_time_s)?;
+        // Calculate gravity field at position of sim object from planet body
+        let grav_accel = newton_gravitational_field(&(position - solar_obj_position), self.mass_kg);
+
+        if self.solar_obj_index == 0 {
+            return Some(grav_accel);
+        }
+
+        // Calculate gravity field at position of centric and subtract it from current
+        let centric_grav = newton_gravitational_field(&(solar_obj_position * -1f64), self.mass_kg);
+
+        Some(grav_accel - centric_grav)
+    }
+}
+
+/// Gravity between the simulation objects sharing a gravity group, approximated with the
+/// Barnes-Hut tree of the group built by the environment at the start of each step.
+pub struct MutualGravity {
+    theta: f64, // Opening angle of the approximation
+}
+
+impl MutualGravity {
+    fn from_config(
+        config: &ForceModelConfig,
+        _env: &bodies::Environment,
+    ) -> Result<Box<dyn ForceModel>, String> {
+        let theta = *config.params.get("theta").unwrap_or(&DEFAULT_BARNES_HUT_THETA);
+        if theta < 0f64 {
+            return Err("Barnes-Hut opening angle 'theta' must not be negative.".to_string());
+        }
+
+        Ok(Box::new(MutualGravity { theta }))
+    }
+}
+
+impl ForceModel for MutualGravity {
+    fn name(&self) -> String {
+        String::from("mutual_gravity")
+    }
+
+    fn acceleration(
+        &self,
+        sim_obj: &dyn bodies::Simobj,
+        position: &Array3d,
+        _velocity: &Array3d,
+        _sim_time_s: f64,
+        env: &bodies::Environment,
+    ) -> Option<Array3d> {
+        let tree = env.get_gravity_group(sim_obj.get_gravity_group()?)?;
+
+        Some(tree.acceleration(
+            sim_obj.get_id(),
+            position,
+            self.theta,
+            MUTUAL_GRAVITY_SOFTENING_M,
+            G,
+        ))
+    }
+}
+
+/// Drag of the Earth's atmosphere, co-rotating with the Earth, on the average cross-section of
+/// the objects, with their own drag coefficient where given. The density follows the piecewise
+/// exponential atmosphere model.
+pub struct AtmosphericDrag {
+    drag_coefficient: f64,
+    earth_radius_m: f64,
+}
+
+impl AtmosphericDrag {
+    fn from_config(
+        config: &ForceModelConfig,
+        env: &bodies::Environment,
+    ) -> Result<Box<dyn ForceModel>, String> {
+        if !env.central_is_earth() {
+            return Err("Force model 'drag' requires the Earth as central body.".to_string());
+        }
+
+        Ok(Box::new(AtmosphericDrag {
+            drag_coefficient: positive_param(config, "cd", DEFAULT_DRAG_COEFFICIENT)?,
+            earth_radius_m: env.get_solar_objects()[0].get_solar_object().get_radius_m(),
+        }))
+    }
+
+    /// Density of the atmosphere in kg/m^3 at an altitude in meters.
+    fn density(altitude_m: f64) -> f64 {
+        let altitude_km = altitude_m.max(0f64) / 1000f64;
+        let (base_km, base_density, scale_height_km) = EXPONENTIAL_ATMOSPHERE
+            .iter()
+            .rev()
+            .find(|(base_km, _, _)| altitude_km >= *base_km)
+            .unwrap_or(&EXPONENTIAL_ATMOSPHERE[0]);
+
+        base_density * (-(altitude_km - base_km) / scale_height_km).exp()
+    }
+}
+
+impl ForceModel for AtmosphericDrag {
+    fn name(&self) -> String {
+        String::from("drag")
+    }
+
+    fn acceleration(
+        &self,
+        sim_obj: &dyn bodies::Simobj,
+        position: &Array3d,
+        velocity: &Array3d,
+        _sim_time_s: f64,
+        env: &bodies::Environment,
+    ) -> Option<Array3d> {
+        let density = Self::density(l2_norm(position) - self.earth_radius_m);
+
+        // The atmosphere rotates about the true pole of date, inclined to the ecliptic
+        let rotation = frames::ecliptic_from_equatorial()
+            * env.get_earth_orientation().pole()
+            * frames::EARTH_ROTATION_RATE;
+
+        // Velocity relative to the rotating atmosphere, v - w x r
+        let relative_velocity = velocity - rotation.cross(position);
+
+        let drag_coefficient = sim_obj
+            .get_surface()
+            .drag_coefficient
+            .unwrap_or(self.drag_coefficient);
+        let ballistic = drag_coefficient * sim_obj.get_cross_section() / sim_obj.get_mass();
+        Some(relative_velocity * (-0.5 * ballistic * density * l2_norm(&relative_velocity)))
+    }
+}
+
+/// Pressure of the solar radiation on the radiation pressure area of the objects, their average
+/// cross-section if not given, modelled as a sphere. Objects within the cylindrical shadow of the
+/// central body are not illuminated.
+pub struct SolarRadiationPressure {
+    reflectivity: f64,
+    sun_index: usize,
+    central_radius_m: f64,
+}
+
+impl SolarRadiationPressure {
+    fn from_config(
+        config: &ForceModelConfig,
+        env: &bodies::Environment,
+    ) -> Result<Box<dyn ForceModel>, String> {
+        let solar_objs = env.get_solar_objects();
+        let sun_index = solar_objs
+            .iter()
+            .position(|solar_obj| matches!(solar_obj.get_solar_object(), bodies::Solarobj::Sun { attr: _ }))
+            .ok_or("Force model 'srp' requires the Sun to be simulated.".to_string())?;
+
+        Ok(Box::new(SolarRadiationPressure {
+            reflectivity: positive_param(config, "cr", DEFAULT_REFLECTIVITY_COEFFICIENT)?,
+            sun_index,
+            central_radius_m: solar_objs[0].get_solar_object().get_radius_m(),
+        }))
+    }
+}
+
+impl ForceModel for SolarRadiationPressure {
+    fn name(&self) -> String {
+        String::from("srp")
+    }
+
+    fn acceleration(
+        &self,
+        sim_obj: &dyn bodies::Simobj,
+        position: &Array3d,
+        _velocity: &Array3d,
+        sim_time_s: f64,
+        env: &bodies::Environment,
+    ) -> Option<Array3d> {
+        let sun_position = env.position_from_central(self.sun_index, sim_time_s)?;
+
+        // Behind the central body and within its radius of the Sun line
+        if self.sun_index != 0 {
+            let sun_direction = normalize(&sun_position, None);
+            let along = position.dot(&sun_direction);
+            if along < 0f64 && l2_norm(&(position - sun_direction * along)) < self.central_radius_m {
+                return None;
+            }
+        }
+
+        let from_sun = position - sun_position;
+        let distance = l2_norm(&from_sun);
+        let pressure =
+            SOLAR_PRESSURE_1AU * (bodies::METERS_PER_ASTRONOMICAL_UNIT / distance).powi(2);
+        let surface = sim_obj.get_surface();
+        let reflectivity = surface.reflectivity.unwrap_or(self.reflectivity);
+        let area = surface.srp_area.unwrap_or_else(|| sim_obj.get_cross_section());
+
+        Some(
+            normalize(&from_sun, Some(distance))
+                * (pressure * reflectivity * area / sim_obj.get_mass()),
+        )
+    }
+}
+
+/// Second zonal harmonic of the gravity field of the Earth as central body, the oblateness about
+/// its true pole of date.
+pub struct ZonalHarmonicJ2 {
+    j2: f64,
+    radius_m: f64,
+    mu: f64,
+}
+
+impl ZonalHarmonicJ2 {
+    fn from_config(
+        _config: &ForceModelConfig,
+        env: &bodies::Environment,
+    ) -> Result<Box<dyn ForceModel>, String> {
+        if !env.central_is_earth() {
+            return Err("Force model 'j2' requires the Earth as central body.".to_string());
+        }
+        let earth = env.get_solar_objects()[0].get_solar_object();
+
+        Ok(Box::new(ZonalHarmonicJ2 {
+            j2: earth.get_j2(),
+            radius_m: earth.get_radius_m(),
+            mu: env.central_gravitational_parameter(),
+        }))
+    }
+}
+
+impl ForceModel for ZonalHarmonicJ2 {
+    fn name(&self) -> String {
+        String::from("j2")
+    }
+
+    fn acceleration(
+        &self,
+        _sim_obj: &dyn bodies::Simobj,
+        position: &Array3d,
+        _velocity: &Array3d,
+        _sim_time_s: f64,
+        env: &bodies::Environment,
+    ) -> Option<Array3d> {
+        let pole = frames::ecliptic_from_equatorial() * env.get_earth_orientation().pole();
+        let r = l2_norm(position);
+        // Height above the equatorial plane
+        let z = position.dot(&pole);
+        let scale = -1.5 * self.j2 * self.mu * self.radius_m.powi(2) / r.powi(5);
+
+        Some((position * (1f64 - 5f64 * (z / r).powi(2)) + pole * (2f64 * z)) * scale)
+    }
+}
@@ -0,0 +1,486 @@
+//! Rotations between the inertial and Earth fixed reference frames.
+//!
+//! Implements the IAU-76/FK5 reduction: IAU-1976 precession, the IAU-1980 nutation theory with
+//! the 1982 GMST and the 1994 equation of the equinoxes, and IERS polar motion. The supported
+//! frames are
+//! * GCRF/J2000 - Mean equator and equinox of J2000. With the nutation corrections of the Earth
+//!   orientation parameters applied the FK5 reduction realizes the GCRF.
+//! * MOD - Mean equator and equinox of date.
+//! * TOD - True equator and equinox of date.
+//! * TEME - True equator and mean equinox of date, the frame of the SGP4 theory.
+//! * PEF - Pseudo Earth fixed, rotating with the Earth about the true pole.
+//! * ITRF - International terrestrial reference frame.
+//!
+//! Matrices are named after the frames they map between, `gcrf_from_tod * r_tod = r_gcrf`.
+//!
+//! States and ephemerides are exchanged in the inertial frames of [`Frame`] and rotated into the
+//! simulation frame, the ecliptic of J2000, on the way in and out of the simulation.
+//! The local orbital frames of [`LocalFrame`] follow an object along its orbit.
+
+use force_models::normalize;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use strum_macros::{Display, EnumString};
+use time;
+use types::{Array3d, Matrix3d};
+
+// Obliquity of the ecliptic at J2000 (84381.448")
+pub const OBLIQUITY_J2000_DEG: f64 = 23.4392911;
+pub const ARCSEC_TO_RAD: f64 = PI / (180.0 * 3600.0);
+// Mean angular velocity of the Earth's rotation in rad/s
+pub const EARTH_ROTATION_RATE: f64 = 7.292115146706979e-5;
+const DAYS_PER_JULIAN_CENTURY: f64 = 36525.0;
+// Frame bias of the J2000 dynamical frame relative to the GCRF (IERS Conventions 2003): offsets
+// of the pole in x and y and of the equinox in right ascension, in arcseconds
+const BIAS_XI0: f64 = -0.016617;
+const BIAS_ETA0: f64 = -0.0068192;
+const BIAS_DALPHA0: f64 = -0.0146;
+
+// IAU-1980 nutation series: multipliers of the fundamental arguments (l, l', F, D, Omega) and the
+// coefficients A + B*T of the longitude and C + D*T of the obliquity in units of 0.1 mas
+const NUTATION_1980: [([i8; 5], f64, f64, f64, f64); 106] = [
+    ([0, 0, 0, 0, 1], -171996.0, -174.2, 92025.0, 8.9),
+    ([0, 0, 2, -2, 2], -13187.0, -1.6, 5736.0, -3.1),
+    ([0, 0, 2, 0, 2], -2274.0, -0.2, 977.0, -0.5),
+    ([0, 0, 0, 0, 2], 2062.0, 0.2, -895.0, 0.5),
+    ([0, 1, 0, 0, 0], 1426.0, -3.4, 54.0, -0.1),
+    ([1, 0, 0, 0, 0], 712.0, 0.1, -7.0, 0.0),
+    ([0, 1, 2, -2, 2], -517.0, 1.2, 224.0, -0.6),
+    ([0, 0, 2, 0, 1], -386.0, -0.4, 200.0, 0.0),
+    ([1, 0, 2, 0, 2], -301.0, 0.0, 129.0, -0.1),
+    ([0, -1, 2, -2, 2], 217.0, -0.5, -95.0, 0.3),
+    ([1, 0, 0, -2, 0], -158.0, 0.0, -1.0, 0.0),
+    ([0, 0, 2, -2, 1], 129.0, 0.1, -70.0, 0.0),
+    ([-1, 0, 2, 0, 2], 123.0, 0.0, -53.0, 0.0),
+    ([1, 0, 0, 0, 1], 63.0, 0.1, -33.0, 0.0),
+    ([0, 0, 0, 2, 0], 63.0, 0.0, -2.0, 0.0),
+    ([-1, 0, 2, 2, 2], -59.0, 0.0, 26.0, 0.0),
+    ([-1, 0, 0, 0, 1], -58.0, -0.1, 32.0, 0.0),
+    ([1, 0, 2, 0, 1], -51.0, 0.0, 27.0, 0.0),
+    ([2, 0, 0, -2, 0], 48.0, 0.0, 1.0, 0.0),
+    ([-2, 0, 2, 0, 1], 46.0, 0.0, -24.0, 0.0),
+    ([0, 0, 2, 2, 2], -38.0, 0.0, 16.0, 0.0),
+    ([2, 0, 2, 0, 2], -31.0, 0.0, 13.0, 0.0),
+    ([2, 0, 0, 0, 0], 29.0, 0.0, -1.0, 0.0),
+    ([1, 0, 2, -2, 2], 29.0, 0.0, -12.0, 0.0),
+    ([0, 0, 2, 0, 0], 26.0, 0.0, -1.0, 0.0),
+    ([0, 0, 2, -2, 0], -22.0, 0.0, 0.0, 0.0),
+    ([-1, 0, 2, 0, 1], 21.0, 0.0, -10.0, 0.0),
+    ([0, 2, 0, 0, 0], 17.0, -0.1, 0.0, 0.0),
+    ([0, 2, 2, -2, 2], -16.0, 0.1, 7.0, 0.0),
+    ([-1, 0, 0, 2, 1], 16.0, 0.0, -8.0, 0.0),
+    ([0, 1, 0, 0, 1], -15.0, 0.0, 9.0, 0.0),
+    ([1, 0, 0, -2, 1], -13.0, 0.0, 7.0, 0.0),
+    ([0, -1, 0, 0, 1], -12.0, 0.0, 6.0, 0.0),
+    ([2, 0, -2, 0, 0], 11.0, 0.0, 0.0, 0.0),
+    ([-1, 0, 2, 2, 1], -10.0, 0.0, 5.0, 0.0),
+    ([1, 0, 2, 2, 2], -8.0, 0.0, 3.0, 0.0),
+    ([0, -1, 2, 0, 2], -7.0, 0.0, 3.0, 0.0),
+    ([0, 0, 2, 2, 1], -7.0, 0.0, 3.0, 0.0),
+    ([1, 1, 0, -2, 0], -7.0, 0.0, 0.0, 0.0),
+    ([0, 1, 2, 0, 2], 7.0, 0.0, -3.0, 0.0),
+    ([-2, 0, 0, 2, 1], -6.0, 0.0, 3.0, 0.0),
+    ([0, 0, 0, 2, 1], -6.0, 0.0, 3.0, 0.0),
+    ([2, 0, 2, -2, 2], 6.0, 0.0, -3.0, 0.0),
+    ([1, 0, 0, 2, 0], 6.0, 0.0, 0.0, 0.0),
+    ([1, 0, 2, -2, 1], 6.0, 0.0, -3.0, 0.0),
+    ([0, 0, 0, -2, 1], -5.0, 0.0, 3.0, 0.0),
+    ([0, -1, 2, -2, 1], -5.0, 0.0, 3.0, 0.0),
+    ([2, 0, 2, 0, 1], -5.0, 0.0, 3.0, 0.0),
+    ([1, -1, 0, 0, 0], 5.0, 0.0, 0.0, 0.0),
+    ([1, 0, 0, -1, 0], -4.0, 0.0, 0.0, 0.0),
+    ([0, 0, 0, 1, 0], -4.0, 0.0, 0.0, 0.0),
+    ([0, 1, 0, -2, 0], -4.0, 0.0, 0.0, 0.0),
+    ([1, 0, -2, 0, 0], 4.0, 0.0, 0.0, 0.0),
+    ([2, 0, 0, -2, 1], 4.0, 0.0, -2.0, 0.0),
+    ([0, 1, 2, -2, 1], 4.0, 0.0, -2.0, 0.0),
+    ([1, 1, 0, 0, 0], -3.0, 0.0, 0.0, 0.0),
+    ([1, -1, 0, -1, 0], -3.0, 0.0, 0.0, 0.0),
+    ([-1, -1, 2, 2, 2], -3.0, 0.0, 1.0, 0.0),
+    ([0, -1, 2, 2, 2], -3.0, 0.0, 1.0, 0.0),
+    ([1, -1, 2, 0, 2], -3.0, 0.0, 1.0, 0.0),
+    ([3, 0, 2, 0, 2], -3.0, 0.0, 1.0, 0.0),
+    ([-2, 0, 2, 0, 2], -3.0, 0.0, 1.0, 0.0),
+    ([1, 0, 2, 0, 0], 3.0, 0.0, 0.0, 0.0),
+    ([-1, 0, 2, 4, 2], -2.0, 0.0, 1.0, 0.0),
+    ([1, 0, 0, 0, 2], -2.0, 0.0, 1.0, 0.0),
+    ([-1, 0, 2, -2, 1], -2.0, 0.0, 1.0, 0.0),
+    ([0, -2, 2, -2, 1], -2.0, 0.0, 1.0, 0.0),
+    ([-2, 0, 0, 0, 1], -2.0, 0.0, 1.0, 0.0),
+    ([2, 0, 0, 0, 1], 2.0, 0.0, -1.0, 0.0),
+    ([3, 0, 0, 0, 0], 2.0, 0.0, 0.0, 0.0),
+    ([1, 1, 2, 0, 2], 2.0, 0.0, -1.0, 0.0),
+    ([0, 0, 2, 1, 2], 2.0, 0.0, -1.0, 0.0),
+    ([1, 0, 0, 2, 1], -1.0, 0.0, 0.0, 0.0),
+    ([1, 0, 2, 2, 1], -1.0, 0.0, 1.0, 0.0),
+    ([1, 1, 0, -2, 1], -1.0, 0.0, 0.0, 0.0),
+    ([0, 1, 0, 2, 0], -1.0, 0.0, 0.0, 0.0),
+    ([0, 1, 2, -2, 0], -1.0, 0.0, 0.0, 0.0),
+    ([0, 1, -2, 2, 0], -1.0, 0.0, 0.0, 0.0),
+    ([1, 0, -2, 2, 0], -1.0, 0.0, 0.0, 0.0),
+    ([1, 0, -2, -2, 0], -1.0, 0.0, 0.0, 0.0),
+    ([1, 0, 2, -2, 0], -1.0, 0.0, 0.0, 0.0),
+    ([1, 0, 0, -4, 0], -1.0, 0.0, 0.0, 0.0),
+    ([2, 0, 0, -4, 0], -1.0, 0.0, 0.0, 0.0),
+    ([0, 0, 2, 4, 2], -1.0, 0.0, 0.0, 0.0),
+    ([0, 0, 2, -1, 2], -1.0, 0.0, 0.0, 0.0),
+    ([-2, 0, 2, 4, 2], -1.0, 0.0, 1.0, 0.0),
+    ([2, 0, 2, 2, 2], -1.0, 0.0, 0.0, 0.0),
+    ([0, -1, 2, 0, 1], -1.0, 0.0, 0.0, 0.0),
+    ([0, 0, -2, 0, 1], -1.0, 0.0, 0.0, 0.0),
+    ([0, 0, 4, -2, 2], 1.0, 0.0, 0.0, 0.0),
+    ([0, 1, 0, 0, 2], 1.0, 0.0, 0.0, 0.0),
+    ([1, 1, 2, -2, 2], 1.0, 0.0, -1.0, 0.0),
+    ([3, 0, 2, -2, 2], 1.0, 0.0, 0.0, 0.0),
+    ([-2, 0, 2, 2, 2], 1.0, 0.0, -1.0, 0.0),
+    ([-1, 0, 0, 0, 2], 1.0, 0.0, -1.0, 0.0),
+    ([0, 0, -2, 2, 1], 1.0, 0.0, 0.0, 0.0),
+    ([0, 1, 2, 0, 1], 1.0, 0.0, 0.0, 0.0),
+    ([-1, 0, 4, 0, 2], 1.0, 0.0, 0.0, 0.0),
+    ([2, 1, 0, -2, 0], 1.0, 0.0, 0.0, 0.0),
+    ([2, 0, 0, 2, 0], 1.0, 0.0, 0.0, 0.0),
+    ([2, 0, 2, -2, 1], 1.0, 0.0, -1.0, 0.0),
+    ([2, 0, -2, 0, 1], 1.0, 0.0, 0.0, 0.0),
+    ([1, -1, 0, -2, 0], 1.0, 0.0, 0.0, 0.0),
+    ([-1, 0, 0, 1, 1], 1.0, 0.0, 0.0, 0.0),
+    ([-1, -1, 0, 2, 1], 1.0, 0.0, 0.0, 0.0),
+    ([0, 1, 0, 1, 0], 1.0, 0.0, 0.0, 0.0),
+];
+
+/// Inertial reference frames of the states and ephemerides exchanged with the simulation. All of
+/// them share the origin of the simulation, only the orientation of the axes differs.
+#[derive(Serialize, Deserialize, Display, EnumString, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Frame {
+    /// Mean ecliptic and equinox of J2000, the frame the simulation integrates in. Its axes are
+    /// the GCRF rotated about the x axis by the obliquity of J2000.
+    #[serde(rename = "ecliptic_j2000")]
+    #[strum(serialize = "ecliptic_j2000")]
+    EclipticJ2000,
+    /// Mean ecliptic and equinox of date, the frame of the analytic solar object models.
+    #[serde(rename = "ecliptic_of_date")]
+    #[strum(serialize = "ecliptic_of_date")]
+    EclipticOfDate,
+    /// Mean equator and equinox of J2000, the FK5 based dynamical frame. The frame of input files
+    /// which do not name one.
+    #[default]
+    #[serde(rename = "eme2000")]
+    #[strum(serialize = "eme2000")]
+    Eme2000,
+    /// Geocentric celestial reference frame, aligned with the ICRF of the JPL ephemerides.
+    #[serde(rename = "gcrf")]
+    #[strum(serialize = "gcrf")]
+    Gcrf,
+    /// True equator and true equinox of date.
+    #[serde(rename = "tod")]
+    #[strum(serialize = "tod")]
+    Tod,
+    /// True equator and mean equinox of date, the frame of two-line element sets.
+    #[serde(rename = "teme")]
+    #[strum(serialize = "teme")]
+    Teme,
+}
+
+/// Local orbital frames attached to an object, built from its position and velocity relative to
+/// the central body. They are undefined for purely radial motion.
+#[derive(Serialize, Deserialize, Display, EnumString, Clone, Copy, PartialEq, Debug)]
+pub enum LocalFrame {
+    /// Radial, transverse and normal, also known as RIC or RSW. Transverse lies in the orbit plane
+    /// in the direction of motion, normal along the angular momentum.
+    #[serde(rename = "rtn", alias = "ric")]
+    #[strum(to_string = "rtn", serialize = "ric")]
+    Rtn,
+    /// Local vertical local horizontal: z towards the central body, y against the angular
+    /// momentum and x completing the frame in the direction of motion.
+    #[serde(rename = "lvlh")]
+    #[strum(serialize = "lvlh")]
+    Lvlh,
+    /// Velocity, normal along the angular momentum and binormal completing the frame.
+    #[serde(rename = "vnb")]
+    #[strum(serialize = "vnb")]
+    Vnb,
+}
+
+/// Corrections from the Earth orientation parameters, zero when they are not known.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EopCorrections {
+    pub ut1_minus_utc_s: f64, // UT1-UTC in seconds
+    pub x_pole_rad: f64,      // Polar motion
+    pub y_pole_rad: f64,
+    pub dpsi_rad: f64, // Corrections to the IAU-1980 nutation in longitude and obliquity
+    pub deps_rad: f64,
+}
+
+/// Orientation of the Earth at an epoch.
+pub struct EarthOrientation {
+    gcrf_from_mod: Matrix3d,
+    mod_from_tod: Matrix3d,
+    tod_from_pef: Matrix3d,
+    pef_from_itrf: Matrix3d,
+    tod_from_teme: Matrix3d,
+}
+
+/// Nutation angles of the IAU-1980 theory.
+struct Nutation {
+    dpsi_rad: f64,           // Nutation in longitude
+    deps_rad: f64,           // Nutation in obliquity
+    mean_obliquity_rad: f64, // Mean obliquity of the ecliptic of date
+    node_rad: f64,           // Longitude of the ascending node of the Moon
+}
+
+/// Rotation from the J2000 equator into the J2000 ecliptic, the frame of the solar system
+/// objects.
+pub fn ecliptic_from_equatorial() -> Matrix3d {
+    Matrix3d::rot_x(OBLIQUITY_J2000_DEG.to_radians())
+}
+
+impl Frame {
+    /// Rotation from the frame into the simulation frame. Frames of date are taken at the epoch,
+    /// their slow rotation is not applied to velocities. The true equator of date follows the
+    /// IAU-1980 nutation without the corrections of the Earth orientation parameters.
+    ///
+    /// ### Argument
+    /// * 'epoch' - The epoch of the rotation.
+    ///
+    pub fn simulation_from_frame(&self, epoch: &time::Epoch) -> Matrix3d {
+        match self {
+            Frame::EclipticJ2000 => Matrix3d::identity(),
+            Frame::EclipticOfDate => {
+                let t_tt = (epoch.jd_tt() - time::J2000_JD) / DAYS_PER_JULIAN_CENTURY;
+                let (zeta, theta, z) = precession_angles(t_tt);
+                let gcrf_from_mod =
+                    Matrix3d::rot_z(zeta) * Matrix3d::rot_y(-theta) * Matrix3d::rot_z(z);
+
+                ecliptic_from_equatorial()
+                    * gcrf_from_mod
+                    * Matrix3d::rot_x(-mean_obliquity(t_tt))
+            }
+            Frame::Eme2000 => ecliptic_from_equatorial() * eme2000_from_gcrf().transpose(),
+            Frame::Gcrf => ecliptic_from_equatorial(),
+            Frame::Tod => {
+                let orientation = EarthOrientation::new(epoch, &EopCorrections::default());
+                ecliptic_from_equatorial() * orientation.gcrf_from_tod()
+            }
+            Frame::Teme => {
+                let orientation = EarthOrientation::new(epoch, &EopCorrections::default());
+                ecliptic_from_equatorial() * orientation.gcrf_from_teme()
+            }
+        }
+    }
+
+    /// Rotation from the simulation frame into the frame.
+    ///
+    /// ### Argument
+    /// * 'epoch' - The epoch of the rotation.
+    ///
+    pub fn frame_from_simulation(&self, epoch: &time::Epoch) -> Matrix3d {
+        self.simulation_from_frame(epoch).transpose()
+    }
+}
+
+impl LocalFrame {
+    /// Rotation from the frame of the state into the local frame, the rows are the axes of the
+    /// local frame.
+    ///
+    /// ### Arguments
+    /// * 'position' - Position of the object relative to the central body.
+    /// * 'velocity' - Velocity of the object relative to the central body.
+    ///
+    pub fn local_from_inertial(&self, position: &Array3d, velocity: &Array3d) -> Matrix3d {
+        let normal = normalize(&position.cross(velocity), None);
+        let axes = match self {
+            LocalFrame::Rtn => {
+                let radial = normalize(position, None);
+                [radial, normal.cross(&radial), normal]
+            }
+            LocalFrame::Lvlh => {
+                let nadir = normalize(position, None) * -1f64;
+                let negative_normal = normal * -1f64;
+                [negative_normal.cross(&nadir), negative_normal, nadir]
+            }
+            LocalFrame::Vnb => {
+                let along = normalize(velocity, None);
+                [along, normal, along.cross(&normal)]
+            }
+        };
+
+        Matrix3d {
+            m: axes.map(|axis| [axis.x, axis.y, axis.z]),
+        }
+    }
+}
+
+/// Rotation from the GCRF into the mean equator and equinox of J2000 by the frame bias.
+fn eme2000_from_gcrf() -> Matrix3d {
+    Matrix3d::rot_x(-BIAS_ETA0 * ARCSEC_TO_RAD)
+        * Matrix3d::rot_y(BIAS_XI0 * ARCSEC_TO_RAD)
+        * Matrix3d::rot_z(BIAS_DALPHA0 * ARCSEC_TO_RAD)
+}
+
+/// Mean obliquity of the ecliptic of date of the IAU-1980 theory in radians.
+fn mean_obliquity(t_tt: f64) -> f64 {
+    (23.439291 - 0.0130042 * t_tt - 1.64e-7 * t_tt.powi(2) + 5.04e-7 * t_tt.powi(3)).to_radians()
+}
+
+/// Greenwich mean sidereal time of the IAU-1982 model.
+///
+/// ### Argument
+/// * 'jd_ut1' - Julian date in UT1.
+///
+/// ### Return
+///     GMST in radians within [0, 2 pi).
+///
+pub fn gmst(jd_ut1: f64) -> f64 {
+    let t = (jd_ut1 - time::J2000_JD) / DAYS_PER_JULIAN_CENTURY;
+    let seconds = 67310.54841 + (876600.0 * 3600.0 + 8640184.812866) * t + 0.093104 * t.powi(2)
+        - 6.2e-6 * t.powi(3);
+
+    (seconds / 240.0).to_radians().rem_euclid(2.0 * PI)
+}
+
+/// IAU-1976 precession angles zeta, theta and z in radians.
+fn precession_angles(t_tt: f64) -> (f64, f64, f64) {
+    let zeta = 2306.2181 * t_tt + 0.30188 * t_tt.powi(2) + 0.017998 * t_tt.powi(3);
+    let theta = 2004.3109 * t_tt - 0.42665 * t_tt.powi(2) - 0.041833 * t_tt.powi(3);
+    let z = 2306.2181 * t_tt + 1.09468 * t_tt.powi(2) + 0.018203 * t_tt.powi(3);
+
+    (zeta * ARCSEC_TO_RAD, theta * ARCSEC_TO_RAD, z * ARCSEC_TO_RAD)
+}
+
+/// Evaluates the IAU-1980 nutation series.
+fn nutation(t_tt: f64) -> Nutation {
+    // Fundamental arguments of the Moon and Sun in degrees
+    let revolution = 360.0;
+    let arguments = [
+        134.96298139 + (1325.0 * revolution + 198.8673981) * t_tt + 0.0086972 * t_tt.powi(2)
+            + 1.78e-5 * t_tt.powi(3),
+        357.52772333 + (99.0 * revolution + 359.0503400) * t_tt
+            - 0.0001603 * t_tt.powi(2)
+            - 3.3e-6 * t_tt.powi(3),
+        93.27191028 + (1342.0 * revolution + 82.0175381) * t_tt - 0.0036825 * t_tt.powi(2)
+            + 3.1e-6 * t_tt.powi(3),
+        297.85036306 + (1236.0 * revolution + 307.1114800) * t_tt - 0.0019142 * t_tt.powi(2)
+            + 5.3e-6 * t_tt.powi(3),
+        125.04452222 - (5.0 * revolution + 134.1362608) * t_tt + 0.0020708 * t_tt.powi(2)
+            + 2.2e-6 * t_tt.powi(3),
+    ]
+    .map(|degrees: f64| degrees.rem_euclid(revolution).to_radians());
+
+    let (mut dpsi, mut deps) = (0f64, 0f64);
+    for (multipliers, a, b, c, d) in NUTATION_1980.iter() {
+        let argument: f64 = multipliers
+            .iter()
+            .zip(arguments.iter())
+            .map(|(multiplier, value)| *multiplier as f64 * value)
+            .sum();
+        dpsi += (a + b * t_tt) * argument.sin();
+        deps += (c + d * t_tt) * argument.cos();
+    }
+
+    Nutation {
+        dpsi_rad: dpsi * 1e-4 * ARCSEC_TO_RAD,
+        deps_rad: deps * 1e-4 * ARCSEC_TO_RAD,
+        mean_obliquity_rad: mean_obliquity(t_tt),
+        node_rad: arguments[4],
+    }
+}
+
+impl EarthOrientation {
+    /// Calculates the orientation of the Earth.
+    ///
+    /// ### Arguments
+    /// * 'epoch' - The epoch of the orientation.
+    /// * 'eop' - Earth orientation parameters at the epoch.
+    ///
+    pub fn new(epoch: &time::Epoch, eop: &EopCorrections) -> Self {
+        let t_tt = (epoch.jd_tt() - time::J2000_JD) / DAYS_PER_JULIAN_CENTURY;
+
+        let (zeta, theta, z) = precession_angles(t_tt);
+        let gcrf_from_mod = Matrix3d::rot_z(zeta) * Matrix3d::rot_y(-theta) * Matrix3d::rot_z(z);
+
+        let nutation = nutation(t_tt);
+        let dpsi = nutation.dpsi_rad + eop.dpsi_rad;
+        let true_obliquity = nutation.mean_obliquity_rad + nutation.deps_rad + eop.deps_rad;
+        let mod_from_tod = Matrix3d::rot_x(-nutation.mean_obliquity_rad)
+            * Matrix3d::rot_z(dpsi)
+            * Matrix3d::rot_x(true_obliquity);
+
+        // The kinematic terms of the equation of the equinoxes apply from 1997-02-27
+        let mut equation_of_equinoxes = dpsi * nutation.mean_obliquity_rad.cos();
+        if epoch.jd_tt() > 2450449.5 {
+            equation_of_equinoxes += (0.00264 * nutation.node_rad.sin()
+                + 0.000063 * (2.0 * nutation.node_rad).sin())
+                * ARCSEC_TO_RAD;
+        }
+
+        let gmst_rad = gmst(epoch.jd_ut1(eop.ut1_minus_utc_s));
+        let gast_rad = (gmst_rad + equation_of_equinoxes).rem_euclid(2.0 * PI);
+
+        EarthOrientation {
+            gcrf_from_mod,
+            mod_from_tod,
+            tod_from_pef: Matrix3d::rot_z(-gast_rad),
+            pef_from_itrf: Matrix3d::rot_x(eop.y_pole_rad) * Matrix3d::rot_y(eop.x_pole_rad),
+            // TEME uses the classical equation of the equinoxes without the kinematic terms
+            tod_from_teme: Matrix3d::rot_z(-dpsi * nutation.mean_obliquity_rad.cos()),
+        }
+    }
+
+    /// Rotation from the true of date frame into the GCRF.
+    pub fn gcrf_from_tod(&self) -> Matrix3d {
+        self.gcrf_from_mod * self.mod_from_tod
+    }
+
+    /// Rotation from the TEME frame into the GCRF.
+    pub fn gcrf_from_teme(&self) -> Matrix3d {
+        self.gcrf_from_tod() * self.tod_from_teme
+    }
+
+    /// Rotation from the pseudo Earth fixed frame into the GCRF.
+    pub fn gcrf_from_pef(&self) -> Matrix3d {
+        self.gcrf_from_tod() * self.tod_from_pef
+    }
+
+    /// Rotation from the ITRF into the GCRF.
+    pub fn gcrf_from_itrf(&self) -> Matrix3d {
+        self.gcrf_from_pef() * self.pef_from_itrf
+    }
+
+    /// Converts a position and velocity from the ITRF into the GCRF, adding the velocity of the
+    /// Earth's rotation.
+    ///
+    /// ### Arguments
+    /// * 'position' - Position in the ITRF.
+    /// * 'velocity' - Velocity relative to the ITRF.
+    ///
+    /// ### Return
+    ///     The position and velocity in the GCRF.
+    ///
+    pub fn gcrf_from_itrf_state(&self, position: &Array3d, velocity: &Array3d) -> (Array3d, Array3d) {
+        let position_pef = self.pef_from_itrf * position;
+        let velocity_pef = self.pef_from_itrf * velocity;
+        // w x r with the rotation about the z axis of the pseudo Earth fixed frame
+        let rotation = Array3d {
+            x: -EARTH_ROTATION_RATE * position_pef.y,
+            y: EARTH_ROTATION_RATE * position_pef.x,
+            z: 0.0,
+        };
+        let gcrf_from_pef = self.gcrf_from_pef();
+
+        (gcrf_from_pef * position_pef, gcrf_from_pef * (velocity_pef + rotation))
+    }
+
+    /// Rotation from the GCRF into the ITRF.
+    pub fn itrf_from_gcrf(&self) -> Matrix3d {
+        self.gcrf_from_itrf().transpose()
+    }
+
+    /// The rotation axis of the Earth, the true pole of date, in the GCRF.
+    pub fn pole(&self) -> Array3d {
+        self.gcrf_from_tod()
+            * Array3d {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            }
+    }
+}
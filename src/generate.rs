@@ -0,0 +1,355 @@
+//! Generator of input files with random populations of objects about the Earth.
+//!
+//! Objects are drawn by classes, each with a count and the distributions of its orbits and
+//! sizes. The perigee altitude, eccentricity and inclination are drawn uniformly from their
+//! ranges, the node, argument of perigee and mean anomaly uniformly over the full circle. Sizes
+//! and area-to-mass ratios span orders of magnitude and are drawn uniformly in their logarithm.
+//! The states are written as positions and velocities in the EME2000 frame, from the elements
+//! under the gravity of the Earth as simulated. The same seed draws the same population.
+
+use bodies;
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ArgMatches;
+use elements::KeplerianElements;
+use frames::Frame;
+use geodesy;
+use input::InputError;
+use scenario;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::f64::consts::PI;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use strum_macros::{Display, EnumString};
+use toml;
+use toml::value::Datetime;
+use types::Array3d;
+
+// Label of errors which do not stem from a file
+const COMMAND: &str = "pose generate";
+
+/// Orbit class giving the default orbit distributions of the objects drawn.
+#[derive(Deserialize, Display, EnumString, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OrbitClass {
+    /// Low Earth orbits up to 2000 km.
+    #[default]
+    #[strum(serialize = "leo")]
+    Leo,
+    /// Medium Earth orbits between the low and geostationary orbits.
+    #[strum(serialize = "meo")]
+    Meo,
+    /// Geostationary orbits and the surrounding region.
+    #[strum(serialize = "geo")]
+    Geo,
+    /// Geostationary transfer orbits from low perigees.
+    #[strum(serialize = "gto")]
+    Gto,
+}
+
+impl OrbitClass {
+    /// Ranges of the perigee altitude in km, the eccentricity and the inclination in degrees.
+    fn ranges(&self) -> ([f64; 2], [f64; 2], [f64; 2]) {
+        match self {
+            OrbitClass::Leo => ([200.0, 2000.0], [0.0, 0.02], [0.0, 100.0]),
+            OrbitClass::Meo => ([2000.0, 35000.0], [0.0, 0.02], [0.0, 65.0]),
+            OrbitClass::Geo => ([35586.0, 35986.0], [0.0, 0.001], [0.0, 15.0]),
+            OrbitClass::Gto => ([200.0, 1000.0], [0.6, 0.75], [0.0, 55.0]),
+        }
+    }
+}
+
+/// Kind of the objects drawn, giving the default size distributions.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectKind {
+    Debris,
+    Spacecraft,
+}
+
+impl ObjectKind {
+    /// Ranges of the size in meters and the area-to-mass ratio in m^2/kg.
+    fn ranges(&self) -> ([f64; 2], [f64; 2]) {
+        match self {
+            ObjectKind::Debris => ([0.1, 1.0], [0.01, 1.0]),
+            ObjectKind::Spacecraft => ([1.0, 10.0], [0.005, 0.05]),
+        }
+    }
+}
+
+/// Class of objects to draw, the ranges left out are the ones of the orbit class and object kind.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectClass {
+    pub object: ObjectKind,
+    pub count: u32,
+    #[serde(default)]
+    pub orbit: OrbitClass,
+    pub perigee_altitude_km: Option<[f64; 2]>, // Height of the perigee above the equator
+    pub eccentricity: Option<[f64; 2]>,
+    pub inclination_deg: Option<[f64; 2]>,
+    pub size_m: Option<[f64; 2]>, // Diameter of the sphere of the cross-section
+    pub area_to_mass_m2_kg: Option<[f64; 2]>,
+}
+
+/// Population file declaring the classes of objects to draw.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PopulationSpec {
+    pub date: Option<Datetime>, // Date of the input file, the time of generation if none
+    pub seed: Option<u64>,      // Seed of the draws, a new one every run if none
+    #[serde(default, rename = "class")]
+    pub classes: Vec<ObjectClass>,
+}
+
+impl PopulationSpec {
+    /// Reads a population file.
+    pub fn load(path: &str) -> Result<Self, InputError> {
+        let text = fs::read_to_string(path).map_err(|source| InputError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|err| InputError::toml_syntax(path, &err))
+    }
+}
+
+/// Object in the form of the input file.
+#[derive(Serialize)]
+struct GeneratedObject {
+    id: u32,
+    coords: Array3d,
+    velocity: Array3d,
+    drag_area: f64,
+    mass: f64,
+}
+
+/// Input file of a generated population.
+#[derive(Serialize)]
+pub struct Population {
+    date: String,
+    frame: Frame,
+    debris: Vec<GeneratedObject>,
+    spacecraft: Vec<GeneratedObject>,
+}
+
+/// Draws the objects of every class.
+///
+/// ### Arguments
+/// * 'classes' - The classes of objects to draw.
+/// * 'date' - The date of the input file.
+/// * 'seed' - The seed of the draws.
+///
+/// ### Return
+///     The population, or an error naming the classes with invalid distributions.
+///
+pub fn generate(
+    classes: &[ObjectClass],
+    date: &DateTime<Utc>,
+    seed: u64,
+) -> Result<Population, String> {
+    let mu = bodies::earth_gravitational_parameter();
+    let mut random = Random::new(seed);
+    let mut population = Population {
+        date: date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        frame: Frame::Eme2000,
+        debris: Vec::new(),
+        spacecraft: Vec::new(),
+    };
+    let mut errors = Vec::new();
+    let mut next_id = 1;
+    for (index, class) in classes.iter().enumerate() {
+        let (altitude, eccentricity, inclination) = class.orbit.ranges();
+        let (size, area_to_mass) = class.object.ranges();
+        let altitude = class.perigee_altitude_km.unwrap_or(altitude);
+        let eccentricity = class.eccentricity.unwrap_or(eccentricity);
+        let inclination = class.inclination_deg.unwrap_or(inclination);
+        let size = class.size_m.unwrap_or(size);
+        let area_to_mass = class.area_to_mass_m2_kg.unwrap_or(area_to_mass);
+
+        let checks = [
+            (altitude, altitude[0] > 0.0, "perigee altitudes"),
+            (
+                eccentricity,
+                eccentricity[0] >= 0.0 && eccentricity[1] < 1.0,
+                "eccentricities",
+            ),
+            (
+                inclination,
+                inclination[0] >= 0.0 && inclination[1] <= 180.0,
+                "inclinations",
+            ),
+            (size, size[0] > 0.0, "sizes"),
+            (area_to_mass, area_to_mass[0] > 0.0, "area-to-mass ratios"),
+        ];
+        let invalid: Vec<&str> = checks
+            .iter()
+            .filter(|(range, valid, _)| !(*valid && range[0] <= range[1]))
+            .map(|(_, _, name)| *name)
+            .collect();
+        if !invalid.is_empty() {
+            errors.push(format!(
+                "Class {}: Invalid ranges of {}.",
+                index + 1,
+                invalid.join(", ")
+            ));
+            continue;
+        }
+
+        for _ in 0..class.count {
+            let perigee_radius_m = geodesy::WGS84_A + random.uniform(altitude) * 1000.0;
+            let eccentricity = random.uniform(eccentricity);
+            let elements = KeplerianElements {
+                semi_major_axis_m: perigee_radius_m / (1.0 - eccentricity),
+                eccentricity,
+                inclination_rad: random.uniform(inclination).to_radians(),
+                raan_rad: random.uniform([0.0, 2.0 * PI]),
+                arg_periapsis_rad: random.uniform([0.0, 2.0 * PI]),
+                true_anomaly_rad: KeplerianElements::true_anomaly_from_mean(
+                    eccentricity,
+                    random.uniform([0.0, 2.0 * PI]),
+                ),
+            };
+            let (coords, velocity) = elements.to_state(mu);
+            let drag_area = PI * random.log_uniform(size).powi(2) / 4.0;
+            let object = GeneratedObject {
+                id: next_id,
+                coords,
+                velocity,
+                drag_area,
+                mass: drag_area / random.log_uniform(area_to_mass),
+            };
+            next_id += 1;
+
+            match class.object {
+                ObjectKind::Debris => population.debris.push(object),
+                ObjectKind::Spacecraft => population.spacecraft.push(object),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    Ok(population)
+}
+
+/// Runs the generate subcommand, writing the input file of the drawn population.
+///
+/// ### Argument
+/// * 'matches' - The arguments of the subcommand.
+///
+pub fn run(matches: &ArgMatches) -> Result<(), InputError> {
+    let population_file = matches.value_of("population");
+    let mut spec = match population_file {
+        Some(path) => PopulationSpec::load(path)?,
+        None => PopulationSpec::default(),
+    };
+    let source = population_file.unwrap_or(COMMAND);
+
+    let orbit = matches
+        .value_of("orbit")
+        .map_or(OrbitClass::default(), |orbit| orbit.parse().unwrap());
+    for (arg, object) in [
+        ("debris", ObjectKind::Debris),
+        ("spacecraft", ObjectKind::Spacecraft),
+    ] {
+        if let Some(count) = matches.value_of(arg) {
+            spec.classes.push(ObjectClass {
+                object,
+                count: count.parse().unwrap(),
+                orbit,
+                perigee_altitude_km: None,
+                eccentricity: None,
+                inclination_deg: None,
+                size_m: None,
+                area_to_mass_m2_kg: None,
+            });
+        }
+    }
+    if spec.classes.is_empty() {
+        return Err(InputError::invalid(
+            source,
+            "No objects to generate, give a population file or a number of debris or spacecraft.",
+        ));
+    }
+
+    let date = match (matches.value_of("date"), &spec.date) {
+        (Some(date), _) => date.parse::<DateTime<Utc>>().unwrap(),
+        (None, Some(date)) => {
+            scenario::to_utc(date).map_err(|err| InputError::invalid(source, err))?
+        }
+        (None, None) => Utc::now(),
+    };
+    let seed = match matches.value_of("seed") {
+        Some(seed) => seed.parse().unwrap(),
+        None => spec.seed.unwrap_or_else(|| {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+            eprintln!("Seed: {}", seed);
+            seed
+        }),
+    };
+
+    let population =
+        generate(&spec.classes, &date, seed).map_err(|err| InputError::invalid(source, err))?;
+    let text = serde_json::to_string_pretty(&population).expect("Failed to write the population.");
+    match matches.value_of("out") {
+        Some(path) => fs::write(path, text + "\n").map_err(|source| InputError::Io {
+            path: path.to_string(),
+            source,
+        }),
+        None => {
+            println!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+/// Pseudo-random numbers of the xoshiro256** generator seeded by SplitMix64, kept in POSE so that
+/// a seed draws the same population on every platform and release.
+struct Random {
+    state: [u64; 4],
+}
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut split_mix = || {
+            x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+
+        Random {
+            state: [split_mix(), split_mix(), split_mix(), split_mix()],
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    /// Uniform draw within [min, max).
+    fn uniform(&mut self, range: [f64; 2]) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        range[0] + (range[1] - range[0]) * unit
+    }
+
+    /// Draw uniform in the logarithm within [min, max).
+    fn log_uniform(&mut self, range: [f64; 2]) -> f64 {
+        self.uniform([range[0].ln(), range[1].ln()]).exp()
+    }
+}
@@ -0,0 +1,83 @@
+//! Geodetic coordinates on the WGS-84 ellipsoid.
+
+use types::{Array3d, Matrix3d};
+
+// WGS-84 semi-major axis in meters
+pub const WGS84_A: f64 = 6378137.0;
+// WGS-84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// Position on the ellipsoid.
+#[derive(Clone, Copy, Debug)]
+pub struct Geodetic {
+    pub latitude_rad: f64,
+    pub longitude_rad: f64,
+    pub altitude_m: f64, // Height above the ellipsoid
+}
+
+/// Converts a geodetic position into Earth fixed cartesian coordinates.
+///
+/// ### Argument
+/// * 'geodetic' - Latitude, longitude and height above the ellipsoid.
+///
+/// ### Return
+///     The ECEF position in meters.
+///
+pub fn geodetic_to_ecef(geodetic: &Geodetic) -> Array3d {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let (sin_lat, cos_lat) = geodetic.latitude_rad.sin_cos();
+    let (sin_lon, cos_lon) = geodetic.longitude_rad.sin_cos();
+    // Radius of curvature in the prime vertical
+    let n = WGS84_A / (1.0 - e2 * sin_lat.powi(2)).sqrt();
+
+    Array3d {
+        x: (n + geodetic.altitude_m) * cos_lat * cos_lon,
+        y: (n + geodetic.altitude_m) * cos_lat * sin_lon,
+        z: (n * (1.0 - e2) + geodetic.altitude_m) * sin_lat,
+    }
+}
+
+/// Converts Earth fixed cartesian coordinates into a geodetic position.
+///
+/// ### Argument
+/// * 'position' - The ECEF position in meters.
+///
+/// ### Return
+///     Latitude, longitude and height above the ellipsoid.
+///
+pub fn ecef_to_geodetic(position: &Array3d) -> Geodetic {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let p = (position.x.powi(2) + position.y.powi(2)).sqrt();
+
+    // Fixed point iteration on the latitude, converges to below a millimeter within a few steps
+    let mut latitude = position.z.atan2(p * (1.0 - e2));
+    for _ in 0..5 {
+        let sin_lat = latitude.sin();
+        let n = WGS84_A / (1.0 - e2 * sin_lat.powi(2)).sqrt();
+        latitude = (position.z + e2 * n * sin_lat).atan2(p);
+    }
+
+    let (sin_lat, cos_lat) = latitude.sin_cos();
+    Geodetic {
+        latitude_rad: latitude,
+        longitude_rad: position.y.atan2(position.x),
+        // Valid at every latitude, including the poles where p vanishes
+        altitude_m: p * cos_lat + position.z * sin_lat
+            - WGS84_A * (1.0 - e2 * sin_lat.powi(2)).sqrt(),
+    }
+}
+
+/// Rotation from the local east, north, up frame at a geodetic position into ECEF.
+pub fn ecef_from_enu(geodetic: &Geodetic) -> Matrix3d {
+    let (sin_lat, cos_lat) = geodetic.latitude_rad.sin_cos();
+    let (sin_lon, cos_lon) = geodetic.longitude_rad.sin_cos();
+
+    // Columns are the east, north and up unit vectors
+    Matrix3d {
+        m: [
+            [-sin_lon, -sin_lat * cos_lon, cos_lat * cos_lon],
+            [cos_lon, -sin_lat * sin_lon, cos_lat * sin_lon],
+            [0.0, cos_lat, sin_lat],
+        ],
+    }
+}
@@ -1,29 +1,107 @@
 use super::bodies;
 
-use chrono::DateTime;
+use ccsds;
+use chrono::{DateTime, Utc};
+use force_models::{self, l2_norm, ForceModelConfig};
+use frames::{self, Frame, LocalFrame};
+use geodesy;
+use maneuver::Maneuver;
 use clap::ArgMatches;
+use scenario::{self, Scenario};
+use serde::{Deserialize, Serialize};
+use state::InitialState;
+use std::collections::{BTreeSet, HashSet};
 use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
+use std::fmt;
+use std::fs;
+use std::io;
 use std::path::Path;
+use tle;
+use toml;
+use toml::value::Datetime;
 
 pub struct SimulationParameters {
     pub input_bodies_json: String,
     pub output_dir: String,
     pub sim_time_step: f32,
     pub sim_solar_step: f32,
+    pub ephemeris_files: Vec<String>,
+    pub eop_file: Option<String>,
+    pub geodetic_output: bool,
+    pub output_frame: Option<Frame>, // Frame of the output, the frame of the input file if none
+    pub perturbation_frame: Option<LocalFrame>, // Local frame of the perturbation output
+    pub covariance_frame: Option<LocalFrame>, // Local frame of the covariance output
+    pub relative_to: Option<u32>, // ID of the object relative states are written out for
+    pub elements_output: bool,    // Write out the osculating orbital elements of the objects
+    pub mean_elements_output: bool, // Also write out the mean elements about the Earth's equator
+    pub validate_only: bool,      // Check the input without running the simulation
+    pub output_interval: Option<f32>, // Interval between written object states, every step if none
+    pub start_time: Option<DateTime<Utc>>, // Start of the simulation, the input date if none
+    pub end_time: Option<DateTime<Utc>>, // End of the simulation, runs until stopped if none
+    pub catalogs: Vec<Catalog>,   // Catalogs of the scenario, loaded with the input file
+    pub forces: Option<Vec<ForceModelConfig>>, // Force models replacing the ones of the input
+    pub barnes_hut_theta: Option<f64>, // Opening angle replacing the one of the mutual gravity
 }
 
-pub fn gather_program_arguments(matches: ArgMatches) -> SimulationParameters {
-    // Load with defaults
+/// Simulation setup read from the input file.
+pub struct SimulationInput {
+    pub sim_bodies: Vec<bodies::SimobjT>,           // Debris and spacecraft
+    pub start_time: DateTime<chrono::Utc>,          // Datetime at the start of the simulation
+    pub central_body: bodies::CentralBody,          // Body the objects are positioned relative to
+    pub frame: Frame,                               // Frame of the inertial object states
+    pub forces: Option<Vec<ForceModelConfig>>,      // Configured force models
+}
+
+/// Resolves the parameters of the run from the defaults, the scenario file and the command line
+/// flags, each taking precedence over the former.
+///
+/// ### Argument
+/// * 'matches' - The parsed command line.
+///
+/// ### Return
+///     The parameters of the run, or an error if the scenario file is invalid or no input file
+///     is given.
+///
+pub fn gather_program_arguments(matches: ArgMatches) -> Result<SimulationParameters, InputError> {
+    let scenario_file = matches.value_of("scenario").unwrap_or_default();
+    let scenario = match matches.value_of("scenario") {
+        Some(file) => Scenario::load(file)?,
+        None => Scenario::default(),
+    };
+    let scenario_datetime = |datetime: Option<Datetime>| {
+        datetime
+            .map(|datetime| scenario::to_utc(&datetime))
+            .transpose()
+            .map_err(|err| InputError::invalid(scenario_file, err))
+    };
+
+    // Load with defaults, overridden by the scenario
     let mut sim_params: SimulationParameters = SimulationParameters {
-        input_bodies_json: "".to_string(),
-        output_dir: "".to_string(),
-        sim_time_step: 1.0,
-        sim_solar_step: 3600.0 * 12.0, // Every half day
+        input_bodies_json: scenario.input.unwrap_or_default(),
+        output_dir: scenario.output.dir.unwrap_or_default(),
+        sim_time_step: scenario.integrator.step_s.unwrap_or(1.0),
+        sim_solar_step: scenario.integrator.solar_step_s.unwrap_or(3600.0 * 12.0), // Every half day
+        ephemeris_files: scenario.environment.ephemeris.unwrap_or_default(),
+        eop_file: scenario.environment.eop,
+        geodetic_output: scenario.output.geodetic.unwrap_or(false),
+        output_frame: scenario.output.frame,
+        perturbation_frame: scenario.output.perturbation_frame,
+        covariance_frame: scenario.output.covariance_frame,
+        relative_to: scenario.output.relative_to,
+        elements_output: scenario.output.elements.unwrap_or(false),
+        mean_elements_output: scenario.output.mean_elements.unwrap_or(false),
+        validate_only: false,
+        output_interval: scenario.output.interval_s,
+        start_time: scenario_datetime(scenario.start)?,
+        end_time: scenario_datetime(scenario.end)?,
+        catalogs: scenario.catalogs,
+        forces: scenario.forces,
+        barnes_hut_theta: None,
     };
 
-    sim_params.input_bodies_json = matches.value_of("INPUT").unwrap().to_string();
+    if let Some(input) = matches.value_of("INPUT") {
+        sim_params.input_bodies_json = input.to_string();
+    }
     if matches.is_present("sim_time_step") {
         sim_params.sim_time_step = matches
             .value_of("sim_time_step")
@@ -31,27 +109,213 @@ pub fn gather_program_arguments(matches: ArgMatches) -> SimulationParameters {
             .parse::<f32>()
             .unwrap();
     }
+    if let Some(solar_step) = matches.value_of("sim_solar_step") {
+        sim_params.sim_solar_step = solar_step.parse::<f32>().unwrap();
+    }
 
     if matches.is_present("out") {
         sim_params.output_dir = matches.value_of("out").unwrap().to_string()
     }
 
-    sim_params
+    if let Some(theta) = matches.value_of("barnes_hut_theta") {
+        sim_params.barnes_hut_theta = Some(theta.parse::<f64>().unwrap());
+    }
+
+    if let Some(files) = matches.values_of("ephemeris") {
+        sim_params.ephemeris_files = files.map(String::from).collect();
+    }
+
+    if let Some(eop_file) = matches.value_of("eop") {
+        sim_params.eop_file = Some(eop_file.to_string());
+    }
+    sim_params.geodetic_output |= matches.is_present("geodetic");
+    if let Some(frame) = matches.value_of("output_frame") {
+        sim_params.output_frame = Some(frame.parse::<Frame>().unwrap());
+    }
+    if let Some(frame) = matches.value_of("perturbation_frame") {
+        sim_params.perturbation_frame = Some(frame.parse::<LocalFrame>().unwrap());
+    }
+    if let Some(frame) = matches.value_of("covariance_frame") {
+        sim_params.covariance_frame = Some(frame.parse::<LocalFrame>().unwrap());
+    }
+    if let Some(id) = matches.value_of("relative_to") {
+        sim_params.relative_to = Some(id.parse::<u32>().unwrap());
+    }
+    sim_params.elements_output |= matches.is_present("elements");
+    sim_params.mean_elements_output |= matches.is_present("mean_elements");
+    sim_params.validate_only = matches.is_present("validate_only");
+    if let Some(interval) = matches.value_of("output_interval") {
+        sim_params.output_interval = Some(interval.parse::<f32>().unwrap());
+    }
+    if let Some(start) = matches.value_of("start") {
+        sim_params.start_time = Some(start.parse::<DateTime<Utc>>().unwrap());
+    }
+    if let Some(end) = matches.value_of("end") {
+        sim_params.end_time = Some(end.parse::<DateTime<Utc>>().unwrap());
+    }
+
+    if sim_params.input_bodies_json.is_empty() {
+        return Err(InputError::invalid(
+            scenario_file,
+            "No input file given by the command line or the scenario.",
+        ));
+    }
+    let steps = [
+        Some(sim_params.sim_time_step),
+        Some(sim_params.sim_solar_step),
+        sim_params.output_interval,
+    ];
+    if steps.iter().flatten().any(|step| *step <= 0.0) {
+        return Err(InputError::invalid(
+            scenario_file,
+            "The time step, solar step and output interval have to be positive.",
+        ));
+    }
+
+    Ok(sim_params)
+}
+
+/// Error in the input of a simulation, reported in place of running it.
+#[derive(Debug)]
+pub enum InputError {
+    /// The file could not be read.
+    Io { path: String, source: io::Error },
+    /// The file is no valid JSON or does not match the structure of an input file.
+    Syntax {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A value of the file or its catalogs is invalid.
+    Invalid { path: String, message: String },
+    /// Objects failing the sanity checks.
+    Objects {
+        path: String,
+        failed_checks: Vec<FailedCheck>,
+    },
+}
+
+impl InputError {
+    fn syntax(path: &str, err: &serde_json::Error) -> Self {
+        let message = err.to_string();
+        let position = format!(" at line {} column {}", err.line(), err.column());
+
+        InputError::Syntax {
+            path: path.to_string(),
+            line: err.line(),
+            column: err.column(),
+            message: message.strip_suffix(&position).unwrap_or(&message).to_string(),
+        }
+    }
+
+    /// Error of a TOML file which cannot be read into its structure.
+    pub fn toml_syntax(path: &str, err: &toml::de::Error) -> Self {
+        let (line, column) = err
+            .line_col()
+            .map_or((0, 0), |(line, column)| (line + 1, column + 1));
+        let message = err.to_string();
+        let position = format!(" at line {} column {}", line, column);
+
+        InputError::Syntax {
+            path: path.to_string(),
+            line,
+            column,
+            message: message.strip_suffix(&position).unwrap_or(&message).to_string(),
+        }
+    }
+
+    /// Error of an invalid value in a file.
+    pub fn invalid(path: &str, message: impl Into<String>) -> Self {
+        InputError::Invalid {
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Error of a data file which could not be loaded, an I/O error if it could not be read.
+    pub fn load(path: &str, err: Box<dyn Error>) -> Self {
+        match err.downcast::<io::Error>() {
+            Ok(source) => InputError::Io {
+                path: path.to_string(),
+                source: *source,
+            },
+            Err(err) => InputError::invalid(path, err.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Io { path, source } => write!(f, "{}: {}", path, source),
+            InputError::Syntax {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path, line, column, message),
+            InputError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+            InputError::Objects {
+                path,
+                failed_checks,
+            } => {
+                write!(f, "{}: Objects failing the sanity checks", path)?;
+                for failed_check in failed_checks {
+                    write!(f, "\n  {}", failed_check)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Sanity check of the objects and the IDs of the objects failing it.
+#[derive(Debug)]
+pub struct FailedCheck {
+    pub check: &'static str,
+    pub ids: Vec<u32>,
+}
+
+impl fmt::Display for FailedCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ids: Vec<String> = self.ids.iter().map(u32::to_string).collect();
+        write!(f, "{}: {}", self.check, ids.join(", "))
+    }
 }
 
 /// Main entry point into the init sequence
 ///
-/// ### Argument
+/// ### Arguments
 /// * 'file' - The name of the input file containing the bodies
+/// * 'catalogs' - Further catalogs to load, relative to the working directory
 ///
 /// ### Return
-///      A vector of bodies from the input file.
-///      The datetime delta from year 2000-01-01
+///      The bodies, start datetime, central body and force models from the input file and the
+///      catalogs, or an error locating the first problem of the files.
 ///
-pub fn parse_input(file: &str) -> (Vec<bodies::SimobjT>, DateTime<chrono::Utc>) {
+pub fn parse_input(file: &str, catalogs: &[Catalog]) -> Result<SimulationInput, InputError> {
+    let text = fs::read_to_string(file).map_err(|source| InputError::Io {
+        path: file.to_string(),
+        source,
+    })?;
+    if ccsds::is_orbit_message(&text) {
+        return parse_message_input(file, &text, catalogs);
+    }
+
     let mut sim_bodies: Vec<bodies::SimobjT> = Vec::new();
 
-    let ser_objs = read_object_from_file(file).unwrap();
+    let ser_objs: bodies::InitData =
+        serde_json::from_str(&text).map_err(|err| InputError::syntax(file, &err))?;
 
     //add objects to sim_bodies
     for elem in ser_objs.debris {
@@ -66,48 +330,457 @@ pub fn parse_input(file: &str) -> (Vec<bodies::SimobjT>, DateTime<chrono::Utc>)
         sim_bodies.push(p);
     }
 
-    assign_id(&mut sim_bodies);
+    let input_dir = Path::new(file).parent().unwrap_or_else(|| Path::new(""));
+    load_catalogs(&ser_objs.catalogs, input_dir, ser_objs.central_body, &mut sim_bodies)?;
+    load_catalogs(catalogs, Path::new(""), ser_objs.central_body, &mut sim_bodies)?;
+
+    assign_id(&mut sim_bodies).map_err(|err| InputError::invalid(file, err))?;
 
     let datetime = ser_objs.date;
-    let datetime_obj = datetime
-        .parse::<DateTime<chrono::Utc>>()
-        .expect("Input file contains invalid datetime format, expected ISO 8601 format.");
+    let datetime_obj = datetime.parse::<DateTime<chrono::Utc>>().map_err(|_| {
+        InputError::invalid(file, "The 'date' has to be an ISO 8601 datetime.")
+    })?;
 
-    (sim_bodies, datetime_obj)
+    Ok(SimulationInput {
+        sim_bodies,
+        start_time: datetime_obj,
+        central_body: ser_objs.central_body,
+        frame: ser_objs.frame,
+        forces: ser_objs.forces,
+    })
 }
 
-/// Function responsible for handling opening the file and connecting the
-/// serde reader.
+/// Sets up a scenario of a single spacecraft from an orbit parameter or mean-element message
+/// given as the input file, starting at the epoch of the message.
 ///
-/// ### Argument
-/// * 'path' - The path to the input bodies json file.
+/// ### Arguments
+/// * 'file' - The name of the input file.
+/// * 'text' - The contents of the input file.
+/// * 'catalogs' - Further catalogs to load, relative to the working directory.
+///
+fn parse_message_input(
+    file: &str,
+    text: &str,
+    catalogs: &[Catalog],
+) -> Result<SimulationInput, InputError> {
+    let message = ccsds::OrbitMessage::parse(text).map_err(|err| InputError::invalid(file, err))?;
+    let catalog = Catalog {
+        path: file.to_string(),
+        object: CatalogObject::Spacecraft,
+        drag_area: None,
+        mass: None,
+        surface: bodies::SurfaceProperties::default(),
+        gravity_group: None,
+    };
+    let start_time = message.epoch.to_utc();
+    let central_body = message.center;
+    let frame = message.frame;
+    let mut sim_bodies = vec![catalog
+        .create_message_object(message)
+        .map_err(|err| InputError::invalid(file, err))?];
+    load_catalogs(catalogs, Path::new(""), central_body, &mut sim_bodies)?;
+    assign_id(&mut sim_bodies).map_err(|err| InputError::invalid(file, err))?;
+
+    Ok(SimulationInput {
+        sim_bodies,
+        start_time,
+        central_body,
+        frame,
+        forces: None,
+    })
+}
+
+/// Adds the objects of catalogs to the simulation bodies.
+///
+/// ### Arguments
+/// * 'catalogs' - The catalogs to load.
+/// * 'dir' - Directory the catalog paths are relative to.
+/// * 'central_body' - Central body of the simulation.
+/// * 'sim_bodies' - The simulation bodies the objects are added to.
+///
+fn load_catalogs(
+    catalogs: &[Catalog],
+    dir: &Path,
+    central_body: bodies::CentralBody,
+    sim_bodies: &mut Vec<bodies::SimobjT>,
+) -> Result<(), InputError> {
+    for catalog in catalogs {
+        let catalog_bodies = catalog
+            .load(dir, central_body)
+            .map_err(|err| InputError::invalid(&dir.join(&catalog.path).to_string_lossy(), err))?;
+        sim_bodies.extend(catalog_bodies);
+    }
+
+    Ok(())
+}
+
+/// Kind of simulation object created for the objects of a catalog.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogObject {
+    #[default]
+    Debris,
+    Spacecraft,
+}
+
+/// File of further objects referenced by the input file: a TLE or 3LE catalog or a CCSDS orbit
+/// parameter or mean-element message. The physical properties apply to all objects of the file,
+/// the ones given by a message take precedence. Objects keep the names and catalog numbers of the
+/// file as their names and IDs.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Catalog {
+    pub path: String, // Catalog or message file, relative to the input file
+    #[serde(default)]
+    pub object: CatalogObject, // Kind of the created objects
+    #[serde(default)]
+    pub drag_area: Option<f64>, // Average cross-section in m^2
+    #[serde(default)]
+    pub mass: Option<f64>, // kg
+    #[serde(flatten)]
+    pub surface: bodies::SurfaceProperties,
+    #[serde(default)]
+    pub gravity_group: Option<String>, // Objects sharing a group attract each other
+}
+
+impl Catalog {
+    /// Reads the catalog and creates an object for each of its element sets, or the object of
+    /// its message.
+    ///
+    /// ### Arguments
+    /// * 'input_dir' - Directory of the input file the catalog path is relative to.
+    /// * 'central_body' - Central body of the simulation.
+    ///
+    /// ### Return
+    ///     The objects of the catalog in the order of the file, or an error naming the offending
+    ///     line or object.
+    ///
+    pub fn load(
+        &self,
+        input_dir: &Path,
+        central_body: bodies::CentralBody,
+    ) -> Result<Vec<bodies::SimobjT>, String> {
+        let text = fs::read_to_string(input_dir.join(&self.path)).map_err(|err| err.to_string())?;
+
+        if ccsds::is_orbit_message(&text) {
+            let message = ccsds::OrbitMessage::parse(&text)?;
+            if message.center != central_body {
+                return Err(format!(
+                    "{}: The message is relative to {:?}, the simulation to {:?}.",
+                    message.object_name, message.center, central_body
+                ));
+            }
+            return Ok(vec![self.create_message_object(message)?]);
+        }
+
+        tle::parse_element_sets(&text)?
+            .into_iter()
+            .map(|element_set| {
+                let designation = element_set.designation();
+                self.create_object(
+                    element_set.name.clone(),
+                    InitialState::Tle(element_set),
+                    None,
+                    None,
+                    bodies::SurfaceProperties::default(),
+                    Vec::new(),
+                )
+                .map_err(|err| format!("{}: {}", designation, err))
+            })
+            .collect()
+    }
+
+    fn create_message_object(
+        &self,
+        message: ccsds::OrbitMessage,
+    ) -> Result<bodies::SimobjT, String> {
+        let name = message.object_name;
+        self.create_object(
+            Some(name.clone()),
+            message.state,
+            message.drag_area,
+            message.mass,
+            message.surface,
+            message.maneuvers,
+        )
+        .map_err(|err| format!("{}: {}", name, err))
+    }
+
+    /// Creates an object with the properties of the catalog where the file leaves them out. The
+    /// catalog number of an element set becomes the ID of the object.
+    fn create_object(
+        &self,
+        name: Option<String>,
+        state: InitialState,
+        drag_area: Option<f64>,
+        mass: Option<f64>,
+        surface: bodies::SurfaceProperties,
+        maneuvers: Vec<Maneuver>,
+    ) -> Result<bodies::SimobjT, String> {
+        let drag_area = drag_area
+            .or(self.drag_area)
+            .ok_or("No drag area given by the file or the 'drag_area' of its catalog.")?;
+        let mass = mass
+            .or(self.mass)
+            .ok_or("No mass given by the file or the 'mass' of its catalog.")?;
+        let surface = surface.or(self.surface);
+        let gravity_group = self.gravity_group.clone();
+        let catalog_number = state.catalog_number();
+
+        let mut object: bodies::SimobjT = match self.object {
+            CatalogObject::Debris if !maneuvers.is_empty() => {
+                return Err("Maneuvers require the object to be a spacecraft.".to_string())
+            }
+            CatalogObject::Debris => Box::new(bodies::Debris::new(
+                name,
+                state,
+                drag_area,
+                mass,
+                surface,
+                gravity_group,
+            )?),
+            CatalogObject::Spacecraft => Box::new(bodies::Spacecraft::new(
+                name,
+                state,
+                drag_area,
+                mass,
+                surface,
+                gravity_group,
+                maneuvers,
+            )?),
+        };
+        *object.id_mut() = catalog_number;
+
+        Ok(object)
+    }
+}
+
+/// Moves every object to its initial state, converting the states given in other frames into the
+/// simulation frame, along with their covariances and the attitudes of spacecraft.
+///
+/// ### Arguments
+/// * 'sim_bodies' - A vector containing both debris and spacecraft objects.
+/// * 'frame' - The frame of the inertial states in the input file.
+/// * 'env' - The Simulation environment at the start epoch.
+///
+/// ### Return
+///     The kinds of initial state of the objects in their order, or an error naming every object
+///     whose state could not be converted.
+///
+pub fn resolve_initial_states(
+    sim_bodies: &mut [bodies::SimobjT],
+    frame: Frame,
+    env: &bodies::Environment,
+) -> Result<Vec<&'static str>, String> {
+    let mut state_kinds = Vec::with_capacity(sim_bodies.len());
+    let mut errors = Vec::new();
+    for body in sim_bodies.iter_mut() {
+        let state = body.take_initial_state();
+        state_kinds.extend(state.as_ref().map(InitialState::kind));
+        if let Err(err) = resolve_initial_state(body.as_mut(), state, frame, env, env.sim_time_s) {
+            errors.push(format!("Object {}: {}", body.get_id(), err));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    Ok(state_kinds)
+}
+
+/// Moves an object to its initial state at a simulation time, along with its covariance and
+/// attitude.
+///
+/// ### Arguments
+/// * 'body' - The object.
+/// * 'state' - The initial state taken from the object.
+/// * 'frame' - The frame of the inertial states in the input file.
+/// * 'env' - The Simulation environment.
+/// * 'sim_time_s' - Simulation time the object takes its initial state at.
+///
+pub fn resolve_initial_state(
+    body: &mut dyn bodies::Simobj,
+    state: Option<InitialState>,
+    frame: Frame,
+    env: &bodies::Environment,
+    sim_time_s: f64,
+) -> Result<(), String> {
+    if let Some(state) = state {
+        let (coords, velocity) = state.to_simulation_frame(frame, env, sim_time_s)?;
+        body.set_coords(coords);
+        body.set_velocity(velocity);
+    }
+
+    let epoch = env.epoch(sim_time_s);
+    if let Some(covariance) = body.take_initial_covariance() {
+        *body.covariance_mut() = Some(covariance.to_simulation_frame(
+            frame,
+            &epoch,
+            body.get_ref_coords(),
+            body.get_ref_velocity(),
+        )?);
+    }
+
+    body.resolve_attitude(frame, &epoch)
+}
+
+/// Runs the physical sanity checks on the objects at their initial states. Objects within the
+/// sphere of influence of a solar object lighter than the central body are checked for escape
+/// relative to that object.
+///
+/// ### Arguments
+/// * 'file' - The name of the input file.
+/// * 'sim_bodies' - A vector containing both debris and spacecraft objects.
+/// * 'env' - The Simulation environment at the start epoch.
 ///
 /// ### Return
-///      A result object loaded with an IO error on failure or the serde reader on
-///      success.
+///     The checks that only warrant a warning with the objects failing them, and an error with
+///     every other failed check and the IDs of all objects failing it if there are any.
 ///
-fn read_object_from_file<P: AsRef<Path>>(path: P) -> Result<bodies::InitData, Box<dyn Error>> {
-    // Open the file in read-only mode with buffer.
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
+pub fn check_objects(
+    file: &str,
+    sim_bodies: &[bodies::SimobjT],
+    env: &bodies::Environment,
+) -> (Vec<FailedCheck>, Option<InputError>) {
+    let central_body = env.get_solar_objects()[0].get_solar_object();
+    let (mu, radius) = (env.central_gravitational_parameter(), central_body.get_radius_m());
+    let itrf_from_simulation = env.get_earth_orientation().itrf_from_gcrf()
+        * frames::ecliptic_from_equatorial().transpose();
+    // Position, velocity, gravitational parameter and radius of the sphere of influence of the
+    // solar objects other bodies can orbit within
+    let local_bodies: Vec<_> = env
+        .get_solar_objects()
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(idx, solar_obj)| (idx, solar_obj.get_solar_object().get_mass_kg()))
+        .filter(|(_, mass_kg)| *mass_kg < central_body.get_mass_kg())
+        .filter_map(|(idx, mass_kg)| {
+            let position = env.position_from_central(idx, env.sim_time_s)?;
+            let velocity = env.velocity_from_central(idx, env.sim_time_s)?;
+            let influence_radius = position.dot(&position).sqrt()
+                * (mass_kg / central_body.get_mass_kg()).powf(0.4);
+            Some((position, velocity, force_models::G * mass_kg, influence_radius))
+        })
+        .collect();
+    let is_finite = |body: &bodies::SimobjT| {
+        let (coords, velocity) = (body.get_ref_coords(), body.get_ref_velocity());
+        [coords.x, coords.y, coords.z, velocity.x, velocity.y, velocity.z]
+            .iter()
+            .all(|value| value.is_finite())
+    };
+    let below_surface = |body: &bodies::SimobjT| {
+        let coords = body.get_ref_coords();
+        if env.central_is_earth() {
+            geodesy::ecef_to_geodetic(&(itrf_from_simulation * coords)).altitude_m < 0.0
+        } else {
+            coords.dot(coords).sqrt() < radius
+        }
+    };
+    let escaping = |body: &bodies::SimobjT| {
+        let (coords, velocity) = (*body.get_ref_coords(), *body.get_ref_velocity());
+        let (coords, velocity, mu) = local_bodies
+            .iter()
+            .find(|(position, _, _, influence_radius)| {
+                l2_norm(&(coords - position)) < *influence_radius
+            })
+            .map_or((coords, velocity, mu), |(position, body_velocity, body_mu, _)| {
+                (coords - position, velocity - body_velocity, *body_mu)
+            });
+        velocity.dot(&velocity) / 2.0 >= mu / l2_norm(&coords)
+    };
+
+    let errors = failed_checks(
+        sim_bodies,
+        &[
+            ("mass is not positive", &|body| not_positive(body.get_mass())),
+            ("cross-section is not positive", &|body| {
+                not_positive(body.get_cross_section())
+            }),
+            ("position or velocity is not finite", &|body| !is_finite(body)),
+            ("position is below the surface of the central body", &|body| {
+                is_finite(body) && below_surface(body)
+            }),
+        ],
+    );
+    let warnings = failed_checks(
+        sim_bodies,
+        &[("velocity exceeds the escape velocity of the body it orbits", &|body| {
+            is_finite(body) && escaping(body)
+        })],
+    );
+
+    let errors = Some(errors)
+        .filter(|errors| !errors.is_empty())
+        .map(|failed_checks| InputError::Objects {
+            path: file.to_string(),
+            failed_checks,
+        });
+    (warnings, errors)
+}
 
-    // Read the JSON contents of the file as an instance of `Objects`.
-    let u = serde_json::from_reader(reader)?;
+/// Sanity check of a single object, true if the object fails it.
+type ObjectCheck<'a> = dyn Fn(&bodies::SimobjT) -> bool + 'a;
 
-    // Return the `Objects`.
-    Ok(u)
+/// Collects the IDs of the objects failing each check, leaving out the checks passed by all.
+fn failed_checks(
+    sim_bodies: &[bodies::SimobjT],
+    checks: &[(&'static str, &ObjectCheck)],
+) -> Vec<FailedCheck> {
+    checks
+        .iter()
+        .filter_map(|(check, fails)| {
+            let ids: Vec<u32> = sim_bodies
+                .iter()
+                .filter(|body| fails(body))
+                .map(|body| body.get_id())
+                .collect();
+            if ids.is_empty() {
+                None
+            } else {
+                Some(FailedCheck { check, ids })
+            }
+        })
+        .collect()
 }
 
-/// Adds an sequential id value to each of the simulation bodies.
+fn not_positive(value: f64) -> bool {
+    value.is_nan() || value <= 0.0
+}
+
+/// Checks the IDs given by the input for duplicates and assigns the lowest unused IDs to the
+/// simulation bodies without one, in their order.
 ///
 /// ### Argument
 /// * 'sim_bodies' - A vector containing both debris and spacecraft objects.
 ///
-fn assign_id(sim_bodies: &mut Vec<bodies::SimobjT>) {
-    let mut id_inc: u32 = 1;
+/// ### Return
+///     An error listing every ID given to more than one object.
+///
+fn assign_id(sim_bodies: &mut [bodies::SimobjT]) -> Result<(), String> {
+    let mut used_ids = HashSet::new();
+    let mut duplicate_ids = BTreeSet::new();
+    for body in sim_bodies.iter_mut() {
+        if let Some(id) = *body.id_mut() {
+            if !used_ids.insert(id) {
+                duplicate_ids.insert(id);
+            }
+        }
+    }
+    if !duplicate_ids.is_empty() {
+        let duplicate_ids: Vec<String> = duplicate_ids.iter().map(u32::to_string).collect();
+        return Err(format!(
+            "IDs given to more than one object: {}.",
+            duplicate_ids.join(", ")
+        ));
+    }
 
-    for body in sim_bodies {
-        *body.id_mut() = id_inc;
-        id_inc += 1;
+    let mut free_ids = (1u32..).filter(|id| !used_ids.contains(id));
+    for body in sim_bodies.iter_mut() {
+        let id = body.id_mut();
+        if id.is_none() {
+            *id = free_ids.next();
+        }
     }
+
+    Ok(())
 }
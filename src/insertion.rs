@@ -0,0 +1,140 @@
+//! Objects inserted into the simulation during the run, such as launches and deployments.
+//!
+//! The state of an inserted object is its state at the insertion epoch. The object enters the
+//! simulation in the step containing its insertion epoch and is advanced from the insertion to
+//! the end of that step, after which it moves with the other objects.
+
+use crate::bodies;
+use crate::input;
+use crate::state::InitialState;
+use frames::Frame;
+use time;
+
+/// Object waiting to be inserted at a simulation time.
+struct ScheduledInsertion {
+    sim_time_s: f64,
+    body: bodies::SimobjT,
+    state: Option<InitialState>, // Initial state taken from the object, converted on insertion
+}
+
+/// Pending insertions of objects.
+pub struct InsertionSchedule {
+    frame: Frame,                     // Frame of the inertial states in the input file
+    pending: Vec<ScheduledInsertion>, // Latest first, so the next one due is at the end
+}
+
+impl InsertionSchedule {
+    /// Moves the objects with an insertion epoch out of the simulation objects. Their states are
+    /// tried at the start epoch, so that invalid states are reported before the run.
+    ///
+    /// ### Arguments
+    /// * 'sim_bodies' - All objects of the input, left with the ones present from the start.
+    /// * 'frame' - The frame of the inertial states in the input file.
+    /// * 'env' - The Simulation environment at the start epoch.
+    ///
+    /// ### Return
+    ///     The schedule or an error naming every object with an invalid insertion.
+    ///
+    pub fn new(
+        sim_bodies: &mut Vec<bodies::SimobjT>,
+        frame: Frame,
+        env: &bodies::Environment,
+    ) -> Result<Self, String> {
+        let mut present = Vec::with_capacity(sim_bodies.len());
+        let mut pending = Vec::new();
+        let mut errors = Vec::new();
+        for mut body in sim_bodies.drain(..) {
+            let insert_epoch = match body.get_insert_epoch() {
+                Some(insert_epoch) => *insert_epoch,
+                None => {
+                    present.push(body);
+                    continue;
+                }
+            };
+            let sim_time_s = time::Epoch::from_utc(&insert_epoch).seconds_since(&env.start_epoch);
+            if sim_time_s < 0f64 {
+                errors.push(format!(
+                    "Object {}: Insertion at {} is before the start of the simulation.",
+                    body.get_id(),
+                    insert_epoch.to_rfc3339()
+                ));
+                continue;
+            }
+
+            let state = body.take_initial_state();
+            if let Some(Err(err)) = state
+                .as_ref()
+                .map(|state| state.to_simulation_frame(frame, env, env.sim_time_s))
+            {
+                errors.push(format!("Object {}: {}", body.get_id(), err));
+            }
+            pending.push(ScheduledInsertion {
+                sim_time_s,
+                body,
+                state,
+            });
+        }
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+
+        *sim_bodies = present;
+        pending.sort_by(|a, b| b.sim_time_s.total_cmp(&a.sim_time_s));
+
+        Ok(InsertionSchedule { frame, pending })
+    }
+
+    /// The objects left to be inserted in the order of their insertion, with the kinds of their
+    /// initial states and the simulation times of their insertion.
+    pub fn pending(&self) -> impl Iterator<Item = (&bodies::SimobjT, &'static str, f64)> {
+        self.pending.iter().rev().map(|insertion| {
+            (
+                &insertion.body,
+                insertion.state.as_ref().map_or("", InitialState::kind),
+                insertion.sim_time_s,
+            )
+        })
+    }
+
+    /// Takes the objects inserted before the end of a step, moved to their states at the
+    /// insertion. Objects whose states cannot be converted at their insertion are left out with a
+    /// warning.
+    ///
+    /// ### Arguments
+    /// * 'env' - The Simulation environment at the start of the step.
+    /// * 'step_end_s' - Simulation time at the end of the step in seconds.
+    ///
+    /// ### Return
+    ///     The inserted objects with the simulation times of their insertion.
+    ///
+    pub fn take_due(
+        &mut self,
+        env: &bodies::Environment,
+        step_end_s: f64,
+    ) -> Vec<(bodies::SimobjT, f64)> {
+        let mut due = Vec::new();
+        while self
+            .pending
+            .last()
+            .is_some_and(|insertion| insertion.sim_time_s < step_end_s)
+        {
+            let mut insertion = self.pending.pop().unwrap();
+            match input::resolve_initial_state(
+                insertion.body.as_mut(),
+                insertion.state,
+                self.frame,
+                env,
+                insertion.sim_time_s,
+            ) {
+                Ok(()) => due.push((insertion.body, insertion.sim_time_s)),
+                Err(err) => eprintln!(
+                    "warning: Object {} is not inserted: {}",
+                    insertion.body.get_id(),
+                    err
+                ),
+            }
+        }
+
+        due
+    }
+}
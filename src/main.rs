@@ -12,15 +12,75 @@ extern crate serde;
 extern crate serde_json;
 extern crate strum;
 extern crate strum_macros;
+extern crate toml;
 
+mod attitude;
+mod barnes_hut;
 mod bodies;
+mod ccsds;
+mod covariance;
+mod elements;
+mod eop;
+mod ephemeris;
+mod force_models;
+mod frames;
+mod generate;
+mod geodesy;
 mod input;
+mod insertion;
+mod maneuver;
 mod output;
+mod scenario;
 mod sim_cpu;
+mod state;
+mod time;
+mod tle;
 mod types;
 
+use chrono::DurationRound;
+use std::rc::Rc;
+
 mod cli {
 
+    ///Checks if value passed in to program argument is an object ID. Returns a Result
+    ///
+    ///# Argument
+    ///* 'strng' - The value passed by the user
+    ///
+    fn id_validator(strng: String) -> Result<(), String> {
+        if strng.parse::<u32>().is_ok() {
+            Ok(())
+        } else {
+            Err(String::from("Input is not an object ID"))
+        }
+    }
+
+    ///Checks if value passed in to program argument is a count of objects. Returns a Result
+    ///
+    ///# Argument
+    ///* 'strng' - The value passed by the user
+    ///
+    fn count_validator(strng: String) -> Result<(), String> {
+        if strng.parse::<u32>().is_ok() {
+            Ok(())
+        } else {
+            Err(String::from("Input is not a count"))
+        }
+    }
+
+    ///Checks if value passed in to program argument is a non-negative integer. Returns a Result
+    ///
+    ///# Argument
+    ///* 'strng' - The value passed by the user
+    ///
+    fn unsigned_validator(strng: String) -> Result<(), String> {
+        if strng.parse::<u64>().is_ok() {
+            Ok(())
+        } else {
+            Err(String::from("Input is not a non-negative integer"))
+        }
+    }
+
     ///Checks if value passed in to program argument is numeric. Returns a Result
     ///
     ///# Argument
@@ -34,6 +94,19 @@ mod cli {
         }
     }
 
+    ///Checks if value passed in to program argument is an ISO 8601 datetime. Returns a Result
+    ///
+    ///# Argument
+    ///* 'strng' - The value passed by the user
+    ///
+    fn datetime_validator(strng: String) -> Result<(), String> {
+        if strng.parse::<chrono::DateTime<chrono::Utc>>().is_ok() {
+            Ok(())
+        } else {
+            Err(String::from("Input is not an ISO 8601 datetime"))
+        }
+    }
+
     /// Defines the argument structure for the pose simulation program
     /// Returns the result of user arguments passed over the cli
     pub fn check_cli() -> clap::ArgMatches<'static> {
@@ -44,8 +117,14 @@ mod cli {
             .args(&[
                 clap::Arg::with_name("INPUT")
                     .help("json file containing information on bodies at initialization.")
-                    .required(true)
+                    .required_unless("scenario")
                     .index(1),
+                clap::Arg::with_name("scenario")
+                    .help("TOML scenario file declaring the parameters of the run, overridden by the other flags")
+                    .short("c")
+                    .long("scenario")
+                    .value_name("FILE")
+                    .takes_value(true),
                 clap::Arg::with_name("out")
                     .help("Output specifier")
                     .short("o")
@@ -58,22 +137,330 @@ mod cli {
                     .long("step")
                     .value_name("STEP_INTERVAL")
                     .takes_value(true)
-                    .validator(numeric_validator)
+                    .validator(numeric_validator),
+                clap::Arg::with_name("sim_solar_step")
+                    .help("Interval between refits of the solar system object positions in seconds")
+                    .long("solar-step")
+                    .value_name("STEP_INTERVAL")
+                    .takes_value(true)
+                    .validator(numeric_validator),
+                clap::Arg::with_name("start")
+                    .help("Start of the simulation in ISO 8601 format, overrides the date of the input file")
+                    .long("start")
+                    .value_name("DATETIME")
+                    .takes_value(true)
+                    .validator(datetime_validator),
+                clap::Arg::with_name("end")
+                    .help("End of the simulation in ISO 8601 format. Without it the simulation runs until stopped.")
+                    .long("end")
+                    .value_name("DATETIME")
+                    .takes_value(true)
+                    .validator(datetime_validator),
+                clap::Arg::with_name("output_interval")
+                    .help("Interval between the written out object states in seconds, every step without it")
+                    .long("output-interval")
+                    .value_name("INTERVAL")
+                    .takes_value(true)
+                    .validator(numeric_validator),
+                clap::Arg::with_name("ephemeris")
+                    .help("JPL development ephemeris (DE4xx) used for the Sun and Moon. Either a binary file or an ASCII header followed by its data files.")
+                    .short("e")
+                    .long("ephemeris")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1),
+                clap::Arg::with_name("barnes_hut_theta")
+                    .help("Opening angle of the Barnes-Hut approximation used for mutual gravity between objects sharing a gravity group, overrides the 'theta' of the mutual_gravity force model")
+                    .long("theta")
+                    .value_name("THETA")
+                    .takes_value(true)
+                    .validator(numeric_validator),
+                clap::Arg::with_name("eop")
+                    .help("IERS finals (IAU-1980) or finals2000A Earth orientation parameter file. Without it polar motion, UT1-UTC and the nutation corrections are zero, the nutation corrections are only read from IAU-1980 files.")
+                    .long("eop")
+                    .value_name("FILE")
+                    .takes_value(true),
+                clap::Arg::with_name("geodetic")
+                    .help("Adds geodetic latitude, longitude, altitude and local solar time of Earth orbiting objects to the output")
+                    .long("geodetic"),
+                clap::Arg::with_name("output_frame")
+                    .help("Inertial frame of the output coordinates, velocities and accelerations. Defaults to the frame of the input file.")
+                    .long("output-frame")
+                    .value_name("FRAME")
+                    .takes_value(true)
+                    .possible_values(&["ecliptic_j2000", "ecliptic_of_date", "eme2000", "gcrf", "tod", "teme"]),
+                clap::Arg::with_name("perturbation_frame")
+                    .help("Local orbital frame of each object the perturbation accelerations are written out in, instead of the output frame")
+                    .long("perturbation-frame")
+                    .value_name("FRAME")
+                    .takes_value(true)
+                    .possible_values(&["rtn", "ric", "lvlh", "vnb"]),
+                clap::Arg::with_name("covariance_frame")
+                    .help("Local orbital frame of each object the state covariances are written out in, instead of the output frame")
+                    .long("covariance-frame")
+                    .value_name("FRAME")
+                    .takes_value(true)
+                    .possible_values(&["rtn", "ric", "lvlh", "vnb"]),
+                clap::Arg::with_name("relative_to")
+                    .help("Writes out the states of all objects relative to the object with this ID, in its RTN frame")
+                    .long("relative-to")
+                    .value_name("ID")
+                    .takes_value(true)
+                    .validator(id_validator),
+                clap::Arg::with_name("elements")
+                    .help("Writes out the osculating Keplerian and equinoctial elements of every object about the central body, referred to the output frame")
+                    .long("elements"),
+                clap::Arg::with_name("mean_elements")
+                    .help("Adds the Brouwer-Lyddane mean semi-major axis, eccentricity, inclination and RAAN about the Earth's equator to the written out elements, requires the Earth as central body and the j2 force model")
+                    .long("mean-elements"),
+                clap::Arg::with_name("validate_only")
+                    .help("Reads and checks the input, reporting all problems found, without running the simulation")
+                    .long("validate-only")
             ])
+            .setting(clap::AppSettings::SubcommandsNegateReqs)
+            .subcommand(clap::SubCommand::with_name("generate")
+                .about("Generates an input file of objects about the Earth drawn at random from orbit classes")
+                .args(&[
+                    clap::Arg::with_name("population")
+                        .help("TOML population file declaring the classes of objects with their counts and distributions")
+                        .short("p")
+                        .long("population")
+                        .value_name("FILE")
+                        .takes_value(true),
+                    clap::Arg::with_name("debris")
+                        .help("Number of debris drawn from the orbit class, in addition to the population file")
+                        .long("debris")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .validator(count_validator),
+                    clap::Arg::with_name("spacecraft")
+                        .help("Number of spacecraft drawn from the orbit class, in addition to the population file")
+                        .long("spacecraft")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .validator(count_validator),
+                    clap::Arg::with_name("orbit")
+                        .help("Orbit class of the debris and spacecraft counts, leo without it")
+                        .long("orbit")
+                        .value_name("CLASS")
+                        .takes_value(true)
+                        .possible_values(&["leo", "meo", "geo", "gto"]),
+                    clap::Arg::with_name("seed")
+                        .help("Seed of the random draws, overrides the one of the population file. Without either a new seed is drawn and reported.")
+                        .long("seed")
+                        .value_name("SEED")
+                        .takes_value(true)
+                        .validator(unsigned_validator),
+                    clap::Arg::with_name("date")
+                        .help("Date of the input file in ISO 8601 format, overrides the one of the population file. Defaults to now.")
+                        .long("date")
+                        .value_name("DATETIME")
+                        .takes_value(true)
+                        .validator(datetime_validator),
+                    clap::Arg::with_name("out")
+                        .help("Input file written, printed out without it")
+                        .short("o")
+                        .long("out")
+                        .value_name("FILE")
+                        .takes_value(true)
+                ]))
             .get_matches()
     }
 }
 
+/// Reports an invalid input and exits without running the simulation.
+fn exit_with_error(err: &input::InputError) -> ! {
+    eprintln!("error: {}", err);
+    std::process::exit(1)
+}
+
 fn main() {
     let matches = cli::check_cli();
-    let sim_params = input::gather_program_arguments(matches);
+    if let Some(generate_matches) = matches.subcommand_matches("generate") {
+        generate::run(generate_matches).unwrap_or_else(|err| exit_with_error(&err));
+        return;
+    }
+    let mut sim_params =
+        input::gather_program_arguments(matches).unwrap_or_else(|err| exit_with_error(&err));
 
-    let (sim_bodies, start_time) = input::parse_input(sim_params.input_bodies_json.as_str());
-    let env = bodies::Environment::new(start_time);
+    let input_file = sim_params.input_bodies_json.clone();
+    let mut sim_input = input::parse_input(&input_file, &sim_params.catalogs)
+        .unwrap_or_else(|err| exit_with_error(&err));
+    if let Some(start_time) = sim_params.start_time {
+        sim_input.start_time = start_time;
+    }
+    if let Some(end_time) = sim_params.end_time {
+        if end_time <= sim_input.start_time {
+            exit_with_error(&input::InputError::invalid(
+                &input_file,
+                "The end of the simulation has to be after its start.",
+            ));
+        }
+    }
+    let ephemeris = if sim_params.ephemeris_files.is_empty() {
+        None
+    } else {
+        Some(Rc::new(
+            ephemeris::JplEphemeris::load(&sim_params.ephemeris_files).unwrap_or_else(|err| {
+                exit_with_error(&input::InputError::load(&sim_params.ephemeris_files[0], err))
+            }),
+        ))
+    };
+    if let Some(ephemeris) = &ephemeris {
+        // The solar objects are fitted up to a solar step ahead of the simulation time, and the
+        // final states can be written out up to a time step after the end
+        let fit_span_s = (sim_params.sim_solar_step + sim_params.sim_time_step) as f64;
+        let start_jd = time::Epoch::from_utc(&sim_input.start_time).jd_tdb();
+        ephemeris
+            .check_span(start_jd, start_jd)
+            .unwrap_or_else(|err| exit_with_error(&input::InputError::invalid(&input_file, err)));
+        let end_time = *sim_params.end_time.get_or_insert_with(|| {
+            // Whole minutes keep the end within the span across the TDB and UTC conversions
+            let end_time = time::Epoch::from_jd_tt(ephemeris.end_jd)
+                .offset(-fit_span_s)
+                .to_utc()
+                .duration_trunc(chrono::Duration::minutes(1))
+                .unwrap();
+            eprintln!(
+                "warning: {}: The simulation stops at {}, the end of the DE{} ephemeris.",
+                input_file,
+                end_time.to_rfc3339(),
+                ephemeris.de_number
+            );
+            end_time
+        });
+        ephemeris
+            .check_span(
+                start_jd,
+                time::Epoch::from_utc(&end_time).offset(fit_span_s).jd_tdb(),
+            )
+            .unwrap_or_else(|err| exit_with_error(&input::InputError::invalid(&input_file, err)));
+    }
+    let eop = sim_params.eop_file.as_ref().map(|path| {
+        eop::EopTable::load(path)
+            .unwrap_or_else(|err| exit_with_error(&input::InputError::load(path, err)))
+    });
+    if let Some(eop) = &eop {
+        // Runs without an end are only checked at their start
+        let start_mjd = time::Epoch::from_utc(&sim_input.start_time).mjd_utc();
+        let end_mjd = sim_params
+            .end_time
+            .map_or(start_mjd, |end_time| time::Epoch::from_utc(&end_time).mjd_utc());
+        if let Err(err) = eop.check_span(start_mjd, end_mjd) {
+            eprintln!("warning: {}: {}", input_file, err);
+        }
+    }
+    let env = bodies::Environment::new(
+        sim_input.start_time,
+        sim_input.central_body,
+        ephemeris,
+        eop,
+        sim_params.sim_solar_step as f64,
+    )
+    .unwrap_or_else(|err| exit_with_error(&input::InputError::invalid(&input_file, err)));
+
+    let mut sim_bodies = sim_input.sim_bodies;
+    let maneuvers = maneuver::ManeuverSchedule::new(&mut sim_bodies, &env.start_epoch)
+        .unwrap_or_else(|err| exit_with_error(&input::InputError::invalid(&input_file, err)));
+    let insertions = insertion::InsertionSchedule::new(&mut sim_bodies, sim_input.frame, &env)
+        .unwrap_or_else(|err| exit_with_error(&input::InputError::invalid(&input_file, err)));
+    let state_kinds = input::resolve_initial_states(&mut sim_bodies, sim_input.frame, &env)
+        .unwrap_or_else(|err| exit_with_error(&input::InputError::invalid(&input_file, err)));
+    let (warnings, errors) = input::check_objects(&input_file, &sim_bodies, &env);
+    for warning in &warnings {
+        eprintln!("warning: {}: {}", input_file, warning);
+    }
+    if let Some(err) = errors {
+        exit_with_error(&err);
+    }
+    sim_params.output_frame.get_or_insert(sim_input.frame);
+    if let Some(reference_id) = sim_params.relative_to {
+        let mut ids = sim_bodies
+            .iter()
+            .chain(insertions.pending().map(|(body, _, _)| body))
+            .map(|body| body.get_id());
+        if !ids.any(|id| id == reference_id) {
+            exit_with_error(&input::InputError::invalid(
+                &input_file,
+                format!("No object with the ID {} to write out relative states for.", reference_id),
+            ));
+        }
+    }
+
+    let mut force_configs = sim_params
+        .forces
+        .clone()
+        .or(sim_input.forces)
+        .unwrap_or_else(|| force_models::ForceRegistry::default_configs(&env));
+    if let Some(theta) = sim_params.barnes_hut_theta {
+        let mut mutual_gravity = force_configs
+            .iter_mut()
+            .filter(|config| config.name == "mutual_gravity")
+            .peekable();
+        if mutual_gravity.peek().is_none() {
+            eprintln!(
+                "warning: {}: The opening angle --theta is unused, mutual_gravity is not among the force models.",
+                input_file
+            );
+        }
+        for config in mutual_gravity {
+            config.params.insert("theta".to_string(), theta);
+        }
+    }
+    if sim_params.mean_elements_output && sim_input.central_body != bodies::CentralBody::Earth {
+        exit_with_error(&input::InputError::invalid(
+            &input_file,
+            "Mean elements can only be written out about the Earth as central body.",
+        ));
+    }
+    // The mean elements remove the periodic effects of J2, meaningless if it is not simulated
+    if sim_params.mean_elements_output && !force_configs.iter().any(|config| config.name == "j2") {
+        exit_with_error(&input::InputError::invalid(
+            &input_file,
+            "Mean elements require the force model 'j2' to be simulated.",
+        ));
+    }
+    let force_models = force_models::ForceRegistry::new(&env)
+        .build(&force_configs, &env)
+        .unwrap_or_else(|err| exit_with_error(&input::InputError::invalid(&input_file, err)));
 
-    let output_controller = Box::new(output::csv_output::CSVController::new(
-        sim_params.output_dir.as_str(),
+    if sim_params.validate_only {
+        println!(
+            "{}: No problems found, objects: {}.",
+            input_file,
+            sim_bodies.len() + insertions.pending().count()
+        );
+        return;
+    }
+
+    let mut output_controller: Box<dyn output::SimulationOutput> =
+        Box::new(output::csv_output::CSVController::new(sim_params.output_dir.as_str()));
+    output_controller.write_scenario(scenario::Scenario::resolved(
+        &sim_params,
+        &sim_input.start_time,
+        &force_configs,
+    ));
+    output_controller.write_manifest(output::RunManifest::new(
+        sim_params.input_bodies_json.as_str(),
+        sim_input.central_body,
+        sim_params.output_frame.unwrap_or_default(),
+        &env,
+        &sim_bodies,
+        &state_kinds,
+        &insertions,
     ));
 
-    sim_cpu::simulate(sim_bodies, env, output_controller, sim_params);
+    if let Err(err) = sim_cpu::simulate(
+        sim_bodies,
+        env,
+        force_models,
+        output_controller,
+        maneuvers,
+        insertions,
+        sim_params,
+    ) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
 }
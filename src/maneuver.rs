@@ -0,0 +1,120 @@
+//! Impulsive maneuvers of the spacecraft.
+//!
+//! Maneuvers are velocity changes along the axes of a local orbital frame of the spacecraft at
+//! the time of the burn. They are applied at the start of the simulation step containing their
+//! epoch, before the forces of the step.
+
+use crate::bodies;
+use chrono::{DateTime, Utc};
+use frames::LocalFrame;
+use serde::Deserialize;
+use time;
+use types::Array3d;
+
+/// Impulsive velocity change as provided by the input file.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Maneuver {
+    pub epoch: String,     // Datetime of the burn in ISO 8601 format
+    pub delta_v: Array3d,  // Velocity change along the axes of the local frame in m/s
+    pub frame: LocalFrame, // Local frame of the velocity change
+}
+
+/// Maneuver of an object at a simulation time.
+struct ScheduledManeuver {
+    object_id: u32, // ID of the object, which may only be inserted later
+    sim_time_s: f64,
+    delta_v: Array3d,
+    frame: LocalFrame,
+}
+
+/// Pending maneuvers of all simulation objects.
+pub struct ManeuverSchedule {
+    pending: Vec<ScheduledManeuver>, // Latest first, so the next one due is at the end
+}
+
+impl ManeuverSchedule {
+    /// Collects the maneuvers of every object.
+    ///
+    /// ### Arguments
+    /// * 'sim_bodies' - A vector containing both debris and spacecraft objects.
+    /// * 'start_epoch' - The epoch at the start of the simulation.
+    ///
+    /// ### Return
+    ///     The schedule or an error naming the object with an invalid maneuver.
+    ///
+    pub fn new(
+        sim_bodies: &mut [bodies::SimobjT],
+        start_epoch: &time::Epoch,
+    ) -> Result<Self, String> {
+        let mut pending = Vec::new();
+        for body in sim_bodies.iter_mut() {
+            let insert_s = body.get_insert_epoch().map_or(0f64, |insert_epoch| {
+                time::Epoch::from_utc(insert_epoch).seconds_since(start_epoch)
+            });
+            for maneuver in body.take_maneuvers() {
+                let datetime = maneuver.epoch.parse::<DateTime<Utc>>().map_err(|_| {
+                    format!(
+                        "Object {}: Invalid maneuver datetime '{}', expected ISO 8601 format.",
+                        body.get_id(),
+                        maneuver.epoch
+                    )
+                })?;
+                let sim_time_s = time::Epoch::from_utc(&datetime).seconds_since(start_epoch);
+                if sim_time_s < 0f64 {
+                    return Err(format!(
+                        "Object {}: Maneuver at {} is before the start of the simulation.",
+                        body.get_id(),
+                        maneuver.epoch
+                    ));
+                }
+                if sim_time_s < insert_s {
+                    return Err(format!(
+                        "Object {}: Maneuver at {} is before the insertion of the object.",
+                        body.get_id(),
+                        maneuver.epoch
+                    ));
+                }
+
+                pending.push(ScheduledManeuver {
+                    object_id: body.get_id(),
+                    sim_time_s,
+                    delta_v: maneuver.delta_v,
+                    frame: maneuver.frame,
+                });
+            }
+        }
+        pending.sort_by(|a, b| b.sim_time_s.total_cmp(&a.sim_time_s));
+
+        Ok(ManeuverSchedule { pending })
+    }
+
+    /// Applies the maneuvers due before the end of a step.
+    ///
+    /// ### Arguments
+    /// * 'sim_bodies' - The objects in the simulation, including the ones inserted during the step.
+    /// * 'step_end_s' - Simulation time at the end of the step in seconds.
+    ///
+    pub fn apply_due(&mut self, sim_bodies: &mut [bodies::SimobjT], step_end_s: f64) {
+        while self
+            .pending
+            .last()
+            .is_some_and(|maneuver| maneuver.sim_time_s < step_end_s)
+        {
+            let maneuver = self.pending.pop().unwrap();
+            let body = match sim_bodies
+                .iter_mut()
+                .find(|body| body.get_id() == maneuver.object_id)
+            {
+                Some(body) => body,
+                None => continue,
+            };
+            let velocity = *body.get_ref_velocity();
+            let inertial_from_local = maneuver
+                .frame
+                .local_from_inertial(body.get_ref_coords(), &velocity)
+                .transpose();
+
+            body.set_velocity(velocity + inertial_from_local * maneuver.delta_v);
+        }
+    }
+}
@@ -1,9 +1,49 @@
+use attitude::{Attitude, Quaternion};
+use bodies;
+use covariance::Covariance;
+use frames::Frame;
+use insertion::InsertionSchedule;
+use scenario::Scenario;
 use serde::Serialize;
+use time;
+use types::{Array3d, Matrix3d};
+
+/// Inertial frame the output of a single epoch is expressed in.
+pub struct OutputFrame {
+    pub frame: Frame,
+    output_from_simulation: Matrix3d,
+}
+
+impl OutputFrame {
+    pub fn new(frame: Frame, epoch: &time::Epoch) -> Self {
+        OutputFrame {
+            frame,
+            output_from_simulation: frame.frame_from_simulation(epoch),
+        }
+    }
+
+    /// Rotates a vector from the simulation frame into the output frame.
+    pub fn rotate(&self, vector: &Array3d) -> Array3d {
+        self.output_from_simulation * vector
+    }
+
+    /// Rotates a covariance from the simulation frame into the output frame.
+    pub fn rotate_covariance(&self, covariance: &Covariance) -> Covariance {
+        covariance.rotated(&self.output_from_simulation)
+    }
+
+    /// Orientation of the body axes of an attitude relative to the output frame.
+    pub fn orientation(&self, attitude: &Attitude) -> Quaternion {
+        attitude.orientation_in(&self.output_from_simulation)
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct SolarObjectOut {
     pub name: String,  // Name of the solar object
     pub sim_time: f64, // Simulation time
+    pub utc: String,   // UTC timestamp of the simulation time
+    pub frame: String, // Frame of the coordinates
     pub x_coord: f32,  // Coordinate of object in the x axis
     pub y_coord: f32,  // Coordinate of object in the y axis
     pub z_coord: f32,  // Coordinate of object in the z axis
@@ -12,8 +52,10 @@ pub struct SolarObjectOut {
 #[derive(Debug, Serialize)]
 pub struct PerturbationOut {
     pub id: u32,             // ID of the object perturbation was applied to or calculated for
+    pub name: Option<String>, // Name of the object, empty if none was given
     pub sim_time: f64,       // Simulation time
     pub petrub_type: String, // Type of perturbation
+    pub frame: String,       // Frame of the accelerations
     pub acceleration_x_mpss: f64, // Acceleration placed on object by perturbing force in x axis
     pub acceleration_y_mpss: f64, // Acceleration placed on object by perturbing force in y axis
     pub acceleration_z_mpss: f64, // Acceleration placed on object by perturbing force in z axis
@@ -22,33 +64,341 @@ pub struct PerturbationOut {
 #[derive(Debug, Serialize)]
 pub struct SimulationObjectParameters {
     pub id: u32,         // ID of the object
+    pub name: Option<String>, // Name of the object, empty if none was given
     pub sim_time: f64,   // Simulation time
+    pub utc: String,     // UTC timestamp of the simulation time
+    pub frame: String,   // Frame of the coordinates and velocities
     pub x_coord: f64,    // Coordinate of object in the x axis
     pub y_coord: f64,    // Coordinate of object in the y axis
     pub z_coord: f64,    // Coordinate of object in the z axis
     pub x_velocity: f64, // Velocity of object in the x axis
     pub y_velocity: f64, // Velocity of object in the y axis
     pub z_velocity: f64, // Velocity of object in the z axis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude_deg: Option<f64>, // Geodetic latitude on the WGS-84 ellipsoid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude_deg: Option<f64>, // Longitude east of Greenwich
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub altitude_m: Option<f64>, // Height above the WGS-84 ellipsoid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_solar_time_h: Option<f64>, // Apparent local solar time in hours
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrbitalElementsOut {
+    pub id: u32,                  // ID of the object
+    pub name: Option<String>,     // Name of the object, empty if none was given
+    pub sim_time: f64,            // Simulation time
+    pub utc: String,              // UTC timestamp of the simulation time
+    pub frame: String,            // Frame the elements are referred to
+    pub semi_major_axis_m: f64,   // Semi-major axis, negative for hyperbolic orbits
+    pub eccentricity: f64,        // Eccentricity
+    pub inclination_deg: f64,     // Inclination to the xy plane of the frame
+    pub raan_deg: f64,            // Right ascension of the ascending node
+    pub arg_periapsis_deg: f64,   // Argument of periapsis
+    pub true_anomaly_deg: f64,    // True anomaly
+    pub mean_anomaly_deg: f64,    // Mean anomaly
+    pub equinoctial_h: f64,       // e sin(arg_periapsis + raan)
+    pub equinoctial_k: f64,       // e cos(arg_periapsis + raan)
+    pub equinoctial_p: f64,       // tan(i / 2) sin(raan)
+    pub equinoctial_q: f64,       // tan(i / 2) cos(raan)
+    pub mean_longitude_deg: f64,  // mean_anomaly + arg_periapsis + raan
+    pub semi_latus_rectum_m: f64, // Semi-latus rectum of the modified equinoctial elements
+    pub true_longitude_deg: f64,  // true_anomaly + arg_periapsis + raan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_semi_major_axis_m: Option<f64>, // Brouwer-Lyddane mean semi-major axis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_eccentricity: Option<f64>, // Brouwer-Lyddane mean eccentricity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_inclination_deg: Option<f64>, // Mean inclination to the GCRF equator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_raan_deg: Option<f64>, // Mean right ascension of the ascending node in the GCRF
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelativeStateOut {
+    pub id: u32,                       // ID of the object
+    pub name: Option<String>,          // Name of the object, empty if none was given
+    pub reference_id: u32,             // ID of the object the state is relative to
+    pub reference_name: Option<String>, // Name of the reference object, empty if none was given
+    pub sim_time: f64,                 // Simulation time
+    pub utc: String,                   // UTC timestamp of the simulation time
+    pub frame: String,                 // Local frame of the reference object
+    pub radial_m: f64,                 // Position along the radial axis
+    pub transverse_m: f64,             // Position along the transverse axis
+    pub normal_m: f64,                 // Position along the normal axis
+    pub radial_velocity_mps: f64,      // Velocity along the radial axis, relative to the rotating frame
+    pub transverse_velocity_mps: f64,  // Velocity along the transverse axis
+    pub normal_velocity_mps: f64,      // Velocity along the normal axis
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttitudeOut {
+    pub id: u32,                     // ID of the spacecraft
+    pub name: Option<String>,        // Name of the spacecraft, empty if none was given
+    pub sim_time: f64,               // Simulation time
+    pub utc: String,                 // UTC timestamp of the simulation time
+    pub frame: String,               // Frame the orientation is relative to
+    pub q_w: f64,                    // Scalar part of the rotation from the body axes into the frame
+    pub q_x: f64,                    // Vector part along the x axis
+    pub q_y: f64,                    // Vector part along the y axis
+    pub q_z: f64,                    // Vector part along the z axis
+    pub alpha_deg: f64,              // Euler angle about the z axis
+    pub beta_deg: f64,               // Euler angle about the new y axis
+    pub gamma_deg: f64,              // Euler angle about the new x axis
+    pub angular_velocity_x_dps: f64, // Angular velocity about the body x axis
+    pub angular_velocity_y_dps: f64, // Angular velocity about the body y axis
+    pub angular_velocity_z_dps: f64, // Angular velocity about the body z axis
+}
+
+impl AttitudeOut {
+    /// Attitude of a spacecraft relative to the output frame.
+    pub fn new(
+        sim_obj: &dyn bodies::Simobj,
+        attitude: &Attitude,
+        sim_time: f64,
+        utc: &str,
+        frame: &OutputFrame,
+    ) -> Self {
+        let orientation = frame.orientation(attitude);
+        let (alpha, beta, gamma) = orientation.to_euler();
+        let angular_velocity_dps = attitude.angular_velocity * 1f64.to_degrees();
+
+        AttitudeOut {
+            id: sim_obj.get_id(),
+            name: sim_obj.get_name().map(String::from),
+            sim_time,
+            utc: utc.to_string(),
+            frame: frame.frame.to_string(),
+            q_w: orientation.w,
+            q_x: orientation.x,
+            q_y: orientation.y,
+            q_z: orientation.z,
+            alpha_deg: alpha.to_degrees(),
+            beta_deg: beta.to_degrees(),
+            gamma_deg: gamma.to_degrees(),
+            angular_velocity_x_dps: angular_velocity_dps.x,
+            angular_velocity_y_dps: angular_velocity_dps.y,
+            angular_velocity_z_dps: angular_velocity_dps.z,
+        }
+    }
+}
+
+/// Lower triangle of a state covariance, named after the elements of the CCSDS orbit parameter
+/// message.
+#[derive(Debug, Serialize)]
+pub struct CovarianceOut {
+    pub id: u32,              // ID of the object
+    pub name: Option<String>, // Name of the object, empty if none was given
+    pub sim_time: f64,        // Simulation time
+    pub utc: String,          // UTC timestamp of the simulation time
+    pub frame: String,        // Frame of the position and velocity
+    pub cx_x: f64,            // m^2
+    pub cy_x: f64,            // m^2
+    pub cy_y: f64,            // m^2
+    pub cz_x: f64,            // m^2
+    pub cz_y: f64,            // m^2
+    pub cz_z: f64,            // m^2
+    pub cx_dot_x: f64,        // m^2/s
+    pub cx_dot_y: f64,        // m^2/s
+    pub cx_dot_z: f64,        // m^2/s
+    pub cx_dot_x_dot: f64,    // m^2/s^2
+    pub cy_dot_x: f64,        // m^2/s
+    pub cy_dot_y: f64,        // m^2/s
+    pub cy_dot_z: f64,        // m^2/s
+    pub cy_dot_x_dot: f64,    // m^2/s^2
+    pub cy_dot_y_dot: f64,    // m^2/s^2
+    pub cz_dot_x: f64,        // m^2/s
+    pub cz_dot_y: f64,        // m^2/s
+    pub cz_dot_z: f64,        // m^2/s
+    pub cz_dot_x_dot: f64,    // m^2/s^2
+    pub cz_dot_y_dot: f64,    // m^2/s^2
+    pub cz_dot_z_dot: f64,    // m^2/s^2
+}
+
+impl CovarianceOut {
+    /// Covariance of an object in the frame it is written out in.
+    pub fn new(
+        sim_obj: &dyn bodies::Simobj,
+        covariance: &Covariance,
+        sim_time: f64,
+        utc: &str,
+        frame: String,
+    ) -> Self {
+        let [cx_x, cy_x, cy_y, cz_x, cz_y, cz_z, cx_dot_x, cx_dot_y, cx_dot_z, cx_dot_x_dot, cy_dot_x, cy_dot_y, cy_dot_z, cy_dot_x_dot, cy_dot_y_dot, cz_dot_x, cz_dot_y, cz_dot_z, cz_dot_x_dot, cz_dot_y_dot, cz_dot_z_dot] = covariance.lower_triangle();
+
+        CovarianceOut {
+            id: sim_obj.get_id(),
+            name: sim_obj.get_name().map(String::from),
+            sim_time,
+            utc: utc.to_string(),
+            frame,
+            cx_x,
+            cy_x,
+            cy_y,
+            cz_x,
+            cz_y,
+            cz_z,
+            cx_dot_x,
+            cx_dot_y,
+            cx_dot_z,
+            cx_dot_x_dot,
+            cy_dot_x,
+            cy_dot_y,
+            cy_dot_z,
+            cy_dot_x_dot,
+            cy_dot_y_dot,
+            cz_dot_x,
+            cz_dot_y,
+            cz_dot_z,
+            cz_dot_x_dot,
+            cz_dot_y_dot,
+            cz_dot_z_dot,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestObject {
+    pub id: u32, // ID of the object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>, // Name of the object
+    pub object_type: String,  // Spacecraft or debris
+    pub input_state: String, // Kind of initial state given by the input
+    pub coords: Array3d,     // Derived position relative to the central body in the output frame
+    pub velocity: Array3d,   // Derived velocity relative to the central body in the output frame
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<Quaternion>, // Rotation from the body axes into the output frame
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub angular_velocity_dps: Option<Array3d>, // Angular velocity in the body axes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_file: Option<String>, // Model file of the spacecraft geometry
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduledObject {
+    pub id: u32, // ID of the object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>, // Name of the object
+    pub object_type: String,  // Spacecraft or debris
+    pub input_state: String,  // Kind of initial state given by the input
+    pub insert_utc: String,   // UTC timestamp the object is inserted at
+}
+
+/// Description of a run, written once before the simulation starts.
+#[derive(Debug, Serialize)]
+pub struct RunManifest {
+    pub input: String,     // Input file of the run
+    pub start_utc: String, // UTC timestamp of the start epoch
+    pub central_body: bodies::CentralBody,
+    pub frame: String, // Frame of the derived object states
+    pub objects: Vec<ManifestObject>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scheduled: Vec<ScheduledObject>, // Objects inserted during the run
+}
+
+impl RunManifest {
+    /// Describes the initial states of the objects as derived from the input.
+    ///
+    /// ### Arguments
+    /// * 'input' - The input file of the run.
+    /// * 'central_body' - The body at the origin of the simulation.
+    /// * 'frame' - The output frame.
+    /// * 'env' - The Simulation environment at the start epoch.
+    /// * 'sim_bodies' - The objects at their initial states.
+    /// * 'state_kinds' - The kinds of initial state given for the objects, in the same order.
+    /// * 'insertions' - The objects inserted during the run.
+    ///
+    pub fn new(
+        input: &str,
+        central_body: bodies::CentralBody,
+        frame: Frame,
+        env: &bodies::Environment,
+        sim_bodies: &[bodies::SimobjT],
+        state_kinds: &[&str],
+        insertions: &InsertionSchedule,
+    ) -> Self {
+        let output_frame = OutputFrame::new(frame, &env.start_epoch);
+        let objects = sim_bodies
+            .iter()
+            .zip(state_kinds)
+            .map(|(body, kind)| ManifestObject {
+                id: body.get_id(),
+                name: body.get_name().map(String::from),
+                object_type: body.type_of(),
+                input_state: kind.to_string(),
+                coords: output_frame.rotate(body.get_ref_coords()),
+                velocity: output_frame.rotate(body.get_ref_velocity()),
+                orientation: body
+                    .get_attitude()
+                    .map(|attitude| output_frame.orientation(attitude)),
+                angular_velocity_dps: body
+                    .get_attitude()
+                    .map(|attitude| attitude.angular_velocity * 1f64.to_degrees()),
+                model_file: body.get_model_file().map(String::from),
+            })
+            .collect();
+        let scheduled = insertions
+            .pending()
+            .map(|(body, kind, sim_time_s)| ScheduledObject {
+                id: body.get_id(),
+                name: body.get_name().map(String::from),
+                object_type: body.type_of(),
+                input_state: kind.to_string(),
+                insert_utc: env.epoch(sim_time_s).to_utc_string(),
+            })
+            .collect();
+
+        RunManifest {
+            input: input.to_string(),
+            start_utc: env.start_epoch.to_utc_string(),
+            central_body,
+            frame: frame.to_string(),
+            objects,
+            scheduled,
+        }
+    }
 }
 
 pub trait SimulationOutput {
+    fn write_scenario(&mut self, scenario: Scenario);
+
+    fn write_manifest(&mut self, manifest: RunManifest);
+
     fn write_out_perturbation(&mut self, petrub_out: PerturbationOut);
 
     fn write_out_object_parameters(&mut self, object_params: SimulationObjectParameters);
 
     fn write_out_solar_object(&mut self, solar_object: SolarObjectOut);
+
+    fn write_out_relative_state(&mut self, relative_state: RelativeStateOut);
+
+    fn write_out_orbital_elements(&mut self, elements: OrbitalElementsOut);
+
+    fn write_out_attitude(&mut self, attitude: AttitudeOut);
+
+    fn write_out_covariance(&mut self, covariance: CovarianceOut);
 }
 
 pub mod csv_output {
     use csv;
-    use output::{PerturbationOut, SimulationObjectParameters, SimulationOutput, SolarObjectOut};
+    use output::{
+        AttitudeOut, CovarianceOut, OrbitalElementsOut, PerturbationOut, RelativeStateOut, RunManifest,
+        Scenario, SimulationObjectParameters, SimulationOutput, SolarObjectOut,
+    };
+    use serde_json;
     use std::fs;
     use std::path;
 
     pub struct CSVController {
+        dir_path: path::PathBuf,
         perturbation_writer: csv::Writer<fs::File>,
         object_parameters_writer: csv::Writer<fs::File>,
         solar_object_writer: csv::Writer<fs::File>,
+        relative_state_writer: csv::Writer<fs::File>,
+        elements_writer: csv::Writer<fs::File>,
+        attitude_writer: csv::Writer<fs::File>,
+        covariance_writer: csv::Writer<fs::File>,
     }
 
     impl CSVController {
@@ -61,6 +411,7 @@ pub mod csv_output {
 
             // .unwrap() here is fine as this code is related to initialization
             CSVController {
+                dir_path: full_dirpath.clone(),
                 perturbation_writer: csv::Writer::from_path(
                     full_dirpath.join("pose_perturbations.csv"),
                 )
@@ -73,11 +424,36 @@ pub mod csv_output {
                     full_dirpath.join("pose_solar_objects.csv"),
                 )
                 .unwrap(),
+                relative_state_writer: csv::Writer::from_path(
+                    full_dirpath.join("pose_relative_states.csv"),
+                )
+                .unwrap(),
+                elements_writer: csv::Writer::from_path(full_dirpath.join("pose_elements.csv"))
+                    .unwrap(),
+                attitude_writer: csv::Writer::from_path(full_dirpath.join("pose_attitudes.csv"))
+                    .unwrap(),
+                covariance_writer: csv::Writer::from_path(
+                    full_dirpath.join("pose_covariances.csv"),
+                )
+                .unwrap(),
             }
         }
     }
 
     impl SimulationOutput for CSVController {
+        fn write_scenario(&mut self, scenario: Scenario) {
+            let text = scenario.to_toml().expect("Failed to write the resolved scenario.");
+            fs::write(self.dir_path.join("pose_scenario.toml"), text)
+                .expect("Failed to write the resolved scenario to the output directory.");
+        }
+
+        fn write_manifest(&mut self, manifest: RunManifest) {
+            let file = fs::File::create(self.dir_path.join("pose_manifest.json"))
+                .expect("Failed to create the run manifest in the output directory.");
+            serde_json::to_writer_pretty(file, &manifest)
+                .expect("Failed to write the run manifest.");
+        }
+
         fn write_out_perturbation(&mut self, petrub_out: PerturbationOut) {
             self.perturbation_writer.serialize(petrub_out).expect(
                 "Failed to write simulation perturbation data to the corresponding csv file.",
@@ -103,5 +479,37 @@ pub mod csv_output {
             // Unwrap here as this is a critical error
             self.solar_object_writer.flush().unwrap();
         }
+
+        fn write_out_relative_state(&mut self, relative_state: RelativeStateOut) {
+            self.relative_state_writer
+                .serialize(relative_state)
+                .expect("Failed to write simulation relative states to the corresponding csv file.");
+            // Unwrap here as this is a critical error
+            self.relative_state_writer.flush().unwrap();
+        }
+
+        fn write_out_orbital_elements(&mut self, elements: OrbitalElementsOut) {
+            self.elements_writer
+                .serialize(elements)
+                .expect("Failed to write simulation orbital elements to the corresponding csv file.");
+            // Unwrap here as this is a critical error
+            self.elements_writer.flush().unwrap();
+        }
+
+        fn write_out_attitude(&mut self, attitude: AttitudeOut) {
+            self.attitude_writer
+                .serialize(attitude)
+                .expect("Failed to write simulation attitudes to the corresponding csv file.");
+            // Unwrap here as this is a critical error
+            self.attitude_writer.flush().unwrap();
+        }
+
+        fn write_out_covariance(&mut self, covariance: CovarianceOut) {
+            self.covariance_writer
+                .serialize(covariance)
+                .expect("Failed to write simulation covariances to the corresponding csv file.");
+            // Unwrap here as this is a critical error
+            self.covariance_writer.flush().unwrap();
+        }
     }
 }
@@ -0,0 +1,190 @@
+//! Scenario files declaring the parameters of a simulation run in TOML.
+//!
+//! Every value of a scenario is optional. Command line flags take precedence over the scenario,
+//! the scenario over the defaults of POSE. Paths are relative to the scenario file. The resolved
+//! parameters of a run are written to its output directory as a scenario repeating the run.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use force_models::ForceModelConfig;
+use frames::{Frame, LocalFrame};
+use input::{Catalog, InputError, SimulationParameters};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use toml;
+use toml::value::Datetime;
+
+/// Parameters of a simulation run.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub input: Option<String>, // Input file of the objects, a JSON file or an orbit message
+    pub start: Option<Datetime>, // Start of the simulation, the date of the input file if none
+    pub end: Option<Datetime>, // End of the simulation, runs until stopped if none
+    #[serde(default)]
+    pub integrator: IntegratorConfig,
+    #[serde(default)]
+    pub environment: EnvironmentConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub catalogs: Vec<Catalog>, // Catalogs added to the ones of the input file
+    pub forces: Option<Vec<ForceModelConfig>>, // Replace the force models of the input file
+}
+
+/// The objects are advanced with Cowell's method and a semi-implicit Euler step of fixed size,
+/// there is no choice of the method or tolerances.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct IntegratorConfig {
+    pub step_s: Option<f32>,       // Simulation time step in seconds
+    pub solar_step_s: Option<f32>, // Interval between refits of the solar system objects
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentConfig {
+    pub ephemeris: Option<Vec<String>>, // JPL development ephemeris files
+    pub eop: Option<String>,            // IERS finals Earth orientation parameter file
+}
+
+/// The output is written as CSV files into a timestamped directory.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    pub dir: Option<String>, // Directory the timestamped output directory is created in
+    pub interval_s: Option<f32>, // Interval between written object states, every step if none
+    pub frame: Option<Frame>, // The frame of the input file if none
+    pub perturbation_frame: Option<LocalFrame>,
+    pub covariance_frame: Option<LocalFrame>,
+    pub relative_to: Option<u32>, // ID of the object relative states are written out for
+    pub elements: Option<bool>,
+    pub mean_elements: Option<bool>,
+    pub geodetic: Option<bool>,
+}
+
+impl Scenario {
+    /// Reads a scenario file, making its paths relative to the working directory.
+    ///
+    /// ### Argument
+    /// * 'path' - The scenario file.
+    ///
+    /// ### Return
+    ///     The scenario, or an error locating the invalid part of the file.
+    ///
+    pub fn load(path: &str) -> Result<Self, InputError> {
+        let text = fs::read_to_string(path).map_err(|source| InputError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        let mut scenario: Scenario =
+            toml::from_str(&text).map_err(|err| InputError::toml_syntax(path, &err))?;
+
+        let dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        let relative_to_dir = |file: &mut String| {
+            *file = dir.join(&*file).to_string_lossy().into_owned();
+        };
+        scenario.input.iter_mut().for_each(relative_to_dir);
+        scenario
+            .catalogs
+            .iter_mut()
+            .for_each(|catalog| relative_to_dir(&mut catalog.path));
+        scenario
+            .environment
+            .ephemeris
+            .iter_mut()
+            .flatten()
+            .for_each(relative_to_dir);
+        scenario
+            .environment
+            .eop
+            .iter_mut()
+            .for_each(relative_to_dir);
+        scenario.output.dir.iter_mut().for_each(relative_to_dir);
+
+        Ok(scenario)
+    }
+
+    /// The scenario of a run with all parameters resolved, paths are made absolute.
+    ///
+    /// ### Arguments
+    /// * 'sim_params' - The parameters of the run.
+    /// * 'start_time' - The start of the simulation.
+    /// * 'forces' - The enabled force models.
+    ///
+    pub fn resolved(
+        sim_params: &SimulationParameters,
+        start_time: &DateTime<Utc>,
+        forces: &[ForceModelConfig],
+    ) -> Self {
+        let catalogs = sim_params
+            .catalogs
+            .iter()
+            .map(|catalog| Catalog {
+                path: absolute_path(&catalog.path),
+                ..catalog.clone()
+            })
+            .collect();
+
+        Scenario {
+            input: Some(absolute_path(&sim_params.input_bodies_json)),
+            start: Some(to_toml_datetime(start_time)),
+            end: sim_params.end_time.as_ref().map(to_toml_datetime),
+            integrator: IntegratorConfig {
+                step_s: Some(sim_params.sim_time_step),
+                solar_step_s: Some(sim_params.sim_solar_step),
+            },
+            environment: EnvironmentConfig {
+                ephemeris: Some(
+                    sim_params
+                        .ephemeris_files
+                        .iter()
+                        .map(|file| absolute_path(file))
+                        .collect(),
+                ),
+                eop: sim_params.eop_file.as_deref().map(absolute_path),
+            },
+            output: OutputConfig {
+                dir: Some(absolute_path(&sim_params.output_dir)),
+                interval_s: sim_params.output_interval,
+                frame: sim_params.output_frame,
+                perturbation_frame: sim_params.perturbation_frame,
+                covariance_frame: sim_params.covariance_frame,
+                relative_to: sim_params.relative_to,
+                elements: Some(sim_params.elements_output),
+                mean_elements: Some(sim_params.mean_elements_output),
+                geodetic: Some(sim_params.geodetic_output),
+            },
+            catalogs,
+            forces: Some(forces.to_vec()),
+        }
+    }
+
+    /// Writes the scenario in TOML.
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+}
+
+/// Converts a TOML datetime of the scenario into UTC, it has to include the offset.
+pub fn to_utc(datetime: &Datetime) -> Result<DateTime<Utc>, String> {
+    datetime
+        .to_string()
+        .parse::<DateTime<Utc>>()
+        .map_err(|_| format!("{} is not a datetime with a UTC offset.", datetime))
+}
+
+fn to_toml_datetime(datetime: &DateTime<Utc>) -> Datetime {
+    datetime
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+        .parse()
+        .expect("RFC 3339 timestamps are TOML datetimes.")
+}
+
+/// Absolute form of a path, unchanged if it does not exist.
+fn absolute_path(path: &str) -> String {
+    fs::canonicalize(path).map_or_else(
+        |_| path.to_string(),
+        |path| path.to_string_lossy().into_owned(),
+    )
+}
@@ -1,13 +1,16 @@
 use crate::bodies;
+use crate::elements::{EquinoctialElements, KeplerianElements, ModifiedEquinoctialElements};
+use crate::force_models;
+use crate::frames;
+use crate::geodesy;
+use crate::insertion::InsertionSchedule;
+use crate::maneuver::ManeuverSchedule;
 use crate::output;
+use crate::time;
+use frames::LocalFrame;
 use input::SimulationParameters;
 use sim_cpu::cowell_perturb::apply_perturbations;
-use std::string::ToString;
-use strum_macros::Display;
-use types::Array3d;
-
-// Gravitational constant 6.674×10−11
-const G: f64 = 6.674e-11;
+use types::{Array3d, Matrix3d};
 
 pub struct PerturbationDelta {
     id: u32,
@@ -29,35 +32,45 @@ impl Default for PerturbationDelta {
     }
 }
 
-#[derive(Display)]
-pub enum Perturbation {
-    #[strum(serialize = "solar_obj")]
-    SolarObject(bodies::Solarobj, PerturbationDelta),
+/// Acceleration contributed by a single force model.
+pub struct Perturbation {
+    force_name: String, // Name of the force model
+    name: Option<String>, // Name of the object the acceleration was calculated for
+    delta: PerturbationDelta,
+    position: Array3d, // State of the object the acceleration was calculated at
+    velocity: Array3d,
 }
 
 impl Perturbation {
-    fn into_output_form(self) -> output::PerturbationOut {
+    /// Converts the perturbation into its output, with the acceleration in the local frame of the
+    /// object if one is given or in the inertial output frame otherwise.
+    fn into_output_form(
+        self,
+        frame: &output::OutputFrame,
+        local_frame: Option<LocalFrame>,
+    ) -> output::PerturbationOut {
+        let acceleration = Array3d {
+            x: self.delta.acceleration_x_mpss,
+            y: self.delta.acceleration_y_mpss,
+            z: self.delta.acceleration_z_mpss,
+        };
+        let (frame_name, acceleration) = match local_frame {
+            Some(local_frame) => (
+                local_frame.to_string(),
+                local_frame.local_from_inertial(&self.position, &self.velocity) * acceleration,
+            ),
+            None => (frame.frame.to_string(), frame.rotate(&acceleration)),
+        };
+
         output::PerturbationOut {
-            id: match &self {
-                Perturbation::SolarObject(_, perturb_delta) => perturb_delta.id,
-            },
-            sim_time: match &self {
-                Perturbation::SolarObject(_, perturb_delta) => perturb_delta.sim_time,
-            },
-            petrub_type: match &self {
-                Perturbation::SolarObject(solar_obj, _) => {
-                    format!("{}_{}", self.to_string(), solar_obj.to_string())
-                }
-            },
-            acceleration_x_mpss: match &self {
-                Perturbation::SolarObject(_, perturb_delta) => perturb_delta.acceleration_x_mpss,
-            },
-            acceleration_y_mpss: match &self {
-                Perturbation::SolarObject(_, perturb_delta) => perturb_delta.acceleration_y_mpss,
-            },
-            acceleration_z_mpss: match &self {
-                Perturbation::SolarObject(_, perturb_delta) => perturb_delta.acceleration_z_mpss,
-            },
+            id: self.delta.id,
+            name: self.name,
+            sim_time: self.delta.sim_time,
+            petrub_type: self.force_name,
+            frame: frame_name,
+            acceleration_x_mpss: acceleration.x,
+            acceleration_y_mpss: acceleration.y,
+            acceleration_z_mpss: acceleration.z,
         }
     }
 }
@@ -66,70 +79,303 @@ impl Perturbation {
 ///
 /// ### Arguments
 /// * 'perturbations' - Vector containing perturbations to be written out.
+/// * 'frame' - Frame the accelerations are written out in.
+/// * 'local_frame' - Local frame of the objects used instead of the output frame if given.
 /// * 'output_controller' - Controller object used to facilitate the output of perturbation data.
 ///
 fn write_out_all_perturbations(
     perturbations: Vec<Perturbation>,
+    frame: &output::OutputFrame,
+    local_frame: Option<LocalFrame>,
     output_controller: &mut dyn output::SimulationOutput,
 ) {
     for perturbation in perturbations {
-        output_controller.write_out_perturbation(perturbation.into_output_form());
+        output_controller
+            .write_out_perturbation(perturbation.into_output_form(frame, local_frame));
     }
 }
 
 fn write_out_all_object_parameters(
     env: &bodies::Environment,
     sim_objects: &[bodies::SimobjT],
+    frame: &output::OutputFrame,
     output_controller: &mut dyn output::SimulationOutput,
+    geodetic_output: bool,
 ) {
+    let utc = env.epoch(env.sim_time_s).to_utc_string();
+    let ground_track = if geodetic_output && env.central_is_earth() {
+        GroundTrack::new(env)
+    } else {
+        None
+    };
+
     for sim_obj in sim_objects {
-        output_controller.write_out_object_parameters(sim_obj.to_output_form(env.sim_time_s));
+        let mut object_params = sim_obj.to_output_form(env.sim_time_s, &utc, frame);
+        if let Some(ground_track) = &ground_track {
+            ground_track.fill(&mut object_params, sim_obj.get_ref_coords());
+        }
+        output_controller.write_out_object_parameters(object_params);
     }
 }
 
-fn write_out_all_solar_objects(
+fn write_out_all_attitudes(
     env: &bodies::Environment,
+    sim_objects: &[bodies::SimobjT],
+    frame: &output::OutputFrame,
     output_controller: &mut dyn output::SimulationOutput,
 ) {
-    for solar_object in env.get_solar_objects() {
-        output_controller.write_out_solar_object(solar_object.to_output_form(env.sim_time_s));
+    let utc = env.epoch(env.sim_time_s).to_utc_string();
+    for sim_obj in sim_objects {
+        if let Some(attitude) = sim_obj.get_attitude() {
+            output_controller.write_out_attitude(output::AttitudeOut::new(
+                sim_obj.as_ref(),
+                attitude,
+                env.sim_time_s,
+                &utc,
+                frame,
+            ));
+        }
+    }
+}
+
+/// Write out the covariances of the objects which have one.
+///
+/// ### Arguments
+/// * 'env' - The Simulation environment.
+/// * 'sim_objects' - The simulation objects.
+/// * 'frame' - Frame the covariances are written out in.
+/// * 'local_frame' - Local frame of the objects used instead of the output frame if given.
+/// * 'output_controller' - Controller object used to facilitate the output of covariances.
+///
+fn write_out_all_covariances(
+    env: &bodies::Environment,
+    sim_objects: &[bodies::SimobjT],
+    frame: &output::OutputFrame,
+    local_frame: Option<LocalFrame>,
+    output_controller: &mut dyn output::SimulationOutput,
+) {
+    let utc = env.epoch(env.sim_time_s).to_utc_string();
+    for sim_obj in sim_objects {
+        if let Some(covariance) = sim_obj.get_covariance() {
+            let (frame_name, covariance) = match local_frame {
+                Some(local_frame) => (
+                    local_frame.to_string(),
+                    covariance.rotated(&local_frame.local_from_inertial(
+                        sim_obj.get_ref_coords(),
+                        sim_obj.get_ref_velocity(),
+                    )),
+                ),
+                None => (frame.frame.to_string(), frame.rotate_covariance(covariance)),
+            };
+            output_controller.write_out_covariance(output::CovarianceOut::new(
+                sim_obj.as_ref(),
+                &covariance,
+                env.sim_time_s,
+                &utc,
+                frame_name,
+            ));
+        }
     }
 }
 
-fn l2_norm(x: &Array3d) -> f64 {
-    x.dot(x).sqrt()
+/// Earth fixed transform of a single step, used to place the objects over the Earth.
+struct GroundTrack {
+    itrf_from_simulation: Matrix3d,
+    subsolar_longitude_rad: f64, // Longitude the Sun is overhead at
 }
 
-fn normalize(x: &Array3d, l2_norm_precalc: Option<f64>) -> Array3d {
-    let norm = match l2_norm_precalc {
-        Some(val) => val,
-        None => l2_norm(x),
+impl GroundTrack {
+    /// Calculates the transform at the current simulation time, none if the Sun is not
+    /// simulated.
+    fn new(env: &bodies::Environment) -> Option<Self> {
+        let itrf_from_simulation = env.earth_orientation_at(env.sim_time_s).itrf_from_gcrf()
+            * frames::ecliptic_from_equatorial().transpose();
+
+        let sun_idx = env
+            .get_solar_objects()
+            .iter()
+            .position(|body| matches!(body.get_solar_object(), bodies::Solarobj::Sun { attr: _ }))?;
+        let sun = itrf_from_simulation * env.position_from_central(sun_idx, env.sim_time_s)?;
+
+        Some(GroundTrack {
+            itrf_from_simulation,
+            subsolar_longitude_rad: sun.y.atan2(sun.x),
+        })
+    }
+
+    /// Adds the geodetic position and local solar time of an object to its output.
+    fn fill(&self, object_params: &mut output::SimulationObjectParameters, coords: &Array3d) {
+        let geodetic = geodesy::ecef_to_geodetic(&(self.itrf_from_simulation * coords));
+        // Local noon is when the object passes the subsolar meridian
+        let hour_angle_deg = (geodetic.longitude_rad - self.subsolar_longitude_rad).to_degrees();
+
+        object_params.latitude_deg = Some(geodetic.latitude_rad.to_degrees());
+        object_params.longitude_deg = Some(geodetic.longitude_rad.to_degrees());
+        object_params.altitude_m = Some(geodetic.altitude_m);
+        object_params.local_solar_time_h = Some((12.0 + hour_angle_deg / 15.0).rem_euclid(24.0));
+    }
+}
+
+/// Write out the osculating orbital elements of all objects about the central body, referred to
+/// the output frame.
+///
+/// ### Arguments
+/// * 'env' - The Simulation environment.
+/// * 'sim_objects' - All simulation objects.
+/// * 'frame' - Frame the elements are referred to.
+/// * 'mean_elements' - Also write out the Brouwer-Lyddane mean elements about the equator.
+/// * 'output_controller' - Controller object used to facilitate the output of the elements.
+///
+fn write_out_all_orbital_elements(
+    env: &bodies::Environment,
+    sim_objects: &[bodies::SimobjT],
+    frame: &output::OutputFrame,
+    mean_elements: bool,
+    output_controller: &mut dyn output::SimulationOutput,
+) {
+    let mu = env.central_gravitational_parameter();
+    let epoch = env.epoch(env.sim_time_s);
+    let utc = epoch.to_utc_string();
+    // The mean elements are referred to the equator of the central body, approximated by the GCRF
+    let gcrf_from_simulation = frames::Frame::Gcrf.frame_from_simulation(&epoch);
+    let central_body = env.get_solar_objects()[0].get_solar_object();
+
+    for sim_obj in sim_objects {
+        let position = frame.rotate(sim_obj.get_ref_coords());
+        let velocity = frame.rotate(sim_obj.get_ref_velocity());
+        let keplerian = KeplerianElements::from_state(&position, &velocity, mu);
+        let equinoctial = EquinoctialElements::from_state(&position, &velocity, mu);
+        let modified = ModifiedEquinoctialElements::from_state(&position, &velocity, mu);
+        let mean = if mean_elements {
+            let osculating = KeplerianElements::from_state(
+                &(gcrf_from_simulation * sim_obj.get_ref_coords()),
+                &(gcrf_from_simulation * sim_obj.get_ref_velocity()),
+                mu,
+            );
+            let radius_m = central_body.get_radius_m();
+            // Orbits without mean elements are written out as NaN
+            Some(
+                osculating
+                    .brouwer_lyddane_mean(central_body.get_j2(), radius_m)
+                    .unwrap_or(KeplerianElements {
+                        semi_major_axis_m: f64::NAN,
+                        eccentricity: f64::NAN,
+                        inclination_rad: f64::NAN,
+                        raan_rad: f64::NAN,
+                        arg_periapsis_rad: f64::NAN,
+                        true_anomaly_rad: f64::NAN,
+                    }),
+            )
+        } else {
+            None
+        };
+
+        output_controller.write_out_orbital_elements(output::OrbitalElementsOut {
+            id: sim_obj.get_id(),
+            name: sim_obj.get_name().map(String::from),
+            sim_time: env.sim_time_s,
+            utc: utc.clone(),
+            frame: frame.frame.to_string(),
+            semi_major_axis_m: keplerian.semi_major_axis_m,
+            eccentricity: keplerian.eccentricity,
+            inclination_deg: keplerian.inclination_rad.to_degrees(),
+            raan_deg: keplerian.raan_rad.to_degrees(),
+            arg_periapsis_deg: keplerian.arg_periapsis_rad.to_degrees(),
+            true_anomaly_deg: keplerian.true_anomaly_rad.to_degrees(),
+            mean_anomaly_deg: keplerian.mean_anomaly().to_degrees(),
+            equinoctial_h: equinoctial.h,
+            equinoctial_k: equinoctial.k,
+            equinoctial_p: equinoctial.p,
+            equinoctial_q: equinoctial.q,
+            mean_longitude_deg: equinoctial.mean_longitude_rad.to_degrees(),
+            semi_latus_rectum_m: modified.semi_latus_rectum_m,
+            true_longitude_deg: modified.true_longitude_rad.to_degrees(),
+            mean_semi_major_axis_m: mean.map(|mean| mean.semi_major_axis_m),
+            mean_eccentricity: mean.map(|mean| mean.eccentricity),
+            mean_inclination_deg: mean.map(|mean| mean.inclination_rad.to_degrees()),
+            mean_raan_deg: mean.map(|mean| mean.raan_rad.to_degrees()),
+        });
+    }
+}
+
+/// Write out the states of all objects relative to a reference object, in its RTN frame. The
+/// velocities are relative to the rotating frame.
+///
+/// ### Arguments
+/// * 'env' - The Simulation environment.
+/// * 'sim_objects' - All simulation objects.
+/// * 'reference_id' - ID of the reference object.
+/// * 'output_controller' - Controller object used to facilitate the output of the states.
+///
+fn write_out_all_relative_states(
+    env: &bodies::Environment,
+    sim_objects: &[bodies::SimobjT],
+    reference_id: u32,
+    output_controller: &mut dyn output::SimulationOutput,
+) {
+    let reference = match sim_objects.iter().find(|obj| obj.get_id() == reference_id) {
+        Some(reference) => reference,
+        None => return,
     };
-    
-    Array3d{
-        x: x.x / norm,
-        y: x.y / norm,
-        z: x.z / norm
+    let (reference_position, reference_velocity) =
+        (reference.get_ref_coords(), reference.get_ref_velocity());
+    let rtn_from_inertial =
+        LocalFrame::Rtn.local_from_inertial(reference_position, reference_velocity);
+    // Angular velocity of the frame
+    let frame_rotation = reference_position.cross(reference_velocity)
+        * (1f64 / reference_position.dot(reference_position));
+
+    let utc = env.epoch(env.sim_time_s).to_utc_string();
+    for sim_obj in sim_objects.iter().filter(|obj| obj.get_id() != reference_id) {
+        let position = sim_obj.get_ref_coords() - reference_position;
+        let velocity =
+            sim_obj.get_ref_velocity() - reference_velocity - frame_rotation.cross(&position);
+        let position = rtn_from_inertial * position;
+        let velocity = rtn_from_inertial * velocity;
+
+        output_controller.write_out_relative_state(output::RelativeStateOut {
+            id: sim_obj.get_id(),
+            name: sim_obj.get_name().map(String::from),
+            reference_id,
+            reference_name: reference.get_name().map(String::from),
+            sim_time: env.sim_time_s,
+            utc: utc.clone(),
+            frame: LocalFrame::Rtn.to_string(),
+            radial_m: position.x,
+            transverse_m: position.y,
+            normal_m: position.z,
+            radial_velocity_mps: velocity.x,
+            transverse_velocity_mps: velocity.y,
+            normal_velocity_mps: velocity.z,
+        });
+    }
+}
+
+fn write_out_all_solar_objects(
+    env: &bodies::Environment,
+    frame: &output::OutputFrame,
+    output_controller: &mut dyn output::SimulationOutput,
+) {
+    let utc = env.epoch(env.sim_time_s).to_utc_string();
+    for solar_object in env.get_solar_objects() {
+        output_controller
+            .write_out_solar_object(solar_object.to_output_form(env.sim_time_s, &utc, frame));
     }
 }
 
 /// Module used to apply perturbation calculations on individual bodies
 mod cowell_perturb {
     use crate::bodies;
+    use crate::force_models::ForceModel;
     use crate::sim_cpu::{Perturbation, PerturbationDelta};
-    use bodies::Solarobj;
-    use sim_cpu::{l2_norm, normalize, G};
     use types::Array3d;
 
-    /// Apply all perturbations handled by POSE. This includes:
-    /// * 'Solar Body Earth'
-    /// * 'Solar Body Moon'
-    /// * 'Solar Body Sun'
-    /// TODO add more
+    /// Apply all perturbations from the enabled force models, in their configured order.
     ///
     /// ### Parameters
     /// * 'sim_obj' - The object basis for calculation and apply
     /// * 'env' - The Simulation environment
+    /// * 'force_models' - The enabled force models
     /// * 'step_time_s' - Step time of the simulation in seconds
     /// * 'do_return_peturb' - true if vector should be returned, false otherwise
     ///
@@ -139,215 +385,259 @@ mod cowell_perturb {
     pub fn apply_perturbations(
         sim_obj: &mut dyn bodies::Simobj,
         env: &bodies::Environment,
+        force_models: &[Box<dyn ForceModel>],
         step_time_s: f64,
         do_return_perturb: bool,
     ) -> Option<Vec<Perturbation>> {
-
-        let mut net_acceleration = Array3d{
+        let mut net_acceleration = Array3d {
             x: 0.0,
             y: 0.0,
-            z: 0.0
+            z: 0.0,
         };
+        let mut perturbations = Vec::with_capacity(force_models.len());
+
+        // Combine the accelerations from all perturbating forces
+        let position = *sim_obj.get_ref_coords();
+        let velocity = *sim_obj.get_ref_velocity();
+        for force_model in force_models {
+            let acceleration = match force_model.acceleration(
+                sim_obj,
+                &position,
+                &velocity,
+                env.sim_time_s,
+                env,
+            ) {
+                Some(acceleration) => acceleration,
+                None => continue,
+            };
 
-        // Calculate the pertubation forces for all planetary objects
-        let gravity_perturbations = calc_planet_perturb(sim_obj, env, do_return_perturb);
+            net_acceleration = net_acceleration + acceleration;
 
-        // TODO make the delta return a Array3d type
-        // Combine the perturbation deltas from all perturbating forces
-        net_acceleration.x += gravity_perturbations.0.acceleration_x_mpss;
-        net_acceleration.y += gravity_perturbations.0.acceleration_y_mpss;
-        net_acceleration.z += gravity_perturbations.0.acceleration_z_mpss;
+            if do_return_perturb {
+                perturbations.push(Perturbation {
+                    force_name: force_model.name(),
+                    name: sim_obj.get_name().map(String::from),
+                    delta: PerturbationDelta {
+                        id: sim_obj.get_id(),
+                        sim_time: env.sim_time_s,
+                        acceleration_x_mpss: acceleration.x,
+                        acceleration_y_mpss: acceleration.y,
+                        acceleration_z_mpss: acceleration.z,
+                    },
+                    position,
+                    velocity,
+                });
+            }
+        }
 
         // Calculate the velocity change from net acceleration
         let velocity_delta = net_acceleration * step_time_s;
         // Calculate new velocity for the given simulation object
-        let updated_sim_obj_velocity = velocity_delta + sim_obj.get_ref_velocity();
+        let updated_sim_obj_velocity = velocity_delta + velocity;
 
         // Calculate the position change from the updated velocity
-        let position_delta = updated_sim_obj_velocity.clone() * step_time_s;
+        let position_delta = updated_sim_obj_velocity * step_time_s;
         // Calculate the new position for the simulation object
-        let updated_sim_obj_coords = position_delta + sim_obj.get_ref_coords();
+        let updated_sim_obj_coords = position_delta + position;
 
         // Update the new values within the simulation object
-        sim_obj.set_velocity(updated_sim_obj_velocity.clone());
-        sim_obj.set_coords(updated_sim_obj_coords.clone());
+        sim_obj.set_velocity(updated_sim_obj_velocity);
+        sim_obj.set_coords(updated_sim_obj_coords);
 
         // If pertubation details are not needed, return
         if !do_return_perturb {
             return None;
         }
 
-        let output_vec = {
-            // Upwrap here as this will contain a value at this stage
-            let mut result_vec = gravity_perturbations.1.unwrap();
-            result_vec
-        };
-
-        Some(output_vec)
-    }
-
-    fn calc_atmospheric_drag(
-        sim_obj: &dyn bodies::Simobj,
-        env: &bodies::Environment,
-        do_return_perturb: bool,
-    ) -> (PerturbationDelta, Perturbation) {
-        unimplemented!();
+        Some(perturbations)
     }
+}
 
-    /// Calculate perturbations due to solar system objects.
-    ///
-    /// ### Parameters
-    /// * 'sim_obj' - The object basis for calculation
-    /// * 'env' - The Simulation environment
-    /// * 'do_return_peturb' - true if vector should be returned, false otherwise
-    ///
-    /// ### Return
-    ///     A struct of size two containing
-    ///         (Total perturbation delta, individual perturbation deltas or none)
-    ///
-    fn calc_planet_perturb(
-        sim_obj: &dyn bodies::Simobj,
-        env: &bodies::Environment,
-        do_return_perturb: bool,
-    ) -> (PerturbationDelta, Option<Vec<Perturbation>>) {
-        fn newton_gravitational_field(
-            distance_vector: &Array3d,
-            planet_idx: usize,
-            env: &bodies::Environment,
-        ) -> Array3d {
-            let l2_dist = l2_norm(distance_vector);
-            // Calculate unit vector for perturbation
-            let unit_vector = normalize(distance_vector, Some(l2_dist));
-            // Calculate force using Newton's law of universal gravitation
-            let planet_mass_kg = env
-                .get_solar_objects()
-                .get(planet_idx)
-                .expect("Expected in range environment access, invalid index provided.")
-                .get_solar_object()
-                .get_mass_kg();
-
-            unit_vector * (-G * (planet_mass_kg / l2_dist.powi(2)))
-        }
-
-        let mut perturbation_vec = Vec::<Array3d>::with_capacity(env.get_solar_objects().len());
-        // Calculate perturbations for each planet object in the environment
-        for planet_idx in 0..env.get_solar_objects().len() {
-            // Calculate L2 Norm from sim_obj to planet at index planet_index
-            let distance_vector = env
-                .distance_to(sim_obj, planet_idx)
-                .expect("Expected in range environment access, invalid index provided.");
-            // Calculate gravity field at position of sim object from planet body
-            let mut grav_accel =
-                newton_gravitational_field(&distance_vector, planet_idx, env);
-
-            let solar_obj = env
-                .get_solar_objects()
-                .get(planet_idx)
-                .expect("Expected in range environment access, invalid index provided");
-
-            // Special case to handle differential forces on sim object. This is done as
-            // simulation objects have positions relative to centric.
-            if let Solarobj::Sun { attr: _ } = solar_obj.get_solar_object() {
-                if planet_idx != 0 {
-                    // Get distance from centric to sun as vector
-                    let centric_sun_dist_vector = {
-                        let centric_obj_coords = env
-                            .get_solar_objects()
-                            .get(0)
-                            .expect("Expected in range environment access, invalid index provided")
-                            .get_coords();
-                        let current_obj_coords = solar_obj.get_coords();
-                        Array3d{
-                            x: current_obj_coords.xh - centric_obj_coords.xh,
-                            y: current_obj_coords.yh - centric_obj_coords.yh,
-                            z: current_obj_coords.zh - centric_obj_coords.zh
-                        }
-                    };
-                    // Calculate gravity field at position of centric
-                    let centric_grav = newton_gravitational_field(
-                        &centric_sun_dist_vector,
-                        planet_idx,
-                        env,
-                    );
-
-                    // Subtract centric from current
-                    grav_accel = grav_accel - centric_grav; // Grav accel on centric
-                }
-            }
-
-            perturbation_vec.push(grav_accel);
-        }
-
-        // Calculate the net acceleration on the sim object
-        let pertubation_sum: Array3d = perturbation_vec.iter().sum(); 
-
-        // Calculate final perturbation
-        let sum_perturb = {
-            PerturbationDelta {
-                id: sim_obj.get_id(),
-                sim_time: env.sim_time_s,
-                acceleration_x_mpss: pertubation_sum.x,
-                acceleration_y_mpss: pertubation_sum.y,
-                acceleration_z_mpss: pertubation_sum.z,
-            }
-        };
-
-        // If per object perturbation calculations are not needed return here
-        if !do_return_perturb {
-            return (sum_perturb, None);
+/// Checks if the object states are written out at a step, the step covering each multiple of the
+/// output interval or every step without one.
+///
+/// ### Arguments
+/// * 'sim_time_s' - Simulation time at the start of the step in seconds.
+/// * 'step_s' - Simulation time step in seconds.
+/// * 'output_interval' - Interval between written out states in seconds.
+///
+fn output_due(sim_time_s: f64, step_s: f64, output_interval: Option<f32>) -> bool {
+    match output_interval {
+        Some(interval) => {
+            let interval = interval as f64;
+            (sim_time_s / interval).ceil() * interval < sim_time_s + step_s
         }
+        None => true,
+    }
+}
 
-        let combined_iter = perturbation_vec.iter().zip(env.get_solar_objects());
-        let final_perturb_vec = combined_iter
-            .map(|(perturb, solar_obj)| {
-                Perturbation::SolarObject(
-                    solar_obj.get_solar_object().clone(),
-                    PerturbationDelta {
-                        id: sim_obj.get_id(),
-                        sim_time: env.sim_time_s,
-                        acceleration_x_mpss: perturb.x,
-                        acceleration_y_mpss: perturb.y,
-                        acceleration_z_mpss: perturb.z,
-                    },
-                )
-            })
-            .collect();
+/// Write out the states of all objects at the current simulation time, before the step moves
+/// them.
+///
+/// ### Arguments
+/// * 'env' - The Simulation environment.
+/// * 'sim_objects' - All simulation objects.
+/// * 'frame' - Frame the states are written out in.
+/// * 'sim_params' - The parameters of the run, selecting the written out states.
+/// * 'output_controller' - Controller object used to facilitate the output of the states.
+///
+fn write_out_all_states(
+    env: &bodies::Environment,
+    sim_objects: &[bodies::SimobjT],
+    frame: &output::OutputFrame,
+    sim_params: &SimulationParameters,
+    output_controller: &mut dyn output::SimulationOutput,
+) {
+    write_out_all_object_parameters(
+        env,
+        sim_objects,
+        frame,
+        output_controller,
+        sim_params.geodetic_output,
+    );
+
+    if sim_params.elements_output || sim_params.mean_elements_output {
+        write_out_all_orbital_elements(
+            env,
+            sim_objects,
+            frame,
+            sim_params.mean_elements_output,
+            output_controller,
+        );
+    }
 
-        (sum_perturb, Some(final_perturb_vec))
+    write_out_all_attitudes(env, sim_objects, frame, output_controller);
+    write_out_all_covariances(
+        env,
+        sim_objects,
+        frame,
+        sim_params.covariance_frame,
+        output_controller,
+    );
+
+    if let Some(reference_id) = sim_params.relative_to {
+        write_out_all_relative_states(env, sim_objects, reference_id, output_controller);
     }
 }
 
 /// Main entry point into the cpu_sim module, gathers all needed data for orbit modeling
-/// using Cowell's method.
+/// using Cowell's method. Fails if the solar system objects cannot be evaluated over the run.
 pub fn simulate(
     mut sim_bodies: Vec<bodies::SimobjT>,
     mut env: bodies::Environment,
+    force_models: Vec<Box<dyn force_models::ForceModel>>,
     mut output_controller: Box<dyn output::SimulationOutput>,
+    mut maneuvers: ManeuverSchedule,
+    mut insertions: InsertionSchedule,
     sim_params: SimulationParameters,
-) {
-    loop {
-        // Update solar objs
-        if env.sim_time_s > env.last_day_update_s + sim_params.sim_solar_step as f64 {
-            write_out_all_solar_objects(&env, output_controller.as_mut());
-            env.update();
+) -> Result<(), String> {
+    let output_frame = sim_params.output_frame.unwrap_or_default();
+    let end_s = sim_params
+        .end_time
+        .map(|end_time| time::Epoch::from_utc(&end_time).seconds_since(&env.start_epoch));
+
+    while end_s.is_none_or(|end_s| env.sim_time_s < end_s) {
+        let write_out_states = output_due(
+            env.sim_time_s,
+            sim_params.sim_time_step as f64,
+            sim_params.output_interval,
+        );
+        let frame = output::OutputFrame::new(output_frame, &env.epoch(env.sim_time_s));
+
+        // Update solar objs, refitting them once the last fit has been exhausted
+        if env.sim_time_s >= env.last_day_update_s + sim_params.sim_solar_step as f64 {
+            env.update()?;
+            write_out_all_solar_objects(&env, &frame, output_controller.as_mut());
+        } else {
+            env.interpolate_solar_objs();
         }
 
+        // The states are written out at the start of the step they are labelled with
+        if write_out_states {
+            write_out_all_states(
+                &env,
+                &sim_bodies,
+                &frame,
+                &sim_params,
+                output_controller.as_mut(),
+            );
+        }
+
+        // Insert the objects due within the step, they only move from their insertion onwards
+        let step_end_s = env.sim_time_s + sim_params.sim_time_step as f64;
+        let first_inserted = sim_bodies.len();
+        let mut insertion_steps_s = Vec::new();
+        for (sim_obj, insert_s) in insertions.take_due(&env, step_end_s) {
+            insertion_steps_s.push(step_end_s - insert_s);
+            sim_bodies.push(sim_obj);
+        }
+
+        // Apply the impulsive maneuvers falling within the step
+        maneuvers.apply_due(&mut sim_bodies, step_end_s);
+
+        // Snapshot the object positions for mutual gravity before any of them are moved
+        env.update_mutual_gravity(&sim_bodies);
+
         // Calculate and apply perturbations for every object
         // TODO parallelize this
-        for sim_obj in sim_bodies.iter_mut() {
+        for (idx, sim_obj) in sim_bodies.iter_mut().enumerate() {
+            let step_s = idx
+                .checked_sub(first_inserted)
+                .map_or(sim_params.sim_time_step as f64, |inserted| {
+                    insertion_steps_s[inserted]
+                });
+
+            // Covariances are linearized about the state at the start of the step
+            let position = *sim_obj.get_ref_coords();
+            if let Some(covariance) = sim_obj.covariance_mut() {
+                covariance.propagate(&position, env.central_gravitational_parameter(), step_s);
+            }
+
             if let Some(perturb) = apply_perturbations(
                 sim_obj.as_mut(),
                 &env,
-                sim_params.sim_time_step as f64,
-                true,
+                &force_models,
+                step_s,
+                write_out_states,
             ) {
-                write_out_all_perturbations(perturb, output_controller.as_mut());
+                write_out_all_perturbations(
+                    perturb,
+                    &frame,
+                    sim_params.perturbation_frame,
+                    output_controller.as_mut(),
+                );
             }
-        }
 
-        write_out_all_object_parameters(&env, &sim_bodies, output_controller.as_mut());
+            // No torques are modelled, the body keeps rotating at its angular velocity
+            if let Some(attitude) = sim_obj.attitude_mut() {
+                attitude.propagate(step_s);
+            }
+        }
 
         // Move forward simulation by step
         env.sim_time_s += sim_params.sim_time_step as f64;
     }
+
+    // Write out the states reached at the end of the simulation
+    if output_due(
+        env.sim_time_s,
+        sim_params.sim_time_step as f64,
+        sim_params.output_interval,
+    ) {
+        if env.sim_time_s >= env.last_day_update_s + sim_params.sim_solar_step as f64 {
+            env.update()?;
+        } else {
+            env.interpolate_solar_objs();
+        }
+        let frame = output::OutputFrame::new(output_frame, &env.epoch(env.sim_time_s));
+        write_out_all_states(
+            &env,
+            &sim_bodies,
+            &frame,
+            &sim_params,
+            output_controller.as_mut(),
+        );
+    }
+
+    Ok(())
 }
@@ -0,0 +1,354 @@
+//! Initial states of the simulation objects as provided by the input file.
+//!
+//! States are converted into the simulation frame, centered on the central body with the axes of
+//! the J2000 ecliptic, once the environment is set up at the start epoch, or at the insertion
+//! epoch for objects inserted during the run. Inertial states and orbital elements are rotated
+//! from the frame declared by the input file, element sets of catalogs are propagated to the epoch
+//! the object enters the simulation.
+
+use crate::bodies;
+use crate::elements::{EquinoctialElements, KeplerianElements};
+use crate::time;
+use force_models;
+use frames;
+use geodesy;
+use serde::Deserialize;
+use tle;
+use types::Array3d;
+
+/// Initial state of an object, selected by the 'type' field.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum InitialState {
+    /// Position and velocity relative to the central body in the inertial frame of the input.
+    Inertial { coords: Array3d, velocity: Array3d },
+    /// Position and velocity in the Earth fixed ITRF, in meters and m/s.
+    Ecef {
+        position: Array3d,
+        velocity: Array3d,
+    },
+    /// Position on the WGS-84 ellipsoid with the velocity relative to the rotating Earth given
+    /// as speed, azimuth clockwise from north and flight path angle above the local horizon.
+    Geodetic {
+        latitude_deg: f64,
+        longitude_deg: f64,
+        altitude_m: f64,
+        speed_mps: f64,
+        azimuth_deg: f64,
+        flight_path_angle_deg: f64,
+    },
+    /// Osculating classical elements about a solar system object, the central body if none is
+    /// given, referred to the axes of the inertial frame of the input. Takes exactly one of the
+    /// true and mean anomaly.
+    Keplerian {
+        #[serde(default)]
+        central_body: Option<bodies::CentralBody>,
+        semi_major_axis_m: f64, // Negative for hyperbolic orbits
+        eccentricity: f64,
+        inclination_deg: f64,
+        raan_deg: f64,
+        arg_periapsis_deg: f64,
+        #[serde(default)]
+        true_anomaly_deg: Option<f64>,
+        #[serde(default)]
+        mean_anomaly_deg: Option<f64>,
+    },
+    /// Osculating equinoctial elements of an elliptic orbit about a solar system object, the
+    /// central body if none is given, referred to the axes of the inertial frame of the input.
+    Equinoctial {
+        #[serde(default)]
+        central_body: Option<bodies::CentralBody>,
+        semi_major_axis_m: f64,
+        h: f64, // e sin(arg_periapsis + raan)
+        k: f64, // e cos(arg_periapsis + raan)
+        p: f64, // tan(i / 2) sin(raan)
+        q: f64, // tan(i / 2) cos(raan)
+        mean_longitude_deg: f64,
+    },
+    /// Mean elements of a two-line element set in the TEME frame, read from a catalog.
+    #[serde(skip)]
+    Tle(tle::TwoLineElements),
+    /// Position and velocity in an inertial frame at an epoch of their own, read from an orbit
+    /// parameter message. The state is moved to the start epoch on its two-body orbit.
+    #[serde(skip)]
+    Dated {
+        epoch: time::Epoch,
+        frame: frames::Frame,
+        coords: Array3d,
+        velocity: Array3d,
+    },
+}
+
+impl InitialState {
+    /// Combines the legacy 'coords' and 'velocity' fields of an object with its 'state' field,
+    /// exactly one of them has to be provided.
+    pub fn from_fields(
+        coords: Option<Array3d>,
+        velocity: Option<Array3d>,
+        state: Option<InitialState>,
+    ) -> Result<Self, String> {
+        match (coords, velocity, state) {
+            (Some(coords), Some(velocity), None) => Ok(InitialState::Inertial { coords, velocity }),
+            (None, None, Some(state)) => Ok(state),
+            (_, _, Some(_)) => {
+                Err("Objects take either 'state' or 'coords' and 'velocity'.".to_string())
+            }
+            _ => Err("Objects require 'coords' and 'velocity' or a 'state'.".to_string()),
+        }
+    }
+
+    /// Name of the kind of state, as selected by the 'type' field of the input.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            InitialState::Inertial { .. } => "inertial",
+            InitialState::Ecef { .. } => "ecef",
+            InitialState::Geodetic { .. } => "geodetic",
+            InitialState::Keplerian { .. } => "keplerian",
+            InitialState::Equinoctial { .. } => "equinoctial",
+            InitialState::Tle(_) => "tle",
+            InitialState::Dated { .. } => "message",
+        }
+    }
+
+    /// Catalog number of the object given by an element set, none if unknown.
+    pub fn catalog_number(&self) -> Option<u32> {
+        match self {
+            InitialState::Tle(element_set) if element_set.catalog_number != 0 => {
+                Some(element_set.catalog_number)
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts the state into the simulation frame.
+    ///
+    /// ### Arguments
+    /// * 'frame' - The frame of inertial states.
+    /// * 'env' - The Simulation environment.
+    /// * 'sim_time_s' - Simulation time the object takes the state at, zero at the start epoch.
+    ///
+    /// ### Return
+    ///     The position and velocity in the simulation frame, or an error if the state cannot be
+    ///     expressed relative to the central body.
+    ///
+    pub fn to_simulation_frame(
+        &self,
+        frame: frames::Frame,
+        env: &bodies::Environment,
+        sim_time_s: f64,
+    ) -> Result<(Array3d, Array3d), String> {
+        let state_epoch = env.epoch(sim_time_s);
+        let (position, velocity) = match self {
+            InitialState::Inertial { coords, velocity } => {
+                let simulation_from_frame = frame.simulation_from_frame(&state_epoch);
+                return Ok((simulation_from_frame * coords, simulation_from_frame * velocity));
+            }
+            InitialState::Dated {
+                epoch,
+                frame,
+                coords,
+                velocity,
+            } => {
+                let simulation_from_frame = frame.simulation_from_frame(epoch);
+                let mu = env.central_gravitational_parameter();
+                let position = simulation_from_frame * coords;
+                let velocity = simulation_from_frame * velocity;
+                let time_of_flight_s = state_epoch.seconds_since(epoch);
+                if time_of_flight_s == 0.0 {
+                    return Ok((position, velocity));
+                }
+
+                return Ok(KeplerianElements::from_state(&position, &velocity, mu)
+                    .propagate(mu, time_of_flight_s)
+                    .to_state(mu));
+            }
+            InitialState::Keplerian {
+                central_body,
+                semi_major_axis_m,
+                eccentricity,
+                inclination_deg,
+                raan_deg,
+                arg_periapsis_deg,
+                true_anomaly_deg,
+                mean_anomaly_deg,
+            } => {
+                validate_conic(*semi_major_axis_m, *eccentricity)?;
+                let true_anomaly_rad = match (true_anomaly_deg, mean_anomaly_deg) {
+                    (Some(true_anomaly_deg), None) => true_anomaly_deg.to_radians(),
+                    (None, Some(mean_anomaly_deg)) => KeplerianElements::true_anomaly_from_mean(
+                        *eccentricity,
+                        mean_anomaly_deg.to_radians(),
+                    ),
+                    _ => {
+                        return Err("Keplerian elements take either a 'true_anomaly_deg' or a \
+                                    'mean_anomaly_deg'."
+                            .to_string())
+                    }
+                };
+                if 1.0 + eccentricity * true_anomaly_rad.cos() <= 0.0 {
+                    return Err(
+                        "The true anomaly lies beyond the asymptotes of the hyperbolic orbit."
+                            .to_string(),
+                    );
+                }
+
+                let elements = KeplerianElements {
+                    semi_major_axis_m: *semi_major_axis_m,
+                    eccentricity: *eccentricity,
+                    inclination_rad: inclination_deg.to_radians(),
+                    raan_rad: raan_deg.to_radians(),
+                    arg_periapsis_rad: arg_periapsis_deg.to_radians(),
+                    true_anomaly_rad,
+                };
+                return elements_to_simulation_frame(*central_body, frame, env, sim_time_s, |mu| {
+                    elements.to_state(mu)
+                });
+            }
+            InitialState::Equinoctial {
+                central_body,
+                semi_major_axis_m,
+                h,
+                k,
+                p,
+                q,
+                mean_longitude_deg,
+            } => {
+                let eccentricity = h.hypot(*k);
+                if eccentricity >= 1.0 {
+                    return Err("Equinoctial elements require an elliptic orbit.".to_string());
+                }
+                validate_conic(*semi_major_axis_m, eccentricity)?;
+
+                let elements = EquinoctialElements {
+                    semi_major_axis_m: *semi_major_axis_m,
+                    h: *h,
+                    k: *k,
+                    p: *p,
+                    q: *q,
+                    mean_longitude_rad: mean_longitude_deg.to_radians(),
+                };
+                return elements_to_simulation_frame(*central_body, frame, env, sim_time_s, |mu| {
+                    elements.to_state(mu)
+                });
+            }
+            InitialState::Tle(element_set) => {
+                return tle_to_simulation_frame(element_set, env, sim_time_s)
+                    .map_err(|err| format!("{}: {}", element_set.designation(), err));
+            }
+            InitialState::Ecef { position, velocity } => (*position, *velocity),
+            InitialState::Geodetic {
+                latitude_deg,
+                longitude_deg,
+                altitude_m,
+                speed_mps,
+                azimuth_deg,
+                flight_path_angle_deg,
+            } => {
+                let geodetic = geodesy::Geodetic {
+                    latitude_rad: latitude_deg.to_radians(),
+                    longitude_rad: longitude_deg.to_radians(),
+                    altitude_m: *altitude_m,
+                };
+                let (sin_az, cos_az) = azimuth_deg.to_radians().sin_cos();
+                let (sin_fpa, cos_fpa) = flight_path_angle_deg.to_radians().sin_cos();
+                let velocity_enu = Array3d {
+                    x: speed_mps * cos_fpa * sin_az,
+                    y: speed_mps * cos_fpa * cos_az,
+                    z: speed_mps * sin_fpa,
+                };
+
+                (
+                    geodesy::geodetic_to_ecef(&geodetic),
+                    geodesy::ecef_from_enu(&geodetic) * velocity_enu,
+                )
+            }
+        };
+
+        if !env.central_is_earth() {
+            return Err("Earth fixed states require the Earth as central body.".to_string());
+        }
+
+        let (position, velocity) = env
+            .earth_orientation_at(sim_time_s)
+            .gcrf_from_itrf_state(&position, &velocity);
+        let ecliptic_from_equatorial = frames::ecliptic_from_equatorial();
+
+        Ok((
+            ecliptic_from_equatorial * position,
+            ecliptic_from_equatorial * velocity,
+        ))
+    }
+}
+
+/// Checks that the semi-major axis and eccentricity describe an elliptic or hyperbolic orbit.
+fn validate_conic(semi_major_axis_m: f64, eccentricity: f64) -> Result<(), String> {
+    if eccentricity < 0.0 {
+        return Err("The eccentricity can not be negative.".to_string());
+    }
+    if eccentricity == 1.0 {
+        return Err("Parabolic orbits have to be given as a cartesian state.".to_string());
+    }
+    if eccentricity < 1.0 && semi_major_axis_m <= 0.0 {
+        return Err("Elliptic orbits require a positive semi-major axis.".to_string());
+    }
+    if eccentricity > 1.0 && semi_major_axis_m >= 0.0 {
+        return Err("Hyperbolic orbits require a negative semi-major axis.".to_string());
+    }
+
+    Ok(())
+}
+
+/// Converts the state of elements about a solar system object, referred to the axes of the
+/// inertial frame of the input, into the simulation frame.
+///
+/// ### Arguments
+/// * 'body' - The body the elements are relative to, the central body if none.
+/// * 'frame' - The frame of inertial states.
+/// * 'env' - The Simulation environment.
+/// * 'sim_time_s' - Simulation time the object takes the state at.
+/// * 'to_state' - Calculates the state relative to the body from its gravitational parameter.
+///
+fn elements_to_simulation_frame(
+    body: Option<bodies::CentralBody>,
+    frame: frames::Frame,
+    env: &bodies::Environment,
+    sim_time_s: f64,
+    to_state: impl FnOnce(f64) -> (Array3d, Array3d),
+) -> Result<(Array3d, Array3d), String> {
+    let index = match body {
+        Some(body) => env
+            .solar_object_index(body)
+            .ok_or_else(|| format!("{:?} is not a simulated solar system object.", body))?,
+        None => 0,
+    };
+    let mu = force_models::G * env.get_solar_objects()[index].get_solar_object().get_mass_kg();
+    let (position, velocity) = to_state(mu);
+    let (body_position, body_velocity) = env
+        .position_from_central(index, sim_time_s)
+        .zip(env.velocity_from_central(index, sim_time_s))
+        .ok_or("The solar system objects are not set up.")?;
+    let simulation_from_frame = frame.simulation_from_frame(&env.epoch(sim_time_s));
+
+    Ok((
+        simulation_from_frame * position + body_position,
+        simulation_from_frame * velocity + body_velocity,
+    ))
+}
+
+/// Propagates an element set to the epoch of a simulation time and rotates its state into the
+/// simulation frame.
+fn tle_to_simulation_frame(
+    element_set: &tle::TwoLineElements,
+    env: &bodies::Environment,
+    sim_time_s: f64,
+) -> Result<(Array3d, Array3d), String> {
+    if !env.central_is_earth() {
+        return Err("Element sets require the Earth as central body.".to_string());
+    }
+
+    let (position, velocity) = element_set.state_at(&env.epoch(sim_time_s))?;
+    let simulation_from_teme =
+        frames::ecliptic_from_equatorial() * env.earth_orientation_at(sim_time_s).gcrf_from_teme();
+
+    Ok((simulation_from_teme * position, simulation_from_teme * velocity))
+}
@@ -0,0 +1,196 @@
+//! Time scales and Julian dates.
+//!
+//! Instants are held as TT seconds since J2000.0 (2000-01-01 12:00:00 TT), a uniform scale in
+//! which the simulation time advances. UTC is derived through the leap second table, UT1 from
+//! the UT1-UTC correction of the Earth orientation parameters and TDB from the periodic terms
+//! relative to TT.
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+
+// Julian date of J2000.0
+pub const J2000_JD: f64 = 2451545.0;
+pub const SECONDS_PER_DAY: f64 = 86400.0;
+// Offset between the Julian and modified Julian dates
+const MJD_JD_OFFSET: f64 = 2400000.5;
+// Modified Julian date of the Unix epoch
+const MJD_UNIX_EPOCH: i64 = 40587;
+// Unix timestamp of 2000-01-01 12:00:00 UTC
+const UNIX_J2000_S: i64 = 946728000;
+// Constant offset of TT from TAI
+const TT_MINUS_TAI_S: f64 = 32.184;
+
+// TAI-UTC in seconds, from the modified Julian date of the UTC day it takes effect on
+// (IERS Bulletin C). UTC before 1972 is treated as TAI - 10 s.
+const LEAP_SECONDS: [(i64, f64); 28] = [
+    (41317, 10.0), // 1972-01-01
+    (41499, 11.0), // 1972-07-01
+    (41683, 12.0), // 1973-01-01
+    (42048, 13.0), // 1974-01-01
+    (42413, 14.0), // 1975-01-01
+    (42778, 15.0), // 1976-01-01
+    (43144, 16.0), // 1977-01-01
+    (43509, 17.0), // 1978-01-01
+    (43874, 18.0), // 1979-01-01
+    (44239, 19.0), // 1980-01-01
+    (44786, 20.0), // 1981-07-01
+    (45151, 21.0), // 1982-07-01
+    (45516, 22.0), // 1983-07-01
+    (46247, 23.0), // 1985-07-01
+    (47161, 24.0), // 1988-01-01
+    (47892, 25.0), // 1990-01-01
+    (48257, 26.0), // 1991-01-01
+    (48804, 27.0), // 1992-07-01
+    (49169, 28.0), // 1993-07-01
+    (49534, 29.0), // 1994-07-01
+    (50083, 30.0), // 1996-01-01
+    (50630, 31.0), // 1997-07-01
+    (51179, 32.0), // 1999-01-01
+    (53736, 33.0), // 2006-01-01
+    (54832, 34.0), // 2009-01-01
+    (56109, 35.0), // 2012-07-01
+    (57204, 36.0), // 2015-07-01
+    (57754, 37.0), // 2017-01-01
+];
+
+/// TAI-UTC on a UTC day.
+///
+/// ### Argument
+/// * 'mjd_utc' - Modified Julian date of the UTC day.
+///
+/// ### Return
+///     The accumulated leap seconds in seconds.
+///
+pub fn tai_minus_utc(mjd_utc: i64) -> f64 {
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|(mjd, _)| mjd_utc >= *mjd)
+        .map_or(LEAP_SECONDS[0].1, |(_, offset)| *offset)
+}
+
+/// An instant in time, convertible between the supported time scales.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Epoch {
+    tt_s: f64, // TT seconds since J2000.0
+}
+
+impl Epoch {
+    /// Creates an epoch from a UTC datetime, including datetimes within a leap second.
+    pub fn from_utc(datetime: &DateTime<Utc>) -> Self {
+        let unix_s = datetime.timestamp();
+        // Leap seconds are represented by chrono as nanoseconds past the last regular second
+        let subsec_s = datetime.timestamp_subsec_nanos() as f64 * 1e-9;
+        let offset = tai_minus_utc(unix_s.div_euclid(86400) + MJD_UNIX_EPOCH);
+
+        Epoch {
+            tt_s: (unix_s - UNIX_J2000_S) as f64 + subsec_s + offset + TT_MINUS_TAI_S,
+        }
+    }
+
+    /// Creates an epoch from a Julian date in TT.
+    pub fn from_jd_tt(jd_tt: f64) -> Self {
+        Epoch {
+            tt_s: (jd_tt - J2000_JD) * SECONDS_PER_DAY,
+        }
+    }
+
+    /// The epoch a number of SI seconds later.
+    pub fn offset(&self, seconds: f64) -> Self {
+        Epoch {
+            tt_s: self.tt_s + seconds,
+        }
+    }
+
+    /// SI seconds elapsed since another epoch.
+    pub fn seconds_since(&self, other: &Epoch) -> f64 {
+        self.tt_s - other.tt_s
+    }
+
+    /// TDB seconds since J2000.0, TT with the periodic terms of the Earth's orbit (Fairhead and
+    /// Bretagnon, truncated to 10 us).
+    pub fn tdb_seconds(&self) -> f64 {
+        let g = (357.53 + 0.98560028 * self.tt_s / SECONDS_PER_DAY).to_radians();
+
+        self.tt_s + 0.001657 * g.sin() + 0.000014 * (2f64 * g).sin()
+    }
+
+    /// Julian date in TT.
+    pub fn jd_tt(&self) -> f64 {
+        J2000_JD + self.tt_s / SECONDS_PER_DAY
+    }
+
+    /// Julian date in TDB.
+    pub fn jd_tdb(&self) -> f64 {
+        J2000_JD + self.tdb_seconds() / SECONDS_PER_DAY
+    }
+
+    /// UTC seconds since 2000-01-01 12:00:00 UTC counting every day as 86400 seconds, and
+    /// whether the epoch lies within a leap second. Within a leap second the seconds run past
+    /// the end of the day.
+    fn utc_day_seconds(&self) -> (f64, bool) {
+        let tai_s = self.tt_s - TT_MINUS_TAI_S;
+        // Start of the UTC day of a table entry, in UTC seconds since J2000
+        let day_start_s = |mjd: i64| ((mjd - MJD_UNIX_EPOCH) * 86400 - UNIX_J2000_S) as f64;
+
+        let mut offset = LEAP_SECONDS[0].1;
+        let mut next_day_start_s = None;
+        for (mjd, entry_offset) in LEAP_SECONDS.iter() {
+            if tai_s >= day_start_s(*mjd) + entry_offset {
+                offset = *entry_offset;
+            } else {
+                next_day_start_s = Some(day_start_s(*mjd));
+                break;
+            }
+        }
+
+        let utc_s = tai_s - offset;
+        (utc_s, next_day_start_s.is_some_and(|start_s| utc_s >= start_s))
+    }
+
+    /// Julian date in UTC, held at the start of the following day during a leap second.
+    pub fn jd_utc(&self) -> f64 {
+        let (utc_s, in_leap_second) = self.utc_day_seconds();
+        let utc_s = if in_leap_second { utc_s.floor() } else { utc_s };
+
+        J2000_JD + utc_s / SECONDS_PER_DAY
+    }
+
+    /// Julian date in UT1.
+    ///
+    /// ### Argument
+    /// * 'ut1_minus_utc_s' - UT1-UTC from the Earth orientation parameters in seconds.
+    ///
+    pub fn jd_ut1(&self, ut1_minus_utc_s: f64) -> f64 {
+        self.jd_utc() + ut1_minus_utc_s / SECONDS_PER_DAY
+    }
+
+    /// The UTC datetime of the epoch, seconds within a leap second are reported as 23:59:60.
+    pub fn to_utc(self) -> DateTime<Utc> {
+        let (utc_s, in_leap_second) = self.utc_day_seconds();
+        // Rounded to microseconds, the resolution of TT seconds held in a f64
+        let utc_us = (utc_s * 1e6).round();
+        let mut seconds = (utc_us / 1e6).floor();
+        let mut nanos = (utc_us - seconds * 1e6) as u32 * 1000;
+
+        // chrono represents a leap second as nanoseconds past the last second of the day
+        if in_leap_second {
+            seconds -= 1f64;
+            nanos += 1_000_000_000;
+        }
+
+        DateTime::from_utc(
+            NaiveDateTime::from_timestamp(seconds as i64 + UNIX_J2000_S, nanos),
+            Utc,
+        )
+    }
+
+    /// The UTC datetime of the epoch formatted as ISO 8601.
+    pub fn to_utc_string(self) -> String {
+        self.to_utc().to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
+    /// Modified Julian date in UTC.
+    pub fn mjd_utc(&self) -> f64 {
+        self.jd_utc() - MJD_JD_OFFSET
+    }
+}
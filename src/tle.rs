@@ -0,0 +1,705 @@
+//! Reader of two-line and three-line element set catalogs.
+//!
+//! Element sets carry SGP4 mean elements in the TEME frame at their own epoch. The objects of a
+//! catalog are moved to the start epoch of the simulation with the near Earth SGP4 theory of
+//! Hoots and Roehrich in the revision of Vallado et al. (AIAA 2006-6753), with the WGS-72
+//! constants the element sets are fitted with and drag through the B* term. As in SGP4 the
+//! derivatives of the mean motion are not used. The deep space lunisolar and resonance terms of
+//! orbits with periods of 225 minutes and more are not modelled, the states of these objects
+//! drift from a full SDP4 propagation with the time between the element set and start epochs.
+
+use crate::time;
+use chrono::{TimeZone, Utc};
+use std::f64::consts::PI;
+use std::ops::RangeInclusive;
+use types::Array3d;
+
+const LINE_LENGTH: usize = 69;
+const SECONDS_PER_DAY: f64 = 86400.0;
+// Letters of the leading digit of Alpha-5 catalog numbers, starting at 10, I and O are skipped
+const ALPHA5_LETTERS: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+// WGS-72 constants of SGP4: equatorial radius in km, gravitational parameter in km^3/s^2 and the
+// zonal harmonics
+const WGS72_RADIUS_KM: f64 = 6378.135;
+const WGS72_MU: f64 = 398600.8;
+const WGS72_J2: f64 = 0.001082616;
+const WGS72_J3: f64 = -0.00000253881;
+const WGS72_J4: f64 = -0.00000165597;
+// Periods from which SGP4 switches to the deep space theory in minutes
+const DEEP_SPACE_PERIOD_MIN: f64 = 225.0;
+// Lower bound of 1 + cos(i) keeping the long period terms finite for retrograde equatorial orbits
+const RETROGRADE_TOLERANCE: f64 = 1.5e-12;
+const KEPLER_TOLERANCE: f64 = 1e-12;
+const KEPLER_MAX_ITERATIONS: usize = 10;
+
+/// Mean elements of a single two-line element set.
+#[derive(Clone, Debug)]
+pub struct TwoLineElements {
+    pub name: Option<String>, // Name line of a three-line element set
+    pub catalog_number: u32,  // NORAD catalog number, decoded from Alpha-5
+    pub epoch: time::Epoch,
+    pub bstar: f64, // Drag term of SGP4 in 1 / Earth radii
+    pub inclination_rad: f64,
+    pub raan_rad: f64,
+    pub eccentricity: f64,
+    pub arg_perigee_rad: f64,
+    pub mean_anomaly_rad: f64,
+    pub mean_motion: f64, // Kozai mean motion in rad/s
+}
+
+impl TwoLineElements {
+    /// Parses the two lines of an element set.
+    ///
+    /// ### Arguments
+    /// * 'name' - Name of the object from the title line of a three-line element set.
+    /// * 'first' - Line number and text of the first line.
+    /// * 'second' - Line number and text of the second line.
+    ///
+    /// ### Return
+    ///     The element set, or an error naming the offending line.
+    ///
+    pub fn parse(
+        name: Option<String>,
+        first: (usize, &str),
+        second: (usize, &str),
+    ) -> Result<Self, String> {
+        let line1 = TleLine::new(first, '1')?;
+        let line2 = TleLine::new(second, '2')?;
+
+        let catalog_number = line1.catalog_number()?;
+        if line2.catalog_number()? != catalog_number {
+            return Err(format!(
+                "Line {}: Catalog number does not match the first line of the element set.",
+                line2.number
+            ));
+        }
+
+        let epoch_year = line1.field(19..=20).parse::<i32>();
+        let epoch_day = line1.float(21..=32)?;
+        let epoch_year = match epoch_year {
+            // Two digit years from 57 belong to the 20th century, the start of the catalog
+            Ok(year) if year >= 57 => 1900 + year,
+            Ok(year) => 2000 + year,
+            Err(_) => return Err(line1.error("Invalid epoch year.")),
+        };
+        if !(1.0..367.0).contains(&epoch_day) {
+            return Err(line1.error("Epoch day out of range."));
+        }
+        let epoch = time::Epoch::from_utc(&Utc.ymd(epoch_year, 1, 1).and_hms(0, 0, 0))
+            .offset((epoch_day - 1.0) * SECONDS_PER_DAY);
+
+        let eccentricity = format!("0.{}", line2.field(27..=33))
+            .parse::<f64>()
+            .map_err(|_| line2.error("Invalid eccentricity."))?;
+        let mean_motion = line2.float(53..=63)? * 2.0 * PI / SECONDS_PER_DAY;
+        if mean_motion <= 0.0 {
+            return Err(line2.error("Mean motion must be positive."));
+        }
+
+        Ok(TwoLineElements {
+            name,
+            catalog_number,
+            epoch,
+            bstar: line1.exponential(54..=61)?,
+            inclination_rad: line2.float(9..=16)?.to_radians(),
+            raan_rad: line2.float(18..=25)?.to_radians(),
+            eccentricity,
+            arg_perigee_rad: line2.float(35..=42)?.to_radians(),
+            mean_anomaly_rad: line2.float(44..=51)?.to_radians(),
+            mean_motion,
+        })
+    }
+
+    /// Position and velocity of the element set in the TEME frame at an epoch.
+    ///
+    /// ### Argument
+    /// * 'epoch' - Epoch of the state.
+    ///
+    /// ### Return
+    ///     The position in meters and velocity in m/s, or an error if the orbit decayed before
+    ///     the epoch.
+    ///
+    pub fn state_at(&self, epoch: &time::Epoch) -> Result<(Array3d, Array3d), String> {
+        Sgp4::new(self).state_at(epoch.seconds_since(&self.epoch) / 60.0)
+    }
+
+    /// Name of the object for messages, the catalog number if the element set has no name line.
+    pub fn designation(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} ({})", name, self.catalog_number),
+            None => self.catalog_number.to_string(),
+        }
+    }
+}
+
+/// Parses the element sets of a TLE or 3LE file. Three-line element sets are recognized by a
+/// title line in front of the first line, with or without the leading '0 ' of the 3LE format.
+///
+/// ### Argument
+/// * 'text' - Contents of the file.
+///
+/// ### Return
+///     The element sets in the order of the file, or an error naming the offending line.
+///
+pub fn parse_element_sets(text: &str) -> Result<Vec<TwoLineElements>, String> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+
+    let mut element_sets = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let name = if lines[idx].1.starts_with("1 ") {
+            None
+        } else {
+            let title = lines[idx].1;
+            idx += 1;
+            Some(title.strip_prefix("0 ").unwrap_or(title).trim().to_string())
+        };
+
+        match (lines.get(idx), lines.get(idx + 1)) {
+            (Some(first), Some(second)) => {
+                element_sets.push(TwoLineElements::parse(name, *first, *second)?);
+            }
+            _ => {
+                return Err(format!(
+                    "Line {}: Incomplete element set at the end of the file.",
+                    lines[lines.len() - 1].0
+                ))
+            }
+        }
+        idx += 2;
+    }
+
+    Ok(element_sets)
+}
+
+/// Line of an element set with its line number in the file.
+struct TleLine<'a> {
+    number: usize,
+    text: &'a str,
+}
+
+impl<'a> TleLine<'a> {
+    /// Checks the line number, length and checksum of a line.
+    fn new((number, text): (usize, &'a str), line_number: char) -> Result<Self, String> {
+        let line = TleLine { number, text };
+        if !text.starts_with(line_number) {
+            return Err(line.error(&format!("Expected line {} of an element set.", line_number)));
+        }
+        if text.len() != LINE_LENGTH || !text.is_ascii() {
+            return Err(line.error(&format!(
+                "Lines of element sets have {} characters.",
+                LINE_LENGTH
+            )));
+        }
+
+        let checksum = text[..LINE_LENGTH - 1]
+            .chars()
+            .map(|c| match c {
+                '-' => 1,
+                _ => c.to_digit(10).unwrap_or(0),
+            })
+            .sum::<u32>()
+            % 10;
+        if text[LINE_LENGTH - 1..].parse::<u32>() != Ok(checksum) {
+            return Err(line.error(&format!("Checksum mismatch, expected {}.", checksum)));
+        }
+
+        Ok(line)
+    }
+
+    /// Text of the columns of the line, numbered from 1 as in the format description.
+    fn field(&self, columns: RangeInclusive<usize>) -> &'a str {
+        self.text[columns.start() - 1..*columns.end()].trim()
+    }
+
+    fn float(&self, columns: RangeInclusive<usize>) -> Result<f64, String> {
+        let start = *columns.start();
+        self.field(columns)
+            .parse::<f64>()
+            .map_err(|_| self.error(&format!("Invalid number at column {}.", start)))
+    }
+
+    /// Number in the exponential notation of element sets with an implied leading decimal point,
+    /// " 28098-4" is 0.28098e-4.
+    fn exponential(&self, columns: RangeInclusive<usize>) -> Result<f64, String> {
+        let start = *columns.start();
+        let field = self.field(columns);
+        let split = field.len().saturating_sub(2);
+        let (mantissa, exponent) = (&field[..split], &field[split..]);
+        let (sign, digits) = match mantissa.strip_prefix('-') {
+            Some(digits) => (-1.0, digits),
+            None => (1.0, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+        };
+        let valid = !digits.is_empty()
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && exponent.parse::<i32>().is_ok();
+
+        match format!("0.{}e{}", digits, exponent).parse::<f64>() {
+            Ok(value) if valid => Ok(sign * value),
+            _ => Err(self.error(&format!("Invalid number at column {}.", start))),
+        }
+    }
+
+    /// Catalog number in columns 3-7, with the leading letter of the Alpha-5 scheme for numbers
+    /// above 99999.
+    fn catalog_number(&self) -> Result<u32, String> {
+        let field = self.field(3..=7);
+        let mut chars = field.chars();
+        let leading = match chars.next().and_then(|c| ALPHA5_LETTERS.find(c)) {
+            Some(letter) => (letter as u32 + 10) * 10000,
+            None => {
+                return field
+                    .parse::<u32>()
+                    .map_err(|_| self.error("Invalid catalog number."))
+            }
+        };
+
+        match chars.as_str().parse::<u32>() {
+            Ok(rest) if field.len() == 5 => Ok(leading + rest),
+            _ => Err(self.error("Invalid Alpha-5 catalog number.")),
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("Line {}: {}", self.number, message)
+    }
+}
+
+/// Near Earth SGP4 theory initialized for an element set, in the canonical units of Earth radii
+/// and minutes.
+struct Sgp4 {
+    bstar: f64,
+    eccentricity: f64,
+    inclination_rad: f64,
+    raan_rad: f64,
+    arg_perigee_rad: f64,
+    mean_anomaly_rad: f64,
+    mean_motion: f64, // Brouwer mean motion in rad/min
+    simplified: bool, // Drag without the higher order terms, for low perigees and deep space
+    xke: f64,         // Square root of the gravitational parameter
+    // Secular rates of the mean anomaly, argument of perigee and RAAN
+    mdot: f64,
+    argpdot: f64,
+    nodedot: f64,
+    // Coefficients of the drag and the long and short period terms, named as in SGP4
+    eta: f64,
+    cc1: f64,
+    cc4: f64,
+    cc5: f64,
+    d2: f64,
+    d3: f64,
+    d4: f64,
+    delmo: f64,
+    sinmao: f64,
+    omgcof: f64,
+    xmcof: f64,
+    nodecf: f64,
+    t2cof: f64,
+    t3cof: f64,
+    t4cof: f64,
+    t5cof: f64,
+    xlcof: f64,
+    aycof: f64,
+    con41: f64,
+    x1mth2: f64,
+    x7thm1: f64,
+}
+
+impl Sgp4 {
+    fn new(element_set: &TwoLineElements) -> Self {
+        let xke = 60.0 / (WGS72_RADIUS_KM.powi(3) / WGS72_MU).sqrt();
+        let j3oj2 = WGS72_J3 / WGS72_J2;
+        let ecco = element_set.eccentricity;
+        let (sinio, cosio) = element_set.inclination_rad.sin_cos();
+        let cosio2 = cosio.powi(2);
+        let omeosq = 1.0 - ecco.powi(2);
+        let rteosq = omeosq.sqrt();
+
+        // Brouwer mean motion and semi-major axis of the Kozai mean motion of the element set
+        let kozai_mean_motion = element_set.mean_motion * 60.0;
+        let ak = (xke / kozai_mean_motion).powf(2.0 / 3.0);
+        let d1 = 0.75 * WGS72_J2 * (3.0 * cosio2 - 1.0) / (rteosq * omeosq);
+        let del = d1 / ak.powi(2);
+        let adel = ak * (1.0 - del.powi(2) - del * (1.0 / 3.0 + 134.0 * del.powi(2) / 81.0));
+        let no = kozai_mean_motion / (1.0 + d1 / adel.powi(2));
+        let ao = (xke / no).powf(2.0 / 3.0);
+        let po = ao * omeosq;
+        let con42 = 1.0 - 5.0 * cosio2;
+        let con41 = -con42 - 2.0 * cosio2;
+        let perigee_km = (ao * (1.0 - ecco) - 1.0) * WGS72_RADIUS_KM;
+        let deep_space = 2.0 * PI / no >= DEEP_SPACE_PERIOD_MIN;
+
+        // Density function of the atmosphere, adjusted for perigees below 156 km
+        let (sfour, qzms24) = if perigee_km < 156.0 {
+            let sfour_km = if perigee_km < 98.0 { 20.0 } else { perigee_km - 78.0 };
+            (
+                sfour_km / WGS72_RADIUS_KM + 1.0,
+                ((120.0 - sfour_km) / WGS72_RADIUS_KM).powi(4),
+            )
+        } else {
+            (
+                78.0 / WGS72_RADIUS_KM + 1.0,
+                ((120.0 - 78.0) / WGS72_RADIUS_KM).powi(4),
+            )
+        };
+
+        let pinvsq = 1.0 / po.powi(2);
+        let tsi = 1.0 / (ao - sfour);
+        let eta = ao * ecco * tsi;
+        let etasq = eta.powi(2);
+        let eeta = ecco * eta;
+        let psisq = (1.0 - etasq).abs();
+        let coef = qzms24 * tsi.powi(4);
+        let coef1 = coef / psisq.powf(3.5);
+        let cc2 = coef1
+            * no
+            * (ao * (1.0 + 1.5 * etasq + eeta * (4.0 + etasq))
+                + 0.375 * WGS72_J2 * tsi / psisq * con41 * (8.0 + 3.0 * etasq * (8.0 + etasq)));
+        let cc1 = element_set.bstar * cc2;
+        let cc3 = if ecco > 1e-4 {
+            -2.0 * coef * tsi * j3oj2 * no * sinio / ecco
+        } else {
+            0.0
+        };
+        let x1mth2 = 1.0 - cosio2;
+        let cc4 = 2.0
+            * no
+            * coef1
+            * ao
+            * omeosq
+            * (eta * (2.0 + 0.5 * etasq) + ecco * (0.5 + 2.0 * etasq)
+                - WGS72_J2 * tsi / (ao * psisq)
+                    * (-3.0 * con41 * (1.0 - 2.0 * eeta + etasq * (1.5 - 0.5 * eeta))
+                        + 0.75
+                            * x1mth2
+                            * (2.0 * etasq - eeta * (1.0 + etasq))
+                            * (2.0 * element_set.arg_perigee_rad).cos()));
+        let cc5 = 2.0 * coef1 * ao * omeosq * (1.0 + 2.75 * (etasq + eeta) + eeta * etasq);
+
+        let cosio4 = cosio2.powi(2);
+        let temp1 = 1.5 * WGS72_J2 * pinvsq * no;
+        let temp2 = 0.5 * temp1 * WGS72_J2 * pinvsq;
+        let temp3 = -0.46875 * WGS72_J4 * pinvsq.powi(2) * no;
+        let mdot = no
+            + 0.5 * temp1 * rteosq * con41
+            + 0.0625 * temp2 * rteosq * (13.0 - 78.0 * cosio2 + 137.0 * cosio4);
+        let argpdot = -0.5 * temp1 * con42
+            + 0.0625 * temp2 * (7.0 - 114.0 * cosio2 + 395.0 * cosio4)
+            + temp3 * (3.0 - 36.0 * cosio2 + 49.0 * cosio4);
+        let xhdot1 = -temp1 * cosio;
+        let nodedot = xhdot1
+            + (0.5 * temp2 * (4.0 - 19.0 * cosio2) + 2.0 * temp3 * (3.0 - 7.0 * cosio2)) * cosio;
+
+        // Higher order drag terms, dropped for perigees below 220 km
+        let simplified = deep_space || ao * (1.0 - ecco) < 220.0 / WGS72_RADIUS_KM + 1.0;
+        let (d2, d3, d4, t3cof, t4cof, t5cof) = if simplified {
+            (0.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+        } else {
+            let cc1sq = cc1.powi(2);
+            let d2 = 4.0 * ao * tsi * cc1sq;
+            let temp = d2 * tsi * cc1 / 3.0;
+            let d3 = (17.0 * ao + sfour) * temp;
+            let d4 = 0.5 * temp * ao * tsi * (221.0 * ao + 31.0 * sfour) * cc1;
+            (
+                d2,
+                d3,
+                d4,
+                d2 + 2.0 * cc1sq,
+                0.25 * (3.0 * d3 + cc1 * (12.0 * d2 + 10.0 * cc1sq)),
+                0.2 * (3.0 * d4
+                    + 12.0 * cc1 * d3
+                    + 6.0 * d2.powi(2)
+                    + 15.0 * cc1sq * (2.0 * d2 + cc1sq)),
+            )
+        };
+
+        Sgp4 {
+            bstar: element_set.bstar,
+            eccentricity: ecco,
+            inclination_rad: element_set.inclination_rad,
+            raan_rad: element_set.raan_rad,
+            arg_perigee_rad: element_set.arg_perigee_rad,
+            mean_anomaly_rad: element_set.mean_anomaly_rad,
+            mean_motion: no,
+            simplified,
+            xke,
+            mdot,
+            argpdot,
+            nodedot,
+            eta,
+            cc1,
+            cc4,
+            cc5,
+            d2,
+            d3,
+            d4,
+            delmo: (1.0 + eta * element_set.mean_anomaly_rad.cos()).powi(3),
+            sinmao: element_set.mean_anomaly_rad.sin(),
+            omgcof: element_set.bstar * cc3 * element_set.arg_perigee_rad.cos(),
+            xmcof: if ecco > 1e-4 {
+                -2.0 / 3.0 * coef * element_set.bstar / eeta
+            } else {
+                0.0
+            },
+            nodecf: 3.5 * omeosq * xhdot1 * cc1,
+            t2cof: 1.5 * cc1,
+            t3cof,
+            t4cof,
+            t5cof,
+            xlcof: -0.25 * j3oj2 * sinio * (3.0 + 5.0 * cosio)
+                / (1.0 + cosio).max(RETROGRADE_TOLERANCE),
+            aycof: -0.5 * j3oj2 * sinio,
+            con41,
+            x1mth2,
+            x7thm1: 7.0 * cosio2 - 1.0,
+        }
+    }
+
+    /// Position in meters and velocity in m/s in the TEME frame at minutes since the epoch of the
+    /// element set.
+    fn state_at(&self, t: f64) -> Result<(Array3d, Array3d), String> {
+        // Secular gravity and drag
+        let xmdf = self.mean_anomaly_rad + self.mdot * t;
+        let argpdf = self.arg_perigee_rad + self.argpdot * t;
+        let nodedf = self.raan_rad + self.nodedot * t;
+        let t2 = t.powi(2);
+        let nodem = nodedf + self.nodecf * t2;
+        let mut argpm = argpdf;
+        let mut mm = xmdf;
+        let mut tempa = 1.0 - self.cc1 * t;
+        let mut tempe = self.bstar * self.cc4 * t;
+        let mut templ = self.t2cof * t2;
+        if !self.simplified {
+            let delomg = self.omgcof * t;
+            let delm = self.xmcof * ((1.0 + self.eta * xmdf.cos()).powi(3) - self.delmo);
+            mm = xmdf + delomg + delm;
+            argpm = argpdf - delomg - delm;
+            let (t3, t4) = (t2 * t, t2 * t2);
+            tempa -= self.d2 * t2 + self.d3 * t3 + self.d4 * t4;
+            tempe += self.bstar * self.cc5 * (mm.sin() - self.sinmao);
+            templ += self.t3cof * t3 + t4 * (self.t4cof + t * self.t5cof);
+        }
+
+        let am = (self.xke / self.mean_motion).powf(2.0 / 3.0) * tempa.powi(2);
+        let nm = self.xke / am.powf(1.5);
+        let em = self.eccentricity - tempe;
+        if !(-0.001..1.0).contains(&em) || tempa <= 0.0 {
+            return Err("Orbit of the element set decayed before the start epoch.".to_string());
+        }
+        let em = em.max(1e-6);
+        let mm = mm + self.mean_motion * templ;
+
+        // Long period terms of J3
+        let axnl = em * argpm.cos();
+        let temp = 1.0 / (am * (1.0 - em.powi(2)));
+        let aynl = em * argpm.sin() + temp * self.aycof;
+        let xl = mm + argpm + nodem + temp * self.xlcof * axnl;
+
+        // Kepler's equation for the eccentric longitude
+        let u = (xl - nodem).rem_euclid(2.0 * PI);
+        let mut eo1 = u;
+        for _ in 0..KEPLER_MAX_ITERATIONS {
+            let (sineo1, coseo1) = eo1.sin_cos();
+            let delta = ((u - aynl * coseo1 + axnl * sineo1 - eo1)
+                / (1.0 - coseo1 * axnl - sineo1 * aynl))
+                .clamp(-0.95, 0.95);
+            eo1 += delta;
+            if delta.abs() < KEPLER_TOLERANCE {
+                break;
+            }
+        }
+
+        // Short period terms of J2
+        let (sineo1, coseo1) = eo1.sin_cos();
+        let ecose = axnl * coseo1 + aynl * sineo1;
+        let esine = axnl * sineo1 - aynl * coseo1;
+        let el2 = axnl.powi(2) + aynl.powi(2);
+        let pl = am * (1.0 - el2);
+        if pl < 0.0 {
+            return Err("Orbit of the element set decayed before the start epoch.".to_string());
+        }
+        let rl = am * (1.0 - ecose);
+        let rdotl = am.sqrt() * esine / rl;
+        let rvdotl = pl.sqrt() / rl;
+        let betal = (1.0 - el2).sqrt();
+        let temp = esine / (1.0 + betal);
+        let sinu = am / rl * (sineo1 - aynl - axnl * temp);
+        let cosu = am / rl * (coseo1 - axnl + aynl * temp);
+        let su = sinu.atan2(cosu);
+        let sin2u = 2.0 * cosu * sinu;
+        let cos2u = 1.0 - 2.0 * sinu.powi(2);
+        let temp1 = 0.5 * WGS72_J2 / pl;
+        let temp2 = temp1 / pl;
+
+        let mrt = rl * (1.0 - 1.5 * temp2 * betal * self.con41) + 0.5 * temp1 * self.x1mth2 * cos2u;
+        if mrt < 1.0 {
+            return Err(
+                "Perigee of the element set is below the surface of the Earth.".to_string(),
+            );
+        }
+        let su = su - 0.25 * temp2 * self.x7thm1 * sin2u;
+        let cosip = self.inclination_rad.cos();
+        let xnode = nodem + 1.5 * temp2 * cosip * sin2u;
+        let xinc = self.inclination_rad + 1.5 * temp2 * cosip * self.inclination_rad.sin() * cos2u;
+        let mvt = rdotl - nm * temp1 * self.x1mth2 * sin2u / self.xke;
+        let rvdot = rvdotl + nm * temp1 * (self.x1mth2 * cos2u + 1.5 * self.con41) / self.xke;
+
+        // Unit vectors of the position and the direction of motion
+        let (sinsu, cossu) = su.sin_cos();
+        let (snod, cnod) = xnode.sin_cos();
+        let (sini, cosi) = xinc.sin_cos();
+        let (xmx, xmy) = (-snod * cosi, cnod * cosi);
+        let position_unit = Array3d {
+            x: xmx * sinsu + cnod * cossu,
+            y: xmy * sinsu + snod * cossu,
+            z: sini * sinsu,
+        };
+        let motion_unit = Array3d {
+            x: xmx * cossu - cnod * sinsu,
+            y: xmy * cossu - snod * sinsu,
+            z: sini * cossu,
+        };
+
+        let radius_m = WGS72_RADIUS_KM * 1000.0;
+        let speed_scale = radius_m * self.xke / 60.0;
+        Ok((
+            position_unit * (mrt * radius_m),
+            (position_unit * mvt + motion_unit * rvdot) * speed_scale,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test case 00005 of Vallado et al., the verification states are in km and km/s
+    const VANGUARD_LINE1: &str =
+        "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753";
+    const VANGUARD_LINE2: &str =
+        "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667";
+
+    /// Line with the checksum of its first 68 characters appended.
+    fn with_checksum(text: &str) -> String {
+        let checksum: u32 = text
+            .chars()
+            .map(|c| if c == '-' { 1 } else { c.to_digit(10).unwrap_or(0) })
+            .sum();
+        format!("{}{}", text, checksum % 10)
+    }
+
+    fn vanguard_with_year(year: &str) -> TwoLineElements {
+        let line1 = with_checksum(&format!(
+            "{}{}{}",
+            &VANGUARD_LINE1[..18],
+            year,
+            &VANGUARD_LINE1[20..68]
+        ));
+        TwoLineElements::parse(None, (1, &line1), (2, VANGUARD_LINE2)).unwrap()
+    }
+
+    fn assert_state_close(actual: (Array3d, Array3d), expected: ([f64; 3], [f64; 3])) {
+        let position = [actual.0.x, actual.0.y, actual.0.z];
+        let velocity = [actual.1.x, actual.1.y, actual.1.z];
+        for idx in 0..3 {
+            // Within a millimeter and a micrometer per second
+            assert!((position[idx] - expected.0[idx] * 1000.0).abs() < 1e-3, "{:?}", position);
+            assert!((velocity[idx] - expected.1[idx] * 1000.0).abs() < 1e-6, "{:?}", velocity);
+        }
+    }
+
+    #[test]
+    fn checksum() {
+        assert!(TleLine::new((1, VANGUARD_LINE1), '1').is_ok());
+        let corrupted = format!("{}2", &VANGUARD_LINE1[..68]);
+        let err = TleLine::new((3, &corrupted), '1').err().unwrap();
+        assert_eq!(err, "Line 3: Checksum mismatch, expected 3.");
+        assert!(TleLine::new((1, VANGUARD_LINE1), '2').is_err());
+        assert!(TleLine::new((1, &VANGUARD_LINE1[..60]), '1').is_err());
+    }
+
+    #[test]
+    fn alpha5_catalog_number() {
+        let catalog_number = |field: &str| {
+            let text = format!("1 {}U 58002B", field);
+            TleLine {
+                number: 1,
+                text: &text,
+            }
+            .catalog_number()
+        };
+
+        assert_eq!(catalog_number("00005"), Ok(5));
+        assert_eq!(catalog_number("99999"), Ok(99999));
+        assert_eq!(catalog_number("A0000"), Ok(100000));
+        assert_eq!(catalog_number("H9999"), Ok(179999));
+        // I is skipped
+        assert_eq!(catalog_number("J0000"), Ok(180000));
+        // O is skipped
+        assert_eq!(catalog_number("P0000"), Ok(230000));
+        assert_eq!(catalog_number("Z9999"), Ok(339999));
+        assert!(catalog_number("I0000").is_err());
+        assert!(catalog_number("O0000").is_err());
+        assert!(catalog_number("A00-1").is_err());
+    }
+
+    #[test]
+    fn epoch_year_pivot() {
+        let start_of_year =
+            |year: i32| time::Epoch::from_utc(&Utc.ymd(year, 1, 1).and_hms(0, 0, 0));
+        let day_s = 178.78495062 * SECONDS_PER_DAY;
+
+        for &(digits, year) in [("00", 2000), ("56", 2056), ("57", 1957), ("99", 1999)].iter() {
+            let element_set = vanguard_with_year(digits);
+            let offset_s = element_set.epoch.seconds_since(&start_of_year(year));
+            assert!((offset_s - day_s).abs() < 1e-3, "{} {}", digits, offset_s);
+        }
+    }
+
+    #[test]
+    fn exponential_fields() {
+        let element_set = vanguard_with_year("00");
+        assert!((element_set.bstar - 0.28098e-4).abs() < 1e-15);
+
+        let exponential = |field: &str| {
+            TleLine {
+                number: 1,
+                text: field,
+            }
+            .exponential(1..=field.len())
+        };
+        assert_eq!(exponential("-11606-4"), Ok(-0.11606e-4));
+        assert_eq!(exponential("+12345+1"), Ok(1.2345));
+        assert_eq!(exponential(" 00000-0"), Ok(0.0));
+        assert!(exponential(" 1a345-4").is_err());
+    }
+
+    #[test]
+    fn vanguard_verification_states() {
+        let element_set =
+            TwoLineElements::parse(None, (1, VANGUARD_LINE1), (2, VANGUARD_LINE2)).unwrap();
+        assert_eq!(element_set.catalog_number, 5);
+
+        assert_state_close(
+            element_set.state_at(&element_set.epoch).unwrap(),
+            (
+                [7022.46529266, -1400.08296755, 0.03995155],
+                [1.893841015, 6.405893759, 4.534807250],
+            ),
+        );
+        assert_state_close(
+            element_set
+                .state_at(&element_set.epoch.offset(360.0 * 60.0))
+                .unwrap(),
+            (
+                [-7154.03120202, -3783.17682504, -3536.19412294],
+                [4.741887409, -4.151817765, -2.093935425],
+            ),
+        );
+    }
+}
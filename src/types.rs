@@ -13,6 +13,14 @@ impl Array3d {
     pub fn dot(&self, other: &Array3d) -> f64 {
         (self.x * other.x) + (self.y * other.y) + (self.z * other.z)
     }
+
+    pub fn cross(&self, other: &Array3d) -> Array3d {
+        Array3d {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
 }
 
 impl<'a> Sum<&'a Self> for Array3d {
@@ -72,4 +80,71 @@ impl_op_ex_commutative!(* |a: &Array3d, b: &f64| -> Array3d {
         y: a.y * b,
         z: a.z * b,
     }
-});
\ No newline at end of file
+});
+/// 3x3 matrix, used for the rotations between reference frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix3d {
+    pub m: [[f64; 3]; 3], // Row major elements
+}
+
+impl Matrix3d {
+    pub fn identity() -> Self {
+        Matrix3d {
+            m: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 3]; 3];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = self.m[col][row];
+            }
+        }
+
+        Matrix3d { m }
+    }
+
+    /// Coordinate rotation about the x axis by an angle in radians.
+    pub fn rot_x(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Matrix3d {
+            m: [[1.0, 0.0, 0.0], [0.0, c, s], [0.0, -s, c]],
+        }
+    }
+
+    /// Coordinate rotation about the y axis by an angle in radians.
+    pub fn rot_y(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Matrix3d {
+            m: [[c, 0.0, -s], [0.0, 1.0, 0.0], [s, 0.0, c]],
+        }
+    }
+
+    /// Coordinate rotation about the z axis by an angle in radians.
+    pub fn rot_z(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Matrix3d {
+            m: [[c, s, 0.0], [-s, c, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+}
+
+impl_op_ex!(* |a: &Matrix3d, b: &Matrix3d| -> Matrix3d {
+    let mut m = [[0.0; 3]; 3];
+    for (row, values) in m.iter_mut().enumerate() {
+        for (col, value) in values.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a.m[row][k] * b.m[k][col]).sum();
+        }
+    }
+
+    Matrix3d { m }
+});
+
+impl_op_ex!(* |a: &Matrix3d, b: &Array3d| -> Array3d {
+    Array3d {
+        x: a.m[0][0] * b.x + a.m[0][1] * b.y + a.m[0][2] * b.z,
+        y: a.m[1][0] * b.x + a.m[1][1] * b.y + a.m[1][2] * b.z,
+        z: a.m[2][0] * b.x + a.m[2][1] * b.y + a.m[2][2] * b.z,
+    }
+});